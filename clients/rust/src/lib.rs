@@ -7,6 +7,9 @@ mod error;
 #[cfg(feature = "admin")]
 pub mod projects;
 pub mod runs;
+pub mod schedules;
+#[cfg(feature = "admin")]
+pub mod shards;
 pub mod triggers;
 
 pub use cronback_api_model::*;
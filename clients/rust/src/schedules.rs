@@ -0,0 +1,20 @@
+use cronback_api_model::SchedulePreview;
+use http::Method;
+
+use crate::client::RequestRunner;
+use crate::{Response, Result};
+
+/// Evaluate the next `count` occurrences of a schedule from its JSON
+/// definition without installing a trigger.
+pub async fn preview_from_json(
+    client: &impl RequestRunner,
+    schedule: serde_json::Value,
+    count: Option<u64>,
+) -> Result<Response<SchedulePreview>> {
+    let mut path = client.make_url("/v1/schedules/preview")?;
+    if let Some(count) = count {
+        path.query_pairs_mut()
+            .append_pair("count", &count.to_string());
+    }
+    client.run_with_body(Method::POST, path, schedule).await
+}
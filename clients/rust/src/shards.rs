@@ -0,0 +1,24 @@
+use cronback_api_model::admin::{MoveShardsRequest, MoveShardsResponse};
+use http::Method;
+
+use crate::client::RequestRunner;
+use crate::{Response, Result};
+
+/// Moves the triggers of `shards` from a scheduler cell to another
+pub async fn move_shards(
+    client: &impl RequestRunner,
+    shards: Vec<u64>,
+    from_cell: u64,
+    to_cell: u64,
+) -> Result<Response<MoveShardsResponse>> {
+    let path = "/v1/admin/shards/move";
+    let path = client.make_url(path)?;
+
+    let body = MoveShardsRequest {
+        shards,
+        from_cell,
+        to_cell,
+    };
+
+    client.run_with_body(Method::POST, path, body).await
+}
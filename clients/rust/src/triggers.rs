@@ -1,10 +1,17 @@
+use chrono::{DateTime, Utc};
 use cronback_api_model::{
+    BulkUpdateTriggers,
+    BulkUpdateTriggersResult,
     Paginated,
     Pagination,
+    PauseTrigger,
     Run,
     RunMode,
     RunTrigger,
+    SkipTrigger,
+    TriggerRevision,
     TriggersFilter,
+    UpdateTimepoints,
 };
 use http::Method;
 
@@ -60,6 +67,10 @@ pub async fn list(
             path.query_pairs_mut()
                 .append_pair("status", &status.to_string());
         }
+        for selector in filter.label {
+            path.query_pairs_mut()
+                .append_pair("label", &selector.to_string());
+        }
     }
 
     client.run(Method::GET, path).await
@@ -93,6 +104,69 @@ where
     client.run(Method::POST, path).await
 }
 
+/// Pause a `scheduled` trigger until `until`, after which it resumes on its
+/// own.
+pub async fn pause_until<T>(
+    client: &impl RequestRunner,
+    name: T,
+    until: DateTime<Utc>,
+) -> Result<Response<Trigger>>
+where
+    T: AsRef<str>,
+{
+    let path = format!("/v1/triggers/{}/pause", name.as_ref());
+    let path = client.make_url(&path)?;
+
+    let body = PauseTrigger { until: Some(until) };
+
+    client.run_with_body(Method::POST, path, body).await
+}
+
+/// Skip upcoming occurrences of a trigger without pausing it.
+pub async fn skip<T>(
+    client: &impl RequestRunner,
+    name: T,
+    skip: SkipTrigger,
+) -> Result<Response<Trigger>>
+where
+    T: AsRef<str>,
+{
+    let path = format!("/v1/triggers/{}/skip", name.as_ref());
+    let path = client.make_url(&path)?;
+
+    client.run_with_body(Method::POST, path, skip).await
+}
+
+/// Add timepoints to a trigger with a `timepoints` schedule.
+pub async fn add_timepoints<T>(
+    client: &impl RequestRunner,
+    name: T,
+    timepoints: UpdateTimepoints,
+) -> Result<Response<Trigger>>
+where
+    T: AsRef<str>,
+{
+    let path = format!("/v1/triggers/{}/timepoints", name.as_ref());
+    let path = client.make_url(&path)?;
+
+    client.run_with_body(Method::POST, path, timepoints).await
+}
+
+/// Remove upcoming timepoints from a trigger with a `timepoints` schedule.
+pub async fn remove_timepoints<T>(
+    client: &impl RequestRunner,
+    name: T,
+    timepoints: UpdateTimepoints,
+) -> Result<Response<Trigger>>
+where
+    T: AsRef<str>,
+{
+    let path = format!("/v1/triggers/{}/timepoints", name.as_ref());
+    let path = client.make_url(&path)?;
+
+    client.run_with_body(Method::DELETE, path, timepoints).await
+}
+
 /// Resume a `paused` trigger.
 pub async fn resume<T>(
     client: &impl RequestRunner,
@@ -107,6 +181,49 @@ where
     client.run(Method::POST, path).await
 }
 
+/// List the revisions of a trigger, newest first.
+pub async fn list_revisions<T>(
+    client: &impl RequestRunner,
+    pagination: Option<Pagination>,
+    name: T,
+) -> Result<Response<Paginated<TriggerRevision>>>
+where
+    T: AsRef<str>,
+{
+    let path = format!("/v1/triggers/{}/revisions", name.as_ref());
+    let mut path = client.make_url(&path)?;
+    if let Some(pagination) = pagination {
+        if let Some(cursor) = pagination.cursor {
+            path.query_pairs_mut().append_pair("cursor", &cursor);
+        }
+        if let Some(limit) = pagination.limit {
+            path.query_pairs_mut()
+                .append_pair("limit", &limit.to_string());
+        }
+    }
+
+    client.run(Method::GET, path).await
+}
+
+/// Update a trigger to the definition it had at a previous revision.
+pub async fn restore_revision<T>(
+    client: &impl RequestRunner,
+    name: T,
+    revision: u64,
+) -> Result<Response<Trigger>>
+where
+    T: AsRef<str>,
+{
+    let path = format!(
+        "/v1/triggers/{}/revisions/{}/restore",
+        name.as_ref(),
+        revision
+    );
+    let path = client.make_url(&path)?;
+
+    client.run(Method::POST, path).await
+}
+
 /// Run the trigger immediately
 pub async fn run<T>(
     client: &impl RequestRunner,
@@ -137,3 +254,14 @@ where
 
     client.run(Method::DELETE, path).await
 }
+
+/// Pause, resume, cancel or delete all the triggers matching the filter of
+/// the request.
+pub async fn bulk_update(
+    client: &impl RequestRunner,
+    request: BulkUpdateTriggers,
+) -> Result<Response<BulkUpdateTriggersResult>> {
+    let path = client.make_url("/v1/bulk/triggers")?;
+
+    client.run_with_body(Method::POST, path, request).await
+}
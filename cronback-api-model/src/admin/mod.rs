@@ -1,7 +1,9 @@
 mod api_keys;
 mod notifications;
 mod projects;
+mod shards;
 
 pub use api_keys::*;
 pub use notifications::*;
 pub use projects::*;
+pub use shards::*;
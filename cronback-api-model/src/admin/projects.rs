@@ -1,6 +1,58 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+#[cfg(feature = "validation")]
+use validator::Validate;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreateProjectResponse {
     pub id: String,
 }
+
+/// How long the cancelled and expired triggers of a project are kept before
+/// they get deleted.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[serde(deny_unknown_fields)]
+pub struct TriggerRetention {
+    /// Falls back to the server's default if unset.
+    #[cfg_attr(
+        feature = "validation",
+        validate(range(
+            min = 60,
+            message = "Retention must be at least 60 seconds"
+        ))
+    )]
+    pub retention_s: Option<u64>,
+}
+
+/// A secret that the webhook deliveries of a project are signed with. The
+/// secret itself is only returned when it's created.
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SigningSecret {
+    pub id: String,
+    pub secret: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Deliveries aren't signed with this secret after this time.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Creates a new signing secret for a project. Deliveries are signed with
+/// every active secret, so receivers can move to the new secret while the
+/// existing ones are still in use.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[serde(deny_unknown_fields)]
+pub struct CreateSigningSecret {
+    /// Expires the existing secrets after this many seconds, immediately if
+    /// zero. They are kept until deleted if unset.
+    #[cfg_attr(
+        feature = "validation",
+        validate(range(
+            max = 2592000,
+            message = "Existing secrets must expire within 30 days"
+        ))
+    )]
+    pub expire_existing_after_s: Option<u64>,
+}
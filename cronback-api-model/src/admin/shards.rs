@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "validation")]
+use validator::{Validate, ValidationError};
+
+#[cfg(feature = "validation")]
+use crate::validation_util::validation_error;
+
+/// Moves the triggers of a set of shards from one scheduler cell to another.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(
+    feature = "validation",
+    derive(Validate),
+    validate(schema(function = "validate_move_shards"))
+)]
+#[serde(deny_unknown_fields)]
+pub struct MoveShardsRequest {
+    #[cfg_attr(
+        feature = "validation",
+        validate(
+            length(min = 1, message = "at least one shard must be moved"),
+            custom = "validate_shards"
+        )
+    )]
+    pub shards: Vec<u64>,
+    pub from_cell: u64,
+    pub to_cell: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MoveShardsResponse {
+    pub shards: Vec<u64>,
+    pub from_cell: u64,
+    pub to_cell: u64,
+    /// How many triggers, regardless of their status, were moved.
+    pub moved_triggers: usize,
+}
+
+#[cfg(feature = "validation")]
+fn validate_shards(shards: &[u64]) -> Result<(), ValidationError> {
+    use lib::prelude::SHARD_COUNT;
+
+    if let Some(shard) = shards.iter().find(|s| **s >= SHARD_COUNT) {
+        return Err(validation_error(
+            "invalid_shard",
+            format!("Shard {shard} is out of range (0-{})", SHARD_COUNT - 1),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "validation")]
+fn validate_move_shards(
+    request: &MoveShardsRequest,
+) -> Result<(), ValidationError> {
+    if request.from_cell == request.to_cell {
+        return Err(validation_error(
+            "invalid_move",
+            "Shards must be moved to a different cell".to_owned(),
+        ));
+    }
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::time::Duration;
 
 use chrono::{DateTime, Utc};
@@ -37,6 +38,21 @@ pub struct WebhookAttemptDetails {
     #[serde_as(as = "DurationSecondsWithFrac")]
     pub response_latency_s: Duration,
     pub error_message: Option<String>,
+    /// The delay that the receiver asked for in the `Retry-After` header.
+    pub retry_after_s: Option<u64>,
+    /// Why the attempt was or wasn't retried, for failed attempts.
+    pub retry_reason: Option<String>,
+    /// The success criterion that the response didn't match.
+    pub failed_criterion: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub response_headers: HashMap<String, String>,
+    /// The beginning of the response body.
+    pub response_body: Option<String>,
+    /// Whether the response body is longer than `response_body`.
+    #[serde(default)]
+    pub response_body_truncated: bool,
+    /// The chain of errors that caused the request to fail.
+    pub error_cause: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
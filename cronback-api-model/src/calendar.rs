@@ -0,0 +1,182 @@
+use chrono::NaiveDate;
+#[cfg(feature = "dto")]
+use dto::{FromProto, IntoProto};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+#[cfg(feature = "validation")]
+use validator::{Validate, ValidationError};
+
+#[cfg(feature = "validation")]
+use crate::validate_timezone;
+#[cfg(feature = "validation")]
+use crate::validation_util::validation_error;
+
+/// A named set of dates that recurring schedules can skip by listing it in
+/// their `exclude_calendars` (e.g. public holidays).
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::calendars::Calendar")
+)]
+#[cfg_attr(feature = "server", serde(deny_unknown_fields))]
+pub struct Calendar {
+    // The name is taken from the URL path when the calendar is created.
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(
+            min = 2,
+            max = 64,
+            message = "name must be between 2 and 64 characters"
+        ))
+    )]
+    #[cfg_attr(feature = "dto", proto(required))]
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// The timezone in which the dates are interpreted.
+    #[cfg_attr(feature = "validation", validate(custom = "validate_timezone"))]
+    #[cfg_attr(feature = "server", serde(default = "default_timezone"))]
+    #[cfg_attr(feature = "dto", proto(required))]
+    pub timezone: Option<String>,
+    #[cfg_attr(
+        feature = "validation",
+        validate(
+            length(max = 1000, message = "must have no more than 1000 dates"),
+            custom = "validate_calendar_dates"
+        )
+    )]
+    #[serde(default)]
+    pub dates: Vec<CalendarDate>,
+}
+
+/// A single day, or an inclusive range of days.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum CalendarDate {
+    Date(NaiveDate),
+    Range { start: NaiveDate, end: NaiveDate },
+}
+
+impl CalendarDate {
+    pub fn start(&self) -> NaiveDate {
+        match self {
+            | Self::Date(date) => *date,
+            | Self::Range { start, .. } => *start,
+        }
+    }
+
+    pub fn end(&self) -> NaiveDate {
+        match self {
+            | Self::Date(date) => *date,
+            | Self::Range { end, .. } => *end,
+        }
+    }
+}
+
+#[cfg(feature = "dto")]
+impl From<CalendarDate> for proto::calendars::DateRange {
+    fn from(value: CalendarDate) -> Self {
+        Self {
+            start: value.start().to_string(),
+            end: value.end().to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "dto")]
+impl From<proto::calendars::DateRange> for CalendarDate {
+    fn from(value: proto::calendars::DateRange) -> Self {
+        // Dates are validated before they are stored.
+        let start: NaiveDate = value.start.parse().unwrap_or_default();
+        let end: NaiveDate = value.end.parse().unwrap_or_default();
+        if start == end {
+            Self::Date(start)
+        } else {
+            Self::Range { start, end }
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+fn default_timezone() -> Option<String> {
+    Some("Etc/UTC".to_string())
+}
+
+#[cfg(feature = "validation")]
+fn validate_calendar_dates(
+    dates: &Vec<CalendarDate>,
+) -> Result<(), ValidationError> {
+    for date in dates {
+        if date.end() < date.start() {
+            return Err(validation_error(
+                "invalid_date_range",
+                format!(
+                    "Date range end '{}' must not be before its start '{}'",
+                    date.end(),
+                    date.start()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "validation"))]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn validate_calendar() -> Result<()> {
+        let calendar = json!(
+            {
+                "dates": [
+                    "2023-12-25",
+                    { "start": "2023-12-29", "end": "2024-01-01" },
+                ]
+            }
+        );
+        let parsed: Calendar = serde_json::from_value(calendar)?;
+        parsed.validate()?;
+        assert_eq!(Some("Etc/UTC".to_owned()), parsed.timezone);
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2023, 12, 25).unwrap(),
+            parsed.dates[0].end()
+        );
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            parsed.dates[1].end()
+        );
+
+        // ranges can't be reversed
+        let calendar = json!(
+            {
+                "timezone": "Europe/London",
+                "dates": [{ "start": "2024-01-01", "end": "2023-12-29" }]
+            }
+        );
+        let parsed: Calendar = serde_json::from_value(calendar)?;
+        let maybe_validated = parsed.validate();
+        assert!(maybe_validated.is_err());
+        assert!(maybe_validated
+            .unwrap_err()
+            .to_string()
+            .starts_with("dates: Date range end"));
+
+        // invalid timezone
+        let calendar = json!(
+            {
+                "timezone": "Mars/Olympus_Mons",
+                "dates": ["2023-12-25"]
+            }
+        );
+        let parsed: Calendar = serde_json::from_value(calendar)?;
+        assert!(parsed.validate().is_err());
+
+        Ok(())
+    }
+}
@@ -1,22 +1,26 @@
 mod action;
 pub mod admin;
 mod attempt;
+mod calendar;
 mod ids;
 mod pagination;
 mod payload;
 mod run;
 mod schedule;
+mod schedule_preview;
 mod trigger;
 mod validation_util;
 mod webhook;
 
 pub use action::*;
 pub use attempt::*;
+pub use calendar::*;
 #[cfg(not(feature = "dto"))]
 pub use ids::*;
 pub use pagination::*;
 pub use payload::*;
 pub use run::*;
 pub use schedule::*;
+pub use schedule_preview::*;
 pub use trigger::*;
 pub use webhook::*;
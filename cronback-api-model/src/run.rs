@@ -45,6 +45,7 @@ pub enum RunStatus {
     Attempting,
     Succeeded,
     Failed,
+    Skipped,
 }
 
 #[skip_serializing_none]
@@ -73,6 +74,8 @@ pub struct Run {
     pub id: RunId,
     #[cfg_attr(feature = "dto", proto(required))]
     pub created_at: DateTime<Utc>,
+    /// The tick of the trigger's schedule that this run is for, if any.
+    pub scheduled_at: Option<DateTime<Utc>>,
     pub payload: Option<Payload>,
     #[cfg_attr(feature = "dto", proto(required))]
     pub action: Action,
@@ -4,6 +4,7 @@ use dto::{FromProto, IntoProto};
 use monostate::MustBe;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use strum::Display;
 #[cfg(feature = "validation")]
 use validator::{Validate, ValidationError};
 
@@ -22,11 +23,18 @@ use crate::validation_util::validation_error;
 pub enum Schedule {
     Recurring(Recurring),
     RunAt(RunAt),
+    Interval(Interval),
+    OnCompletionOf(OnCompletionOf),
 }
 
 #[skip_serializing_none]
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "validation",
+    derive(Validate),
+    validate(schema(function = "validate_recurring_window")),
+    validate(schema(function = "validate_recurring_misfire"))
+)]
 #[cfg_attr(
     feature = "dto",
     derive(IntoProto, FromProto),
@@ -50,11 +58,37 @@ pub struct Recurring {
     #[cfg_attr(feature = "validation", validate(range(min = 1)))]
     pub limit: Option<u64>,
     pub remaining: Option<u64>,
+    #[cfg_attr(feature = "server", serde(default = "default_misfire_policy"))]
+    pub misfire_policy: Option<MisfirePolicy>,
+    #[cfg_attr(feature = "validation", validate(range(min = 1, max = 1000)))]
+    pub max_missed_runs: Option<u64>,
+    /// The trigger doesn't run before this point in time.
+    pub start_at: Option<DateTime<FixedOffset>>,
+    /// The trigger expires after this point in time.
+    pub end_at: Option<DateTime<FixedOffset>>,
+    /// Delays each run by up to this many seconds to spread out the load of
+    /// triggers sharing the same cron pattern. The delay is stable for a
+    /// given trigger.
+    #[cfg_attr(feature = "validation", validate(range(max = 3600)))]
+    pub jitter_s: Option<u64>,
+    /// Names of the project's calendars, ticks that fall on one of their
+    /// dates are skipped.
+    #[cfg_attr(feature = "validation", validate(length(max = 10)))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_calendars: Vec<String>,
+    /// What happens to the ticks whose local time doesn't exist or happens
+    /// twice when the timezone's UTC offset changes.
+    #[cfg_attr(feature = "server", serde(default = "default_dst_policy"))]
+    pub dst_policy: Option<DstPolicy>,
 }
 
 #[skip_serializing_none]
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "validation",
+    derive(Validate),
+    validate(schema(function = "validate_run_at_misfire"))
+)]
 #[cfg_attr(
     feature = "dto",
     derive(IntoProto, FromProto),
@@ -79,6 +113,184 @@ pub struct RunAt {
     )]
     pub timepoints: Vec<DateTime<FixedOffset>>,
     pub remaining: Option<u64>,
+    #[cfg_attr(feature = "server", serde(default = "default_misfire_policy"))]
+    pub misfire_policy: Option<MisfirePolicy>,
+    #[cfg_attr(feature = "validation", validate(range(min = 1, max = 1000)))]
+    pub max_missed_runs: Option<u64>,
+}
+
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "validation",
+    derive(Validate),
+    validate(schema(function = "validate_interval_misfire"))
+)]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::triggers::Interval")
+)]
+#[cfg_attr(feature = "server", serde(deny_unknown_fields))]
+#[serde(rename_all = "snake_case")]
+pub struct Interval {
+    #[serde(rename = "type")]
+    _kind: MustBe!("interval"),
+    #[cfg_attr(feature = "validation", validate(custom = "validate_interval"))]
+    #[cfg_attr(
+        feature = "dto",
+        proto(name = "every_s"),
+        into_proto(map = "interval_to_secs", map_by_ref),
+        from_proto(map = "IntervalDuration::Seconds")
+    )]
+    pub every: IntervalDuration,
+    pub anchor: Option<DateTime<FixedOffset>>,
+    #[cfg_attr(feature = "validation", validate(range(min = 1)))]
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    #[cfg_attr(feature = "server", serde(default = "default_misfire_policy"))]
+    pub misfire_policy: Option<MisfirePolicy>,
+    #[cfg_attr(feature = "validation", validate(range(min = 1, max = 1000)))]
+    pub max_missed_runs: Option<u64>,
+}
+
+/// Runs the trigger every time a run of another trigger in the same project
+/// completes.
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::triggers::OnCompletionOf")
+)]
+#[cfg_attr(feature = "server", serde(deny_unknown_fields))]
+#[serde(rename_all = "snake_case")]
+pub struct OnCompletionOf {
+    #[serde(rename = "type")]
+    _kind: MustBe!("on_completion_of"),
+    /// The name of the upstream trigger.
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(
+            min = 2,
+            max = 64,
+            message = "trigger must be between 2 and 64 characters"
+        ))
+    )]
+    pub trigger: String,
+    #[cfg_attr(feature = "server", serde(default = "default_outcome"))]
+    pub outcome: Option<CompletionOutcome>,
+}
+
+/// The time between two runs of an interval schedule. It's either a number of
+/// seconds or an ISO-8601 duration (e.g. `PT90S` or `P1DT12H`).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IntervalDuration {
+    Seconds(u64),
+    Iso8601(String),
+}
+
+impl IntervalDuration {
+    /// The interval in whole seconds. Returns `None` if the duration is not
+    /// valid ISO-8601, uses years or months (which don't have a fixed
+    /// length), or has a fraction of a second.
+    pub fn as_secs(&self) -> Option<u64> {
+        match self {
+            | Self::Seconds(secs) => Some(*secs),
+            | Self::Iso8601(raw) => {
+                let duration = iso8601_duration::Duration::parse(raw).ok()?;
+                if duration.year != 0.0 || duration.month != 0.0 {
+                    return None;
+                }
+                let secs = duration.day as f64 * 86400.0
+                    + duration.hour as f64 * 3600.0
+                    + duration.minute as f64 * 60.0
+                    + duration.second as f64;
+                if secs.fract() != 0.0 {
+                    return None;
+                }
+                Some(secs as u64)
+            }
+        }
+    }
+}
+
+/// What the scheduler does with the ticks that were due while it was unable to
+/// run them (e.g. during an outage).
+#[derive(
+    Debug, Display, Clone, Copy, Default, Serialize, Deserialize, PartialEq,
+)]
+#[cfg_attr(feature = "client", non_exhaustive)]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::triggers::MisfirePolicy")
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "clap", clap(rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum MisfirePolicy {
+    /// Drop the missed ticks and wait for the next one.
+    Skip,
+    /// Run once to make up for all the missed ticks.
+    RunOnce,
+    /// Run every missed tick, or only the most recent `max_missed_runs` if
+    /// set.
+    #[default]
+    RunAllMissed,
+}
+
+/// What a recurring schedule does with the local times that are skipped when
+/// the clocks move forward, or repeated when they move back. Unless skipped,
+/// the local times that don't exist run as soon as the clocks move past them.
+#[derive(
+    Debug, Display, Clone, Copy, Default, Serialize, Deserialize, PartialEq,
+)]
+#[cfg_attr(feature = "client", non_exhaustive)]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::triggers::DstPolicy")
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "clap", clap(rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum DstPolicy {
+    /// Don't run the local times that don't exist or happen twice.
+    Skip,
+    /// Run repeated local times on their first occurrence.
+    #[default]
+    RunOnceEarliest,
+    /// Run repeated local times on their second occurrence.
+    RunOnceLatest,
+    /// Run repeated local times on both occurrences.
+    RunBoth,
+}
+
+/// The outcome of an upstream run that fires the dependent triggers.
+#[derive(
+    Debug, Display, Clone, Copy, Default, Serialize, Deserialize, PartialEq,
+)]
+#[cfg_attr(feature = "client", non_exhaustive)]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::triggers::CompletionOutcome")
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "clap", clap(rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum CompletionOutcome {
+    #[default]
+    Succeeded,
+    Failed,
+    /// Either succeeded or failed.
+    Any,
 }
 
 #[cfg(feature = "server")]
@@ -86,12 +298,37 @@ fn default_timezone() -> Option<String> {
     Some("Etc/UTC".to_string())
 }
 
+#[cfg(feature = "server")]
+fn default_misfire_policy() -> Option<MisfirePolicy> {
+    Some(MisfirePolicy::default())
+}
+
+#[cfg(feature = "server")]
+fn default_dst_policy() -> Option<DstPolicy> {
+    Some(DstPolicy::default())
+}
+
+#[cfg(feature = "server")]
+fn default_outcome() -> Option<CompletionOutcome> {
+    Some(CompletionOutcome::default())
+}
+
+#[cfg(feature = "dto")]
+fn interval_to_secs(every: &IntervalDuration) -> u64 {
+    // Validation guarantees that we have a valid interval at this point.
+    every.as_secs().unwrap_or_default()
+}
+
 #[cfg(feature = "validation")]
 impl Validate for Schedule {
     fn validate(&self) -> Result<(), validator::ValidationErrors> {
         match self {
             | Schedule::Recurring(recurring) => recurring.validate(),
             | Schedule::RunAt(run_at) => run_at.validate(),
+            | Schedule::Interval(interval) => interval.validate(),
+            | Schedule::OnCompletionOf(on_completion_of) => {
+                on_completion_of.validate()
+            }
         }
     }
 }
@@ -112,7 +349,7 @@ fn validate_cron(cron_pattern: &String) -> Result<(), ValidationError> {
 
 #[cfg(feature = "validation")]
 // Validate that run_at has no duplicates.
-fn validate_run_at(
+pub(crate) fn validate_run_at(
     run_at: &Vec<DateTime<FixedOffset>>,
 ) -> Result<(), ValidationError> {
     use std::collections::HashSet;
@@ -138,6 +375,91 @@ fn validate_run_at(
     Ok(())
 }
 
+#[cfg(feature = "validation")]
+fn validate_recurring_window(
+    recurring: &Recurring,
+) -> Result<(), ValidationError> {
+    if let (Some(start_at), Some(end_at)) =
+        (recurring.start_at, recurring.end_at)
+    {
+        if end_at <= start_at {
+            return Err(validation_error(
+                "invalid_schedule_window",
+                format!(
+                    "end_at '{end_at}' must be after start_at '{start_at}'"
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "validation")]
+fn validate_recurring_misfire(
+    recurring: &Recurring,
+) -> Result<(), ValidationError> {
+    validate_max_missed_runs(
+        recurring.misfire_policy,
+        recurring.max_missed_runs,
+    )
+}
+
+#[cfg(feature = "validation")]
+fn validate_run_at_misfire(run_at: &RunAt) -> Result<(), ValidationError> {
+    validate_max_missed_runs(run_at.misfire_policy, run_at.max_missed_runs)
+}
+
+#[cfg(feature = "validation")]
+fn validate_interval_misfire(
+    interval: &Interval,
+) -> Result<(), ValidationError> {
+    validate_max_missed_runs(interval.misfire_policy, interval.max_missed_runs)
+}
+
+#[cfg(feature = "validation")]
+// Only `run_all_missed` catches up on more than one missed tick.
+fn validate_max_missed_runs(
+    policy: Option<MisfirePolicy>,
+    max_missed_runs: Option<u64>,
+) -> Result<(), ValidationError> {
+    let policy = policy.unwrap_or_default();
+    if max_missed_runs.is_some() && policy != MisfirePolicy::RunAllMissed {
+        return Err(validation_error(
+            "invalid_max_missed_runs",
+            format!(
+                "max_missed_runs can only be set with the 'run_all_missed' \
+                 misfire policy, not '{policy}'"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "validation")]
+fn validate_interval(every: &IntervalDuration) -> Result<(), ValidationError> {
+    // A year, to keep the arithmetic on ticks far away from overflowing.
+    const MAX_INTERVAL_SECS: u64 = 365 * 24 * 60 * 60;
+
+    let Some(secs) = every.as_secs() else {
+        return Err(validation_error(
+            "invalid_interval",
+            "Interval must be a number of seconds or an ISO-8601 duration in \
+             whole seconds without years or months (e.g. 'PT90S')"
+                .to_owned(),
+        ));
+    };
+    if secs == 0 || secs > MAX_INTERVAL_SECS {
+        return Err(validation_error(
+            "invalid_interval",
+            format!(
+                "Interval must be between 1 second and {MAX_INTERVAL_SECS} \
+                 seconds (365 days)"
+            ),
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(feature = "validation")]
 pub fn validate_timezone(
     cron_timezone: &String,
@@ -232,6 +554,8 @@ mod tests {
         assert_eq!(Some("Etc/UTC".to_owned()), parsed.timezone);
         assert!(parsed.limit.is_none());
         assert!(parsed.remaining.is_none());
+        assert_eq!(Some(MisfirePolicy::RunAllMissed), parsed.misfire_policy);
+        assert_eq!(Some(DstPolicy::RunOnceEarliest), parsed.dst_policy);
 
         // invalid cron
         let recurring = json!(
@@ -248,6 +572,167 @@ mod tests {
             maybe_validated.unwrap_err().to_string()
         );
 
+        // the window must not be empty
+        let recurring = json!(
+            {
+                "type": "recurring",
+                "cron": "0 * * * * *",
+                "start_at": "2023-06-02T12:40:58+03:00",
+                "end_at": "2023-06-02T09:40:58Z",
+            }
+        );
+        let parsed: Recurring = serde_json::from_value(recurring)?;
+        let maybe_validated = parsed.validate();
+        assert!(maybe_validated.is_err());
+        assert!(maybe_validated
+            .unwrap_err()
+            .to_string()
+            .contains("must be after start_at"));
+
+        // calendars are excluded by name
+        let recurring = json!(
+            {
+                "type": "recurring",
+                "cron": "0 * * * * *",
+                "exclude_calendars": ["holidays"],
+            }
+        );
+        let parsed: Recurring = serde_json::from_value(recurring)?;
+        parsed.validate()?;
+        assert_eq!(vec!["holidays".to_owned()], parsed.exclude_calendars);
+
+        // repeated local times can run twice
+        let recurring = json!(
+            {
+                "type": "recurring",
+                "cron": "0 30 1 * * *",
+                "timezone": "America/New_York",
+                "dst_policy": "run_both",
+            }
+        );
+        let parsed: Recurring = serde_json::from_value(recurring)?;
+        parsed.validate()?;
+        assert_eq!(Some(DstPolicy::RunBoth), parsed.dst_policy);
+
+        // catching up on missed runs is capped
+        let recurring = json!(
+            {
+                "type": "recurring",
+                "cron": "0 * * * * *",
+                "misfire_policy": "run_all_missed",
+                "max_missed_runs": 5000,
+            }
+        );
+        let parsed: Recurring = serde_json::from_value(recurring)?;
+        assert_eq!(Some(MisfirePolicy::RunAllMissed), parsed.misfire_policy);
+        let maybe_validated = parsed.validate();
+        assert!(maybe_validated.is_err());
+        assert!(maybe_validated
+            .unwrap_err()
+            .to_string()
+            .starts_with("max_missed_runs: "));
+
+        // only catching up on every missed run can be capped
+        let recurring = json!(
+            {
+                "type": "recurring",
+                "cron": "0 * * * * *",
+                "misfire_policy": "run_once",
+                "max_missed_runs": 5,
+            }
+        );
+        let parsed: Recurring = serde_json::from_value(recurring)?;
+        let maybe_validated = parsed.validate();
+        assert!(maybe_validated.is_err());
+        assert!(maybe_validated
+            .unwrap_err()
+            .to_string()
+            .contains("max_missed_runs can only be set"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_on_completion_of() -> Result<()> {
+        let schedule = json!(
+            {
+                "type": "on_completion_of",
+                "trigger": "export",
+            }
+        );
+        let parsed: Schedule = serde_json::from_value(schedule)?;
+        parsed.validate()?;
+        let Schedule::OnCompletionOf(parsed) = parsed else {
+            panic!("Expected an on_completion_of schedule");
+        };
+        assert_eq!("export", parsed.trigger);
+        assert_eq!(Some(CompletionOutcome::Succeeded), parsed.outcome);
+
+        let schedule = json!(
+            {
+                "type": "on_completion_of",
+                "trigger": "export",
+                "outcome": "any",
+            }
+        );
+        let parsed: OnCompletionOf = serde_json::from_value(schedule)?;
+        parsed.validate()?;
+        assert_eq!(Some(CompletionOutcome::Any), parsed.outcome);
+
+        let schedule = json!(
+            {
+                "type": "on_completion_of",
+                "trigger": "",
+            }
+        );
+        let parsed: OnCompletionOf = serde_json::from_value(schedule)?;
+        assert!(parsed.validate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn validate_interval() -> Result<()> {
+        let interval = json!(
+            {
+                "type": "interval",
+                "every": 90,
+            }
+        );
+        let parsed: Interval = serde_json::from_value(interval)?;
+        parsed.validate()?;
+        assert_eq!(Some(90), parsed.every.as_secs());
+
+        let interval = json!(
+            {
+                "type": "interval",
+                "every": "P1DT12H",
+                "anchor": "2023-06-02T12:40:58+03:00",
+                "limit": 3,
+            }
+        );
+        let parsed: Interval = serde_json::from_value(interval)?;
+        parsed.validate()?;
+        assert_eq!(Some(36 * 60 * 60), parsed.every.as_secs());
+
+        // months, fractions of seconds, non ISO-8601 strings and zero are
+        // all rejected.
+        for every in [json!("P1M"), json!("PT0.5S"), json!("90s"), json!(0)] {
+            let interval = json!(
+                {
+                    "type": "interval",
+                    "every": every,
+                }
+            );
+            let parsed: Interval = serde_json::from_value(interval)?;
+            let maybe_validated = parsed.validate();
+            assert!(maybe_validated.is_err());
+            assert!(maybe_validated
+                .unwrap_err()
+                .to_string()
+                .starts_with("every: Interval must be"));
+        }
+
         Ok(())
     }
 }
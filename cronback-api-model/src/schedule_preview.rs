@@ -0,0 +1,65 @@
+use chrono::{DateTime, FixedOffset};
+#[cfg(feature = "dto")]
+use dto::FromProto;
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+#[cfg(feature = "validation")]
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+pub struct PreviewScheduleQuery {
+    /// How many occurrences to evaluate.
+    #[cfg_attr(
+        feature = "validation",
+        validate(range(
+            min = 1,
+            max = 100,
+            message = "must be between 1 and 100"
+        ))
+    )]
+    #[serde(default = "default_preview_count")]
+    pub count: u64,
+}
+
+fn default_preview_count() -> u64 {
+    5
+}
+
+/// The upcoming occurrences of a schedule that is not installed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    feature = "dto",
+    derive(FromProto),
+    proto(target = "proto::scheduler_svc::PreviewScheduleResponse")
+)]
+pub struct SchedulePreview {
+    pub occurrences: Vec<ScheduleOccurrence>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    feature = "dto",
+    derive(FromProto),
+    proto(target = "proto::scheduler_svc::ScheduleOccurrence")
+)]
+pub struct ScheduleOccurrence {
+    /// In the schedule's timezone.
+    #[cfg_attr(feature = "dto", proto(required))]
+    pub at: DateTime<FixedOffset>,
+    /// Set if the UTC offset of the schedule's timezone changed since the
+    /// previous occurrence, e.g. when daylight saving time starts or ends.
+    pub dst_transition: Option<DstTransition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    feature = "dto",
+    derive(FromProto),
+    proto(target = "proto::scheduler_svc::DstTransition")
+)]
+pub struct DstTransition {
+    pub from_offset: String,
+    pub to_offset: String,
+}
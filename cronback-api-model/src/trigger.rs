@@ -1,13 +1,21 @@
-use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Utc};
 #[cfg(feature = "dto")]
 use dto::{FromProto, IntoProto};
-use serde::{Deserialize, Serialize};
-use serde_with::skip_serializing_none;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_with::{skip_serializing_none, DeserializeFromStr, SerializeDisplay};
 use strum::Display;
 #[cfg(feature = "validation")]
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use super::{Action, Payload, Schedule};
+#[cfg(feature = "validation")]
+use crate::schedule::validate_run_at;
+#[cfg(feature = "validation")]
+use crate::validation_util::validation_error;
 use crate::{Recurring, RunAt, Webhook};
 
 #[derive(Debug, Deserialize, Default)]
@@ -21,6 +29,75 @@ pub struct TriggersFilter {
     #[serde(default)]
     #[cfg_attr(feature = "dto", proto(name = "statuses"))]
     pub status: Vec<TriggerStatus>,
+    /// Label selectors (e.g. `team=billing,env!=staging`), a trigger must
+    /// match all of them.
+    #[serde(default, deserialize_with = "deserialize_label_selectors")]
+    #[cfg_attr(feature = "dto", proto(name = "label_selectors"))]
+    pub label: Vec<LabelSelector>,
+}
+
+/// Matches triggers by one of their labels, written as `key=value` or
+/// `key!=value`. The latter also matches the triggers that don't have the
+/// label at all.
+#[derive(Debug, Clone, PartialEq, SerializeDisplay, DeserializeFromStr)]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto),
+    proto(target = "proto::scheduler_svc::LabelSelector")
+)]
+pub struct LabelSelector {
+    pub key: String,
+    pub value: String,
+    pub not_equal: bool,
+}
+
+impl fmt::Display for LabelSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = if self.not_equal { "!=" } else { "=" };
+        write!(f, "{}{}{}", self.key, op, self.value)
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value, not_equal) = if let Some((k, v)) = s.split_once("!=") {
+            (k, v, true)
+        } else if let Some((k, v)) = s.split_once('=') {
+            (k, v, false)
+        } else {
+            return Err(format!(
+                "Invalid label selector '{s}', expected 'key=value' or \
+                 'key!=value'"
+            ));
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("Label selector '{s}' is missing a key"));
+        }
+        Ok(Self {
+            key: key.to_owned(),
+            value: value.trim().to_owned(),
+            not_equal,
+        })
+    }
+}
+
+// Selectors can be passed as a comma separated list, as a repeated query
+// parameter, or both.
+fn deserialize_label_selectors<'de, D>(
+    deserializer: D,
+) -> Result<Vec<LabelSelector>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Vec<String> = Vec::deserialize(deserializer)?;
+    raw.iter()
+        .flat_map(|s| s.split(','))
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .collect()
 }
 
 #[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -81,6 +158,57 @@ pub struct Trigger {
     // Estimate of timepoints of the next runs (up to 5 runs).
     #[serde(default)]
     pub estimated_future_runs: Vec<DateTime<Utc>>,
+    pub concurrency_policy: Option<ConcurrencyPolicy>,
+    /// Set if the trigger is paused and will resume on its own at this time.
+    pub paused_until: Option<DateTime<Utc>>,
+    /// Upcoming occurrences that won't run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped_occurrences: Vec<DateTime<Utc>>,
+    /// Arbitrary key/value pairs used to organize and select triggers.
+    #[cfg_attr(feature = "validation", validate(custom = "validate_labels"))]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "server",
+    derive(Default),
+    serde(default),
+    serde(deny_unknown_fields)
+)]
+pub struct PauseTrigger {
+    /// Resume the trigger automatically at this time. The trigger remains
+    /// paused until it's resumed if unset.
+    #[cfg_attr(feature = "validation", validate(custom = "validate_until"))]
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Decides what happens when a trigger is due while its previous run is still
+/// attempting.
+#[derive(
+    Debug, Display, Clone, Copy, Default, Serialize, Deserialize, PartialEq,
+)]
+#[cfg_attr(feature = "client", non_exhaustive)]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::common::ConcurrencyPolicy")
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "clap", clap(rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ConcurrencyPolicy {
+    /// Runs are allowed to overlap.
+    #[default]
+    Allow,
+    /// Skip the new run.
+    Forbid,
+    /// Skip the previous runs in favour of the new one.
+    Replace,
 }
 
 impl Trigger {
@@ -109,6 +237,215 @@ impl Trigger {
     }
 }
 
+/// Skips upcoming occurrences of a trigger, either the next `count` ones or
+/// exactly the listed `occurrences`.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "validation",
+    derive(Validate),
+    validate(schema(function = "validate_skip_trigger"))
+)]
+#[cfg_attr(feature = "server", serde(default), serde(deny_unknown_fields))]
+pub struct SkipTrigger {
+    #[cfg_attr(feature = "validation", validate(range(min = 1, max = 100)))]
+    pub count: Option<u64>,
+    #[cfg_attr(
+        feature = "validation",
+        validate(length(
+            max = 100,
+            message = "Can't skip more than 100 occurrences at once"
+        ))
+    )]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub occurrences: Vec<DateTime<Utc>>,
+}
+
+/// Timepoints to add to, or remove from, a trigger with a `timepoints`
+/// schedule. Adding a timepoint that's already upcoming, or removing one that
+/// isn't, has no effect. Adding timepoints to an expired trigger schedules it
+/// again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(feature = "server", serde(deny_unknown_fields))]
+pub struct UpdateTimepoints {
+    #[cfg_attr(
+        feature = "validation",
+        validate(
+            length(
+                min = 1,
+                max = 5000,
+                message = "must have at least one but with no more than 5000 \
+                           timepoints"
+            ),
+            custom = "validate_run_at"
+        )
+    )]
+    pub timepoints: Vec<DateTime<FixedOffset>>,
+}
+
+/// An action applied to all the triggers matching a [`BulkUpdateTriggers`]
+/// filter.
+#[derive(Debug, Display, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "client", non_exhaustive)]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto),
+    proto(target = "proto::scheduler_svc::BulkAction")
+)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "clap", clap(rename_all = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum BulkAction {
+    Pause,
+    Resume,
+    Cancel,
+    Delete,
+}
+
+/// Pauses, resumes, cancels or deletes all the triggers with one of the
+/// given statuses and/or whose name starts with `name_prefix`.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "validation",
+    derive(Validate),
+    validate(schema(function = "validate_bulk_update_triggers"))
+)]
+#[cfg_attr(feature = "server", serde(deny_unknown_fields))]
+pub struct BulkUpdateTriggers {
+    pub action: BulkAction,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<TriggerStatus>,
+    #[cfg_attr(feature = "validation", validate(length(min = 1, max = 64)))]
+    pub name_prefix: Option<String>,
+    /// Reports what would happen to each trigger without changing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// The outcome of a bulk action on a single trigger.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    feature = "dto",
+    derive(FromProto),
+    proto(target = "proto::scheduler_svc::BulkTriggerResult")
+)]
+pub struct BulkTriggerResult {
+    pub name: String,
+    /// The status of the trigger after the action, unset if it was deleted
+    /// or the action failed.
+    pub status: Option<TriggerStatus>,
+    /// Set if the action can't be applied to this trigger.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    feature = "dto",
+    derive(FromProto),
+    proto(target = "proto::scheduler_svc::BulkUpdateTriggersResponse")
+)]
+pub struct BulkUpdateTriggersResult {
+    pub results: Vec<BulkTriggerResult>,
+}
+
+/// The definition of a trigger as it was installed or updated.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(
+    feature = "dto",
+    derive(FromProto),
+    proto(target = "proto::triggers::TriggerRevision")
+)]
+pub struct TriggerRevision {
+    pub revision: u64,
+    /// The id of the API key that made the change.
+    pub author: Option<String>,
+    #[cfg_attr(feature = "dto", proto(required))]
+    pub created_at: DateTime<Utc>,
+    #[cfg_attr(feature = "dto", proto(required))]
+    pub trigger: Trigger,
+}
+
+#[cfg(feature = "validation")]
+fn validate_skip_trigger(skip: &SkipTrigger) -> Result<(), ValidationError> {
+    if skip.count.is_some() != skip.occurrences.is_empty() {
+        return Err(validation_error(
+            "invalid_skip",
+            "Exactly one of count or occurrences must be set".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "validation")]
+fn validate_bulk_update_triggers(
+    bulk: &BulkUpdateTriggers,
+) -> Result<(), ValidationError> {
+    // Matching all the triggers of a project is too easy to do by accident.
+    if bulk.status.is_empty() && bulk.name_prefix.is_none() {
+        return Err(validation_error(
+            "invalid_bulk_filter",
+            "At least one of status or name_prefix must be set".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "validation")]
+fn validate_labels(
+    labels: &HashMap<String, String>,
+) -> Result<(), ValidationError> {
+    // Keys and values end up in selectors, so they can't contain any of the
+    // selectors' separators.
+    fn valid_chars(s: &str) -> bool {
+        s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+    }
+
+    if labels.len() > 32 {
+        return Err(validation_error(
+            "too_many_labels",
+            "A trigger can't have more than 32 labels".to_owned(),
+        ));
+    }
+    for (key, value) in labels {
+        if key.is_empty() || key.len() > 63 || !valid_chars(key) {
+            return Err(validation_error(
+                "invalid_label_key",
+                format!(
+                    "Label key '{key}' must be 1 to 63 characters of \
+                     alphanumerics, '-', '_', '.' or '/'"
+                ),
+            ));
+        }
+        if value.len() > 63 || !valid_chars(value) {
+            return Err(validation_error(
+                "invalid_label_value",
+                format!(
+                    "Label value '{value}' must be at most 63 characters of \
+                     alphanumerics, '-', '_', '.' or '/'"
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "validation")]
+fn validate_until(until: &DateTime<Utc>) -> Result<(), ValidationError> {
+    if *until <= Utc::now() {
+        return Err(validation_error(
+            "invalid_until",
+            format!("Pause until '{until}' must be in the future"),
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(all(test, feature = "validation"))]
 mod tests {
     use anyhow::Result;
@@ -153,4 +490,196 @@ mod tests {
         std::env::remove_var("CRONBACK__SKIP_PUBLIC_IP_VALIDATION");
         Ok(())
     }
+
+    #[test]
+    fn validate_install_trigger_retry() -> Result<()> {
+        std::env::set_var("CRONBACK__SKIP_PUBLIC_IP_VALIDATION", "true");
+        let mut request = json!(
+          {
+            "action": {
+              "url": "http://localhost:3000/action",
+              "retry": {
+                "delay_s": 60,
+                "respect_retry_after": { "max_delay_s": 600 }
+              }
+            }
+          }
+        );
+        let parsed: Trigger = serde_json::from_value(request.clone())?;
+        parsed.validate()?;
+
+        // the retry config is validated as part of the trigger
+        request["action"]["retry"]["respect_retry_after"]["max_delay_s"] =
+            json!(7200);
+        let parsed: Trigger = serde_json::from_value(request.clone())?;
+        let maybe_validated = parsed.validate();
+        assert!(maybe_validated.is_err());
+        assert!(maybe_validated
+            .unwrap_err()
+            .to_string()
+            .contains("Max Retry-After delay must be between"));
+
+        request["action"]["retry"] = json!({ "delay_s": 1 });
+        let parsed: Trigger = serde_json::from_value(request)?;
+        assert!(parsed.validate().is_err());
+        std::env::remove_var("CRONBACK__SKIP_PUBLIC_IP_VALIDATION");
+        Ok(())
+    }
+
+    #[test]
+    fn validate_skip_trigger() -> Result<()> {
+        let parsed: SkipTrigger = serde_json::from_value(json!({"count": 2}))?;
+        parsed.validate()?;
+
+        let parsed: SkipTrigger = serde_json::from_value(json!({
+            "occurrences": ["2023-07-01T09:00:00Z"]
+        }))?;
+        parsed.validate()?;
+
+        // one of them must be set, but not both
+        let parsed: SkipTrigger = serde_json::from_value(json!({}))?;
+        assert!(parsed.validate().is_err());
+        let parsed: SkipTrigger = serde_json::from_value(json!({
+            "count": 1,
+            "occurrences": ["2023-07-01T09:00:00Z"]
+        }))?;
+        assert!(parsed.validate().is_err());
+
+        let parsed: SkipTrigger = serde_json::from_value(json!({"count": 0}))?;
+        assert!(parsed.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_update_timepoints() -> Result<()> {
+        let parsed: UpdateTimepoints = serde_json::from_value(json!({
+            "timepoints": ["2023-07-01T09:00:00Z", "2023-07-01T09:00:00+02:00"]
+        }))?;
+        parsed.validate()?;
+        assert_eq!(2, parsed.timepoints.len());
+
+        let parsed: UpdateTimepoints =
+            serde_json::from_value(json!({ "timepoints": [] }))?;
+        assert!(parsed.validate().is_err());
+
+        // the same instant can't be listed twice
+        let parsed: UpdateTimepoints = serde_json::from_value(json!({
+            "timepoints": ["2023-07-01T09:00:00Z", "2023-07-01T11:00:00+02:00"]
+        }))?;
+        assert!(parsed.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_trigger_labels() -> Result<()> {
+        std::env::set_var("CRONBACK__SKIP_PUBLIC_IP_VALIDATION", "true");
+        let mut request = json!(
+          {
+            "action": { "url": "http://localhost:3000/action" },
+            "labels": { "team": "billing", "env": "prod-eu.1" }
+          }
+        );
+        let parsed: Trigger = serde_json::from_value(request.clone())?;
+        parsed.validate()?;
+        assert_eq!(Some(&"billing".to_owned()), parsed.labels.get("team"));
+
+        // separators aren't allowed in labels
+        request["labels"] = json!({ "team": "billing,ops" });
+        let parsed: Trigger = serde_json::from_value(request.clone())?;
+        let maybe_validated = parsed.validate();
+        assert!(maybe_validated.is_err());
+        assert!(maybe_validated
+            .unwrap_err()
+            .to_string()
+            .starts_with("labels: Label value"));
+
+        request["labels"] = json!({ "": "billing" });
+        let parsed: Trigger = serde_json::from_value(request)?;
+        assert!(parsed.validate().is_err());
+        std::env::remove_var("CRONBACK__SKIP_PUBLIC_IP_VALIDATION");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_label_selectors() -> Result<()> {
+        let filter: TriggersFilter = serde_json::from_value(json!({
+            "label": ["team=billing,env!=staging", "tier="]
+        }))?;
+        assert_eq!(
+            vec![
+                LabelSelector {
+                    key: "team".to_owned(),
+                    value: "billing".to_owned(),
+                    not_equal: false,
+                },
+                LabelSelector {
+                    key: "env".to_owned(),
+                    value: "staging".to_owned(),
+                    not_equal: true,
+                },
+                LabelSelector {
+                    key: "tier".to_owned(),
+                    value: "".to_owned(),
+                    not_equal: false,
+                },
+            ],
+            filter.label
+        );
+        assert_eq!("env!=staging", filter.label[1].to_string());
+
+        assert!("team".parse::<LabelSelector>().is_err());
+        assert!("=billing".parse::<LabelSelector>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_bulk_update_triggers() -> Result<()> {
+        let parsed: BulkUpdateTriggers = serde_json::from_value(json!({
+            "action": "pause",
+            "status": ["scheduled"],
+            "name_prefix": "billing-",
+        }))?;
+        parsed.validate()?;
+        assert_eq!(BulkAction::Pause, parsed.action);
+        assert!(!parsed.dry_run);
+
+        let parsed: BulkUpdateTriggers = serde_json::from_value(json!({
+            "action": "delete",
+            "name_prefix": "tmp-",
+            "dry_run": true,
+        }))?;
+        parsed.validate()?;
+        assert!(parsed.dry_run);
+
+        // a filter is required
+        let parsed: BulkUpdateTriggers =
+            serde_json::from_value(json!({ "action": "cancel" }))?;
+        assert!(parsed.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_pause_trigger() -> Result<()> {
+        let parsed: PauseTrigger = serde_json::from_value(json!({}))?;
+        parsed.validate()?;
+        assert_eq!(None, parsed.until);
+
+        let until = Utc::now() + chrono::Duration::hours(1);
+        let parsed: PauseTrigger =
+            serde_json::from_value(json!({ "until": until }))?;
+        parsed.validate()?;
+        assert_eq!(Some(until), parsed.until);
+
+        // can't pause until a time in the past
+        let until = Utc::now() - chrono::Duration::hours(1);
+        let parsed: PauseTrigger =
+            serde_json::from_value(json!({ "until": until }))?;
+        let maybe_validated = parsed.validate();
+        assert!(maybe_validated.is_err());
+        assert!(maybe_validated
+            .unwrap_err()
+            .to_string()
+            .starts_with("until: Pause until"));
+        Ok(())
+    }
 }
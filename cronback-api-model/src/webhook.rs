@@ -1,3 +1,5 @@
+use std::fmt;
+use std::str::FromStr;
 use std::time::Duration;
 
 #[cfg(feature = "dto")]
@@ -6,7 +8,13 @@ use dto::{FromProto, IntoProto};
 use ipext::IpExt;
 use monostate::MustBe;
 use serde::{Deserialize, Serialize};
-use serde_with::{serde_as, skip_serializing_none, DurationSecondsWithFrac};
+use serde_with::{
+    serde_as,
+    skip_serializing_none,
+    DeserializeFromStr,
+    DurationSecondsWithFrac,
+    SerializeDisplay,
+};
 use strum::Display;
 #[cfg(feature = "validation")]
 use thiserror::Error;
@@ -69,7 +77,11 @@ pub struct Webhook {
     )]
     pub timeout_s: std::time::Duration,
     // None means no retry
+    #[cfg_attr(feature = "validation", validate)]
     pub retry: Option<RetryConfig>,
+    /// Decides which responses are successful. Any 2xx response is if unset.
+    #[cfg_attr(feature = "validation", validate)]
+    pub success_criteria: Option<SuccessCriteria>,
 }
 
 #[cfg(feature = "server")]
@@ -81,10 +93,70 @@ impl Default for Webhook {
             http_method: HttpMethod::Post,
             timeout_s: Duration::from_secs(5),
             retry: None,
+            success_criteria: None,
         }
     }
 }
 
+/// The conditions that a response must meet for an attempt to succeed. All
+/// the criteria that are set must match. Only the first 64KiB of the response
+/// body are checked.
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::common::SuccessCriteria")
+)]
+#[serde(default, deny_unknown_fields)]
+pub struct SuccessCriteria {
+    /// Status codes (e.g. `202`), ranges of them (e.g. `200-302`) or classes
+    /// of them (e.g. `2xx`). Defaults to `2xx` if empty.
+    pub status_codes: Vec<StatusCodeRange>,
+    /// The response body must be JSON with this value at the pointer.
+    #[cfg_attr(feature = "validation", validate)]
+    pub json_pointer: Option<JsonPointerEquals>,
+    /// The response body must contain this string.
+    #[cfg_attr(feature = "validation", validate(length(min = 1, max = 1024)))]
+    pub body_contains: Option<String>,
+}
+
+/// Matches a JSON response body whose value at `pointer` (e.g. `/ok`, see
+/// RFC 6901) equals `equals`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::common::JsonPointerEquals")
+)]
+#[serde(deny_unknown_fields)]
+pub struct JsonPointerEquals {
+    #[cfg_attr(
+        feature = "validation",
+        validate(custom = "validate_json_pointer")
+    )]
+    pub pointer: String,
+    #[cfg_attr(
+        feature = "dto",
+        proto(name = "equals_json"),
+        into_proto(map = "json_to_string", map_by_ref),
+        from_proto(map = "json_from_string")
+    )]
+    pub equals: serde_json::Value,
+}
+
+#[cfg(feature = "dto")]
+fn json_to_string(value: &serde_json::Value) -> String {
+    value.to_string()
+}
+
+#[cfg(feature = "dto")]
+fn json_from_string(value: String) -> serde_json::Value {
+    serde_json::from_str(&value).unwrap_or_default()
+}
+
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[cfg_attr(feature = "client", non_exhaustive)]
@@ -103,6 +175,16 @@ pub enum RetryConfig {
     ExponentialBackoffRetry(ExponentialBackoffRetry),
 }
 
+#[cfg(feature = "validation")]
+impl Validate for RetryConfig {
+    fn validate(&self) -> Result<(), validator::ValidationErrors> {
+        match self {
+            | RetryConfig::SimpleRetry(retry) => retry.validate(),
+            | RetryConfig::ExponentialBackoffRetry(retry) => retry.validate(),
+        }
+    }
+}
+
 #[serde_as]
 #[skip_serializing_none]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -129,6 +211,13 @@ pub struct SimpleRetry {
         validate(custom = "validate_retry_delay")
     )]
     pub delay_s: Duration,
+    /// Only these failures are retried. Every failure is retried if unset.
+    #[cfg_attr(feature = "validation", validate)]
+    pub retry_on: Option<RetryOn>,
+    /// Waits for the delay in the `Retry-After` header of failed responses
+    /// instead of the delay above.
+    #[cfg_attr(feature = "validation", validate)]
+    pub respect_retry_after: Option<RespectRetryAfter>,
 }
 
 #[cfg(feature = "server")]
@@ -138,6 +227,8 @@ impl Default for SimpleRetry {
             _kind: Default::default(),
             max_num_attempts: 5,
             delay_s: Duration::from_secs(60),
+            retry_on: None,
+            respect_retry_after: None,
         }
     }
 }
@@ -179,6 +270,113 @@ pub struct ExponentialBackoffRetry {
         validate(custom = "validate_retry_delay")
     )]
     pub max_delay_s: Duration,
+    /// Only these failures are retried. Every failure is retried if unset.
+    #[cfg_attr(feature = "validation", validate)]
+    pub retry_on: Option<RetryOn>,
+    /// Waits for the delay in the `Retry-After` header of failed responses
+    /// instead of the delay above.
+    #[cfg_attr(feature = "validation", validate)]
+    pub respect_retry_after: Option<RespectRetryAfter>,
+}
+
+/// The failures of a webhook that are retried. Failures that don't come from
+/// the receiver, like failing to sign the request, are always retried.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::common::RetryOn")
+)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryOn {
+    /// Status codes (e.g. `429`), ranges of them (e.g. `500-504`) or classes
+    /// of them (e.g. `5xx`).
+    pub status_codes: Vec<StatusCodeRange>,
+    pub connection_errors: bool,
+    pub timeouts: bool,
+}
+
+/// An inclusive range of HTTP status codes, written as `429`, `500-504` or
+/// `5xx`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, SerializeDisplay, DeserializeFromStr,
+)]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::common::StatusCodeRange")
+)]
+pub struct StatusCodeRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl fmt::Display for StatusCodeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else if self.start % 100 == 0 && self.end == self.start + 99 {
+            write!(f, "{}xx", self.start / 100)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl FromStr for StatusCodeRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let parse = |code: &str| {
+            code.trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|c| (100..=599).contains(c))
+        };
+        let range = if let Some(class) = s.strip_suffix("xx") {
+            parse(&format!("{class}00")).map(|start| (start, start + 99))
+        } else if let Some((start, end)) = s.split_once('-') {
+            parse(start).zip(parse(end))
+        } else {
+            parse(s).map(|code| (code, code))
+        };
+        match range {
+            | Some((start, end)) if start <= end => Ok(Self { start, end }),
+            | _ => {
+                Err(format!(
+                    "Invalid status codes '{s}', expected a status code \
+                     between 100 and 599 (e.g. '429'), a range of them (e.g. \
+                     '500-504') or a class of them (e.g. '5xx')"
+                ))
+            }
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "validation", derive(Validate))]
+#[cfg_attr(
+    feature = "dto",
+    derive(IntoProto, FromProto),
+    proto(target = "proto::common::RespectRetryAfter")
+)]
+#[serde(deny_unknown_fields)]
+pub struct RespectRetryAfter {
+    /// Longer delays asked for by the receiver are capped to this.
+    #[serde_as(as = "DurationSecondsWithFrac")]
+    #[cfg_attr(
+        feature = "dto",
+        into_proto(map = "std::time::Duration::as_secs_f64", map_by_ref),
+        from_proto(map = "Duration::from_secs_f64")
+    )]
+    #[cfg_attr(
+        feature = "validation",
+        validate(custom = "validate_max_retry_after")
+    )]
+    pub max_delay_s: Duration,
 }
 
 #[cfg(feature = "validation")]
@@ -203,6 +401,36 @@ fn validate_retry_delay(delay: &Duration) -> Result<(), ValidationError> {
     Ok(())
 }
 
+#[cfg(feature = "validation")]
+fn validate_json_pointer(pointer: &str) -> Result<(), ValidationError> {
+    if pointer.len() > 1024 {
+        return Err(validation_error(
+            "invalid_json_pointer",
+            "JSON pointer must be at most 1024 characters long".to_string(),
+        ));
+    }
+    // The empty pointer refers to the whole document.
+    if !pointer.is_empty() && !pointer.starts_with('/') {
+        return Err(validation_error(
+            "invalid_json_pointer",
+            format!("JSON pointer '{pointer}' must be empty or start with '/'"),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "validation")]
+fn validate_max_retry_after(delay: &Duration) -> Result<(), ValidationError> {
+    if delay.as_secs_f64() < 1.0 || delay.as_secs_f64() > 3600.0 {
+        return Err(validation_error(
+            "invalid_max_retry_after",
+            "Max Retry-After delay must be between 1.0 and 3600.0 seconds"
+                .to_string(),
+        ));
+    };
+    Ok(())
+}
+
 #[cfg(feature = "validation")]
 #[derive(Error, Debug)]
 enum WebhookUrlValidationError {
@@ -284,7 +512,19 @@ impl From<WebhookUrlValidationError> for ValidationError {
 #[cfg(all(test, feature = "validation"))]
 mod tests {
 
-    use super::{validate_webhook_url, HttpMethod};
+    use std::time::Duration;
+
+    use serde_json::json;
+    use validator::Validate;
+
+    use super::{
+        validate_webhook_url,
+        HttpMethod,
+        RespectRetryAfter,
+        RetryConfig,
+        StatusCodeRange,
+        SuccessCriteria,
+    };
 
     #[test]
     fn http_method_to_string() {
@@ -353,4 +593,124 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn status_code_ranges() {
+        let cases = [
+            ("429", (429, 429), "429"),
+            ("500-504", (500, 504), "500-504"),
+            ("5xx", (500, 599), "5xx"),
+            ("400-499", (400, 499), "4xx"),
+        ];
+        for (input, (start, end), display) in cases {
+            let range: StatusCodeRange = input.parse().unwrap();
+            assert_eq!(range, StatusCodeRange { start, end });
+            assert_eq!(range.to_string(), display);
+        }
+
+        for input in ["", "42", "600", "504-500", "6xx", "abc", "500-"] {
+            assert!(
+                input.parse::<StatusCodeRange>().is_err(),
+                "Input: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn retry_config_with_retry_on() {
+        let retry: RetryConfig = serde_json::from_value(json!({
+            "type": "exponential_backoff",
+            "max_num_attempts": 3,
+            "delay_s": 10,
+            "max_delay_s": 60,
+            "retry_on": {
+                "status_codes": ["429", "5xx"],
+                "timeouts": true
+            },
+            "respect_retry_after": {
+                "max_delay_s": 600
+            }
+        }))
+        .unwrap();
+
+        let RetryConfig::ExponentialBackoffRetry(retry) = retry else {
+            panic!("Unexpected retry config: {retry:?}");
+        };
+        retry.validate().unwrap();
+        let retry_on = retry.retry_on.unwrap();
+        assert_eq!(
+            retry_on.status_codes,
+            vec![
+                StatusCodeRange {
+                    start: 429,
+                    end: 429
+                },
+                StatusCodeRange {
+                    start: 500,
+                    end: 599
+                },
+            ]
+        );
+        assert!(!retry_on.connection_errors);
+        assert!(retry_on.timeouts);
+        assert_eq!(
+            retry.respect_retry_after,
+            Some(RespectRetryAfter {
+                max_delay_s: Duration::from_secs(600)
+            })
+        );
+
+        // The cap can't exceed an hour.
+        let retry: RetryConfig = serde_json::from_value(json!({
+            "type": "exponential_backoff",
+            "max_num_attempts": 3,
+            "delay_s": 10,
+            "max_delay_s": 60,
+            "respect_retry_after": {
+                "max_delay_s": 7200
+            }
+        }))
+        .unwrap();
+        let RetryConfig::ExponentialBackoffRetry(retry) = retry else {
+            panic!("Unexpected retry config: {retry:?}");
+        };
+        assert!(retry.validate().is_err());
+    }
+
+    #[test]
+    fn validate_success_criteria() {
+        let criteria: SuccessCriteria = serde_json::from_value(json!({
+            "status_codes": ["200", "202", "3xx"],
+            "json_pointer": {
+                "pointer": "/ok",
+                "equals": true
+            }
+        }))
+        .unwrap();
+        criteria.validate().unwrap();
+        assert_eq!(criteria.status_codes.len(), 3);
+        assert_eq!(criteria.json_pointer.unwrap().equals, json!(true));
+
+        let criteria: SuccessCriteria =
+            serde_json::from_value(json!({"body_contains": "OK"})).unwrap();
+        criteria.validate().unwrap();
+        assert!(criteria.status_codes.is_empty());
+
+        let invalid = [
+            json!({"json_pointer": {"pointer": "ok", "equals": true}}),
+            json!({"body_contains": ""}),
+            json!({"body_contains": "a".repeat(1025)}),
+        ];
+        for value in invalid {
+            let criteria: SuccessCriteria =
+                serde_json::from_value(value.clone()).unwrap();
+            assert!(criteria.validate().is_err(), "Criteria: {value}");
+        }
+
+        // Unknown status codes are rejected while parsing.
+        assert!(serde_json::from_value::<SuccessCriteria>(
+            json!({"status_codes": ["2xxx"]})
+        )
+        .is_err());
+    }
 }
@@ -2,6 +2,7 @@ use cling::prelude::*;
 
 mod api_keys;
 mod projects;
+mod shards;
 
 #[derive(CliRunnable, Subcommand, Debug, Clone)]
 pub enum AdminCommand {
@@ -11,4 +12,8 @@ pub enum AdminCommand {
     ApiKeys(api_keys::ApiKeysCommand),
     #[command(subcommand)]
     Projects(projects::ProjectsCommand),
+    /// Commands for moving shards between scheduler cells. This subcommand
+    /// requires admin privilliages.
+    #[command(subcommand)]
+    Shards(shards::ShardsCommand),
 }
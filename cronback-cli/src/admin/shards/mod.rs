@@ -0,0 +1,8 @@
+use cling::prelude::*;
+mod move_shards;
+
+#[derive(CliRunnable, Subcommand, Debug, Clone)]
+pub enum ShardsCommand {
+    /// Move shards from a scheduler cell to another
+    Move(move_shards::Move),
+}
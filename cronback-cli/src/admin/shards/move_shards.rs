@@ -0,0 +1,44 @@
+use anyhow::Result;
+use cling::prelude::*;
+
+use crate::args::CommonOptions;
+
+#[derive(CliRunnable, CliParam, Clone, Debug, Parser)]
+#[cling(run = "move_shards")]
+pub struct Move {
+    /// The cell that currently serves the shards
+    #[clap(long)]
+    from_cell: u64,
+    /// The cell that will serve the shards
+    #[clap(long)]
+    to_cell: u64,
+    /// The shards to move
+    #[clap(required = true)]
+    shards: Vec<u64>,
+}
+
+async fn move_shards(
+    common_options: &CommonOptions,
+    opts: &Move,
+) -> Result<()> {
+    let client = common_options.new_client()?;
+
+    let response = cronback_client::shards::move_shards(
+        &client,
+        opts.shards.clone(),
+        opts.from_cell,
+        opts.to_cell,
+    )
+    .await?;
+
+    let response = response.into_inner()?;
+
+    println!(
+        "Moved {} triggers of shards {:?} from cell {} to cell {}.",
+        response.moved_triggers,
+        response.shards,
+        response.from_cell,
+        response.to_cell
+    );
+    Ok(())
+}
@@ -13,7 +13,7 @@ use url::Url;
 use crate::admin;
 use crate::client::WrappedClient;
 use crate::ui::FancyToString;
-use crate::{runs, triggers, whoami};
+use crate::{runs, schedules, triggers, whoami};
 
 const CRONBACK_SECRET_TOKEN_VAR: &str = "CRONBACK_SECRET_TOKEN";
 #[cfg(feature = "admin")]
@@ -73,6 +73,9 @@ pub enum CliCommand {
     /// Commands for trigger runs
     #[command(subcommand)]
     Runs(RunsCommand),
+    /// Commands for schedules
+    #[command(subcommand)]
+    Schedule(ScheduleCommand),
     #[command(name = "whoami")]
     /// Prints information about the current context/environment
     WhoAmI(whoami::WhoAmI),
@@ -104,6 +107,10 @@ pub enum TriggerCommand {
     Pause(triggers::Pause),
     /// Resume a paused trigger
     Resume(triggers::Resume),
+    /// List the revisions of a trigger
+    Revisions(triggers::ListRevisions),
+    /// Restore a trigger to a previous revision
+    Restore(triggers::RestoreRevision),
     /// Delete a trigger
     Delete(triggers::Delete),
 }
@@ -114,6 +121,13 @@ pub enum RunsCommand {
     View(runs::View),
 }
 
+#[derive(CliRunnable, Subcommand, Debug, Clone)]
+pub enum ScheduleCommand {
+    /// Show the next occurrences of a cron expression or a set of timepoints
+    /// without creating a trigger
+    Preview(schedules::Preview),
+}
+
 impl CommonOptions {
     pub fn base_url(&self) -> &Url {
         if self.localhost {
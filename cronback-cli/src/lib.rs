@@ -6,6 +6,7 @@ mod args;
 mod client;
 mod confirm;
 mod runs;
+mod schedules;
 mod triggers;
 mod ui;
 mod whoami;
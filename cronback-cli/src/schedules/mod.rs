@@ -0,0 +1,4 @@
+//! Schedule subcommands
+mod preview;
+
+pub(crate) use preview::Preview;
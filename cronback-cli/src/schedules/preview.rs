@@ -0,0 +1,82 @@
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset};
+use cling::prelude::*;
+use colored::Colorize;
+use serde_json::json;
+
+use crate::args::CommonOptions;
+
+#[derive(CliRunnable, CliParam, Clone, Debug, Parser)]
+#[cling(run = "preview")]
+pub struct Preview {
+    /// Cron expression (e.g. "0 0 9 * * Mon-Fri")
+    cron: Option<String>,
+    /// The timezone the cron expression is evaluated in
+    #[clap(long, requires = "cron")]
+    timezone: Option<String>,
+    /// Timepoints to preview instead of a cron expression (e.g.
+    /// "2023-10-27T09:00:00+02:00"), separated by commas
+    #[clap(
+        long,
+        value_delimiter = ',',
+        conflicts_with = "cron",
+        required_unless_present = "cron"
+    )]
+    at: Vec<DateTime<FixedOffset>>,
+    /// How many occurrences to show
+    #[clap(long, short)]
+    count: Option<u64>,
+}
+
+async fn preview(common_options: &CommonOptions, opts: &Preview) -> Result<()> {
+    let schedule = match &opts.cron {
+        | Some(cron) => {
+            let mut schedule = json!({
+                "type": "recurring",
+                "cron": cron,
+            });
+            if let Some(timezone) = &opts.timezone {
+                schedule["timezone"] = json!(timezone);
+            }
+            schedule
+        }
+        | None => {
+            json!({
+                "type": "timepoints",
+                "timepoints": opts.at,
+            })
+        }
+    };
+
+    let client = common_options.new_client()?;
+    let response = cronback_client::schedules::preview_from_json(
+        &client, schedule, opts.count,
+    )
+    .await?;
+
+    let response = response.into_inner();
+    match response {
+        | Ok(preview) => {
+            for occurrence in preview.occurrences {
+                print!(" - {}", occurrence.at.to_rfc2822());
+                match occurrence.dst_transition {
+                    | Some(transition) => {
+                        println!(
+                            " {}",
+                            format!(
+                                "(UTC offset changes from {} to {})",
+                                transition.from_offset, transition.to_offset
+                            )
+                            .yellow()
+                        );
+                    }
+                    | None => println!(),
+                }
+            }
+        }
+        | Err(bad) => {
+            return Err(bad.into());
+        }
+    };
+    Ok(())
+}
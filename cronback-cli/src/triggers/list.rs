@@ -6,6 +6,9 @@ use clap::Parser;
 use cling::prelude::*;
 use colored::Colorize;
 use cronback_client::{
+    Interval,
+    LabelSelector,
+    OnCompletionOf,
     Pagination,
     Recurring,
     RunAt,
@@ -31,9 +34,14 @@ pub struct List {
     /// and `on_demand` triggers only.
     #[clap(long)]
     status: Option<Vec<TriggerStatus>>,
+    /// Filter by labels, e.g. `team=billing,env!=staging`. Triggers must
+    /// match all the selectors.
+    #[clap(long, value_delimiter = ',')]
+    selector: Vec<LabelSelector>,
 
     #[clap(long, short)]
-    /// List all triggers, including `expired` and `cancelled` triggers.
+    /// List all triggers, including `expired` and `cancelled` triggers that
+    /// haven't been deleted yet.
     all: bool,
 }
 
@@ -51,11 +59,11 @@ async fn list(common_options: &CommonOptions, opts: &List) -> Result<()> {
         }
     };
 
-    let filter = if opts.all {
-        None
-    } else {
-        Some(TriggersFilter { status })
-    };
+    // An empty status filter matches triggers of all statuses.
+    let filter = Some(TriggersFilter {
+        status: if opts.all { vec![] } else { status },
+        label: opts.selector.clone(),
+    });
 
     let pagination = Some(Pagination {
         cursor: opts.cursor.clone(),
@@ -153,6 +161,19 @@ fn fancy_schedule(schedule: Schedule) -> String {
         if let Some(tz) = r.timezone {
             writeln!(buf, "{}", tz).unwrap();
         }
+        if let Some(start_at) = r.start_at {
+            writeln!(buf, "Starting {}", start_at.to_rfc2822()).unwrap();
+        }
+        if let Some(end_at) = r.end_at {
+            writeln!(buf, "Ending {}", end_at.to_rfc2822()).unwrap();
+        }
+        if let Some(jitter_s) = r.jitter_s {
+            writeln!(buf, "Delayed by up to {}s", jitter_s).unwrap();
+        }
+        if !r.exclude_calendars.is_empty() {
+            writeln!(buf, "Except on {}", r.exclude_calendars.join(", "))
+                .unwrap();
+        }
         if let Some(limit) = r.limit {
             write!(buf, "{} limit", limit).unwrap();
             if let Some(remaining) = r.remaining {
@@ -191,9 +212,43 @@ fn fancy_schedule(schedule: Schedule) -> String {
         buf
     }
 
+    fn fancy_interval(i: Interval) -> String {
+        let mut buf = String::new();
+        writeln!(
+            buf,
+            "Every {} seconds",
+            i.every.as_secs().unwrap_or_default()
+        )
+        .unwrap();
+        if let Some(anchor) = i.anchor {
+            writeln!(buf, "Starting {}", anchor.to_rfc2822()).unwrap();
+        }
+        if let Some(limit) = i.limit {
+            write!(buf, "{} limit", limit).unwrap();
+            if let Some(remaining) = i.remaining {
+                write!(buf, " ({} remaining)", remaining).unwrap();
+            }
+            writeln!(buf).unwrap();
+        }
+
+        buf
+    }
+
+    fn fancy_on_completion_of(o: OnCompletionOf) -> String {
+        let mut buf = String::new();
+        writeln!(buf, "On completion of '{}'", o.trigger).unwrap();
+        if let Some(outcome) = o.outcome {
+            writeln!(buf, "When {}", outcome).unwrap();
+        }
+
+        buf
+    }
+
     match schedule {
         | Schedule::Recurring(s) => fancy_recurring(s),
         | Schedule::RunAt(s) => fancy_run_at(s),
+        | Schedule::Interval(s) => fancy_interval(s),
+        | Schedule::OnCompletionOf(s) => fancy_on_completion_of(s),
         | _ => "Unknown Schedule Type!".to_string(),
     }
 }
@@ -5,6 +5,7 @@ mod delete;
 mod list;
 mod pause;
 mod resume;
+mod revisions;
 mod run;
 mod runs;
 mod view;
@@ -15,6 +16,7 @@ pub(crate) use delete::Delete;
 pub(crate) use list::List;
 pub(crate) use pause::Pause;
 pub(crate) use resume::Resume;
+pub(crate) use revisions::{ListRevisions, RestoreRevision};
 pub(crate) use run::RunArgs as Run;
 pub(crate) use runs::ListRuns;
 pub(crate) use view::View;
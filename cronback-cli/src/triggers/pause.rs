@@ -1,4 +1,5 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use cling::prelude::*;
 
 use crate::args::CommonOptions;
@@ -10,6 +11,10 @@ use crate::ui::FancyToString;
 pub struct Pause {
     /// Trigger name
     name: String,
+    /// Resume the trigger automatically at this time (e.g.
+    /// 2023-07-01T09:00:00Z)
+    #[clap(long)]
+    until: Option<DateTime<Utc>>,
 }
 
 async fn pause(common_options: &CommonOptions, opts: &Pause) -> Result<()> {
@@ -20,17 +25,22 @@ async fn pause(common_options: &CommonOptions, opts: &Pause) -> Result<()> {
     );
 
     let client = common_options.new_client()?;
-    let response =
-        cronback_client::triggers::pause(&client, &opts.name).await?;
+    let response = match opts.until {
+        | Some(until) => {
+            cronback_client::triggers::pause_until(&client, &opts.name, until)
+                .await?
+        }
+        | None => cronback_client::triggers::pause(&client, &opts.name).await?,
+    };
 
     let response = response.into_inner();
     match response {
         | Ok(trigger) => {
-            println!(
-                "Trigger '{}' is now {}!",
-                opts.name,
-                trigger.status.fancy(),
-            );
+            print!("Trigger '{}' is now {}", opts.name, trigger.status.fancy());
+            match trigger.paused_until {
+                | Some(until) => println!(" until {}!", until.to_rfc2822()),
+                | None => println!("!"),
+            }
         }
         | Err(bad) => {
             return Err(bad.into());
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use cling::prelude::*;
+use colored::Colorize;
+use cronback_client::Pagination;
+use prettytable::{row, Table};
+
+use crate::args::CommonOptions;
+use crate::confirm_or_abort;
+
+#[derive(CliRunnable, CliParam, Clone, Debug, Parser)]
+#[cling(run = "list_revisions")]
+pub struct ListRevisions {
+    /// Cursor to start listing from
+    #[clap(long)]
+    cursor: Option<String>,
+    /// Limit the number of results
+    #[clap(long, default_value = "20")]
+    limit: Option<i32>,
+    /// Trigger name
+    name: String,
+}
+
+async fn list_revisions(
+    common_options: &CommonOptions,
+    opts: &ListRevisions,
+) -> Result<()> {
+    let client = common_options.new_client()?;
+    let pagination = Some(Pagination {
+        cursor: opts.cursor.clone(),
+        limit: opts.limit,
+    });
+
+    let response = cronback_client::triggers::list_revisions(
+        &client, pagination, &opts.name,
+    )
+    .await?;
+
+    let response = response.into_inner()?;
+
+    if !response.data.is_empty() {
+        let len = response.data.len();
+
+        let mut table = Table::new();
+        table.set_titles(row!["Revision", "Created At", "Author", "Name"]);
+        for revision in response.data {
+            table.add_row(row![
+                revision.revision,
+                revision.created_at.to_rfc2822(),
+                revision.author.unwrap_or_else(|| "-".to_string()),
+                revision.trigger.name.unwrap_or_default(),
+            ]);
+        }
+
+        println!("{}", table);
+        // Print Pagination Metadata
+        eprintln!("{len} Revisions Shown");
+        if let Some(next_page_cursor) = response.meta.next_cursor {
+            eprintln!(
+                "View next page by {}{}",
+                "--cursor=".bold(),
+                next_page_cursor.bold()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[derive(CliRunnable, CliParam, Clone, Debug, Parser)]
+#[cling(run = "restore_revision")]
+pub struct RestoreRevision {
+    /// Trigger name
+    name: String,
+    /// The revision to restore
+    revision: u64,
+}
+
+async fn restore_revision(
+    common_options: &CommonOptions,
+    opts: &RestoreRevision,
+) -> Result<()> {
+    confirm_or_abort!(
+        common_options,
+        "Are you sure you want to restore the trigger '{}' to revision {}?",
+        opts.name,
+        opts.revision
+    );
+
+    let client = common_options.new_client()?;
+    let response = cronback_client::triggers::restore_revision(
+        &client,
+        &opts.name,
+        opts.revision,
+    )
+    .await?;
+
+    let trigger = response.into_inner()?;
+    println!(
+        "Trigger '{}' was restored to revision {}",
+        trigger.name.unwrap_or_default(),
+        opts.revision
+    );
+    Ok(())
+}
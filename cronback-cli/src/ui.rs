@@ -65,6 +65,9 @@ impl FancyToString for RunStatus {
             | RunStatus::Succeeded => {
                 format!("{}{}", emoji("✅"), self.to_string().green())
             }
+            | RunStatus::Skipped => {
+                format!("{}{}", emoji("⏭"), self.to_string().dimmed())
+            }
             | s => s.to_string(),
         }
     }
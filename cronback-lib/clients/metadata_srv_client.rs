@@ -36,9 +36,15 @@ impl GrpcClientType for ScopedMetadataSvcClient {
         channel: tonic::transport::Channel,
         interceptor: GrpcRequestInterceptor,
     ) -> Self {
-        let client =
-            GenMetadataSvcClient::with_interceptor(channel, interceptor);
+        let client = Self::create_raw_client(channel, interceptor);
 
         Self(ScopedGrpcClient::new(project_id, request_id, client))
     }
+
+    fn create_raw_client(
+        channel: tonic::transport::Channel,
+        interceptor: GrpcRequestInterceptor,
+    ) -> Self::RawGrpcClient {
+        GenMetadataSvcClient::with_interceptor(channel, interceptor)
+    }
 }
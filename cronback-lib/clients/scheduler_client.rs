@@ -8,7 +8,7 @@ use tonic::codegen::InterceptedService;
 use crate::config::MainConfig;
 use crate::grpc_client_provider::{GrpcClientType, ScopedGrpcClient};
 use crate::grpc_helpers::GrpcRequestInterceptor;
-use crate::prelude::ValidShardedId;
+use crate::prelude::{Shard, ValidShardedId};
 use crate::types::{ProjectId, RequestId};
 
 type SchedulerSvcClient = GenSchedulerSvcClient<
@@ -30,15 +30,33 @@ impl GrpcClientType for ScopedSchedulerSvcClient {
         &config.scheduler_cell_map
     }
 
+    fn cell_for_shard(config: &MainConfig, shard: Shard) -> u64 {
+        config
+            .scheduler_shard_map
+            .get(&shard.0)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn has_movable_shards() -> bool {
+        true
+    }
+
     fn create_scoped_client(
         project_id: ValidShardedId<ProjectId>,
         request_id: RequestId,
         channel: tonic::transport::Channel,
         interceptor: GrpcRequestInterceptor,
     ) -> Self {
-        let client =
-            GenSchedulerSvcClient::with_interceptor(channel, interceptor);
+        let client = Self::create_raw_client(channel, interceptor);
 
         Self(ScopedGrpcClient::new(project_id, request_id, client))
     }
+
+    fn create_raw_client(
+        channel: tonic::transport::Channel,
+        interceptor: GrpcRequestInterceptor,
+    ) -> Self::RawGrpcClient {
+        GenSchedulerSvcClient::with_interceptor(channel, interceptor)
+    }
 }
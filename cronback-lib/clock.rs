@@ -0,0 +1,78 @@
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// The source of the current time for services that schedule work. Services
+/// read the time through the clock of their [`ServiceContext`] instead of
+/// calling `Utc::now()` so that tests can control it.
+///
+/// [`ServiceContext`]: crate::service::ServiceContext
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The wall clock of the machine.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to. Moving it backwards simulates wall
+/// clock adjustments.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    /// Moves the clock by `duration`, which can be negative.
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn manual_clock_moves_only_when_told() {
+        let start = Utc.with_ymd_and_hms(2023, 3, 26, 0, 30, 0).unwrap();
+        let clock = ManualClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::hours(1));
+        assert_eq!(clock.now(), start + Duration::hours(1));
+
+        // Backwards jumps are allowed.
+        clock.advance(Duration::minutes(-90));
+        assert_eq!(clock.now(), start - Duration::minutes(30));
+
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+    }
+}
@@ -58,6 +58,9 @@ pub struct MainConfig {
     pub dispatcher_cell_map: HashMap<u64, String>,
     pub scheduler_cell_map: HashMap<u64, String>,
     pub metadata_cell_map: HashMap<u64, String>,
+    // Maps shards that don't live on scheduler cell 0 to their cell.
+    #[serde(default)]
+    pub scheduler_shard_map: HashMap<u64, u64>,
 }
 
 #[derive(Clone)]
@@ -4,8 +4,14 @@ pub static PROJECT_ID_HEADER: &str = "x-cronback-project-id";
 
 // Those are internal cross-service headers.
 pub static PARENT_SPAN_HEADER: &str = "x-cronback-parent-span-id";
+/// Set on errors of a cell that no longer serves the shard of the project.
+pub static SHARD_MOVED_HEADER: &str = "x-cronback-shard-moved";
 
 // Headers we send out to webhook endpoints
 pub static DELIVERY_ATTEMPT_NUM_HEADER: &str =
     "x-cronback-delivery-attempt-number";
 pub static RUN_ID_HEADER: &str = "x-cronback-run-id";
+/// Set if the project has signing secrets, formatted as
+/// `t=<unix timestamp>,v1=<signature>[,v1=<signature>]` with one signature
+/// per active secret.
+pub static SIGNATURE_HEADER: &str = "x-cronback-signature";
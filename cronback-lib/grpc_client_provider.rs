@@ -1,13 +1,18 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use derive_more::{Deref, DerefMut};
+use proto::metadata_svc::GetShardAssignmentsRequest;
 use thiserror::Error;
 use tonic::transport::{Channel, Endpoint};
+use tracing::warn;
 
+use crate::clients::ScopedMetadataSvcClient;
 use crate::config::MainConfig;
+use crate::consts::SHARD_MOVED_HEADER;
 use crate::model::ValidShardedId;
 use crate::prelude::{GrpcRequestInterceptor, Shard};
 use crate::types::{ProjectId, RequestId};
@@ -52,7 +57,7 @@ impl<T> ScopedGrpcClient<T> {
 
 #[async_trait]
 pub trait GrpcClientType: Sync + Send {
-    type RawGrpcClient;
+    type RawGrpcClient: Send;
 
     fn create_scoped_client(
         project_id: ValidShardedId<ProjectId>,
@@ -61,6 +66,11 @@ pub trait GrpcClientType: Sync + Send {
         interceptor: GrpcRequestInterceptor,
     ) -> Self;
 
+    fn create_raw_client(
+        channel: tonic::transport::Channel,
+        interceptor: GrpcRequestInterceptor,
+    ) -> Self::RawGrpcClient;
+
     fn get_mut(&mut self) -> &mut ScopedGrpcClient<Self::RawGrpcClient>;
 
     // Concrete default implementations
@@ -76,18 +86,26 @@ pub trait GrpcClientType: Sync + Send {
 
     fn address_map(config: &MainConfig) -> &HashMap<u64, String>;
 
+    // For now, we'll assume everything is on Cell 0 unless the service
+    // supports routing shards to other cells.
+    fn cell_for_shard(_config: &MainConfig, _shard: Shard) -> u64 {
+        0
+    }
+
+    // Whether shards of the service can be moved between cells at runtime,
+    // the shards that were moved are recorded by the metadata service.
+    fn has_movable_shards() -> bool {
+        false
+    }
+
     fn get_address(
         config: &MainConfig,
-        _project_id: &ValidShardedId<ProjectId>,
+        cell_id: u64,
     ) -> Result<String, GrpcClientError> {
-        // For now, we'll assume everything is on Cell 0
-        // TODO: support multiple cells
-        let shard = Shard(0);
-
         let address =
-            Self::address_map(config).get(&shard.0).ok_or_else(|| {
+            Self::address_map(config).get(&cell_id).ok_or_else(|| {
                 GrpcClientError::Routing(format!(
-                    "No endpoint was found for shard {shard} in config (grpc \
+                    "No endpoint was found for cell {cell_id} in config (grpc \
                      client type {:?})",
                     std::any::type_name::<Self>(),
                 ))
@@ -98,12 +116,50 @@ pub trait GrpcClientType: Sync + Send {
 
 #[async_trait]
 pub trait GrpcClientFactory: Send + Sync {
-    type ClientType;
+    type ClientType: GrpcClientType;
     async fn get_client(
         &self,
         request_id: &RequestId,
         project_id: &ValidShardedId<ProjectId>,
     ) -> Result<Self::ClientType, GrpcClientError>;
+
+    /// Connects to a cell directly, bypassing shard routing. Meant for
+    /// operations that manage the cells themselves.
+    async fn get_cell_client(
+        &self,
+        request_id: &RequestId,
+        cell_id: u64,
+    ) -> Result<
+        <Self::ClientType as GrpcClientType>::RawGrpcClient,
+        GrpcClientError,
+    >;
+
+    /// Routes the shards to `cell_id` from now on, taking precedence over the
+    /// routing in config. The move must be recorded by the metadata service
+    /// for other processes to pick it up.
+    fn route_shards(&self, shards: &[Shard], cell_id: u64);
+
+    /// Reloads the shard assignments from the metadata service on the next
+    /// request. Meant for when a cell reports that it doesn't serve a shard
+    /// anymore, see `is_shard_moved`.
+    fn expire_shard_routes(&self);
+}
+
+/// Whether the cell rejected the request because the shard of the project
+/// was moved to another cell.
+pub fn is_shard_moved(status: &tonic::Status) -> bool {
+    status.metadata().contains_key(SHARD_MOVED_HEADER)
+}
+
+// How long the shard assignments loaded from the metadata service are used
+// before being reloaded.
+const SHARD_ROUTES_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+struct ShardRoutes {
+    // Shards that were moved to another cell at runtime.
+    routes: HashMap<Shard, u64>,
+    loaded_at: Option<Instant>,
 }
 
 // A concrete channel-caching implementation of the GrpcClientFactory used in
@@ -111,6 +167,7 @@ pub trait GrpcClientFactory: Send + Sync {
 pub struct GrpcClientProvider<T> {
     config: Config,
     channel_cache: RwLock<HashMap<String, Channel>>,
+    shard_routes: RwLock<ShardRoutes>,
     phantom: std::marker::PhantomData<T>,
 }
 
@@ -119,9 +176,97 @@ impl<T: GrpcClientType> GrpcClientProvider<T> {
         Self {
             config,
             channel_cache: Default::default(),
+            shard_routes: Default::default(),
             phantom: Default::default(),
         }
     }
+
+    async fn get_channel(
+        &self,
+        address: &str,
+    ) -> Result<Channel, GrpcClientError> {
+        {
+            let cache = self.channel_cache.read().unwrap();
+            if let Some(ch) = cache.get(address) {
+                return Ok(ch.clone());
+            }
+        }
+        // We attempt to create a new channel anyway because we don't want
+        // to block the write lock during connection.
+        let temp_new_ch = T::create_channel(address).await?;
+        // Only upgrade to a write lock if we need to create a new
+        let mut cache = self.channel_cache.write().unwrap();
+        // check again, someone might have already created the channel
+        if let Some(ch) = cache.get(address) {
+            // temp_new_ch dropped here
+            Ok(ch.clone())
+        } else {
+            cache.insert(address.to_owned(), temp_new_ch.clone());
+            Ok(temp_new_ch)
+        }
+    }
+
+    async fn cell_for_shard(&self, config: &MainConfig, shard: Shard) -> u64 {
+        // Shards can only move if there is more than one cell to move them
+        // to.
+        if T::has_movable_shards() && T::address_map(config).len() > 1 {
+            let expired = self
+                .shard_routes
+                .read()
+                .unwrap()
+                .loaded_at
+                .map(|loaded_at| loaded_at.elapsed() >= SHARD_ROUTES_TTL)
+                .unwrap_or(true);
+            if expired {
+                self.reload_shard_routes(config).await;
+            }
+        }
+        self.shard_routes
+            .read()
+            .unwrap()
+            .routes
+            .get(&shard)
+            .copied()
+            .unwrap_or_else(|| T::cell_for_shard(config, shard))
+    }
+
+    async fn reload_shard_routes(&self, config: &MainConfig) {
+        let res = self.fetch_shard_routes(config).await;
+        let mut shard_routes = self.shard_routes.write().unwrap();
+        // On failure, we keep routing with what we know and try again once
+        // the routes expire again.
+        shard_routes.loaded_at = Some(Instant::now());
+        match res {
+            | Ok(routes) => shard_routes.routes = routes,
+            | Err(e) => {
+                warn!("Failed to load the shard assignments: {e}");
+            }
+        }
+    }
+
+    async fn fetch_shard_routes(
+        &self,
+        config: &MainConfig,
+    ) -> Result<HashMap<Shard, u64>, GrpcClientError> {
+        let address = ScopedMetadataSvcClient::get_address(config, 0)?;
+        let channel = self.get_channel(&address).await?;
+        let interceptor = GrpcRequestInterceptor {
+            project_id: None,
+            request_id: Some(RequestId::new()),
+        };
+        let mut client =
+            ScopedMetadataSvcClient::create_raw_client(channel, interceptor);
+        let assignments = client
+            .get_shard_assignments(GetShardAssignmentsRequest {})
+            .await
+            .map_err(|e| GrpcClientError::Routing(e.to_string()))?
+            .into_inner()
+            .assignments;
+        Ok(assignments
+            .into_iter()
+            .map(|a| (Shard(a.shard), a.cell_id))
+            .collect())
+    }
 }
 
 #[async_trait]
@@ -135,34 +280,9 @@ impl<T: GrpcClientType> GrpcClientFactory for GrpcClientProvider<T> {
     ) -> Result<Self::ClientType, GrpcClientError> {
         let config = self.config.get_main();
         // resolve shard -> cell
-        let address = T::get_address(&config, project_id)?;
-
-        let mut channel = None;
-        {
-            let cache = self.channel_cache.read().unwrap();
-            if let Some(ch) = cache.get(&address) {
-                channel = Some(ch.clone());
-            }
-        }
-        if channel.is_none() {
-            // We attempt to create a new channel anyway because we don't want
-            // to block the write lock during connection.
-            let temp_new_ch = T::create_channel(&address).await?;
-            {
-                // Only upgrade to a write lock if we need to create a new
-                let mut cache = self.channel_cache.write().unwrap();
-                // check again, someone might have already created the channel
-                if let Some(ch) = cache.get(&address) {
-                    channel = Some(ch.clone());
-                    // temp_new_ch dropped here
-                } else {
-                    cache.insert(address.clone(), temp_new_ch.clone());
-                    channel = Some(temp_new_ch);
-                }
-            }
-        }
-
-        assert!(channel.is_some());
+        let cell_id = self.cell_for_shard(&config, project_id.shard()).await;
+        let address = T::get_address(&config, cell_id)?;
+        let channel = self.get_channel(&address).await?;
 
         let interceptor = GrpcRequestInterceptor {
             project_id: Some(project_id.clone()),
@@ -172,10 +292,38 @@ impl<T: GrpcClientType> GrpcClientFactory for GrpcClientProvider<T> {
         Ok(T::create_scoped_client(
             project_id.clone(),
             request_id.clone(),
-            channel.unwrap(),
+            channel,
             interceptor,
         ))
     }
+
+    async fn get_cell_client(
+        &self,
+        request_id: &RequestId,
+        cell_id: u64,
+    ) -> Result<T::RawGrpcClient, GrpcClientError> {
+        let config = self.config.get_main();
+        let address = T::get_address(&config, cell_id)?;
+        let channel = self.get_channel(&address).await?;
+
+        let interceptor = GrpcRequestInterceptor {
+            project_id: None,
+            request_id: Some(request_id.clone()),
+        };
+        Ok(T::create_raw_client(channel, interceptor))
+    }
+
+    fn route_shards(&self, shards: &[Shard], cell_id: u64) {
+        // All shards switch over at once.
+        let mut shard_routes = self.shard_routes.write().unwrap();
+        for shard in shards {
+            shard_routes.routes.insert(*shard, cell_id);
+        }
+    }
+
+    fn expire_shard_routes(&self) {
+        self.shard_routes.write().unwrap().loaded_at = None;
+    }
 }
 
 pub mod grpc_test_helpers {
@@ -192,6 +340,7 @@ pub mod grpc_test_helpers {
     pub struct TestGrpcClientProvider<T> {
         cell_to_socket_path: HashMap<u16, Arc<TempPath>>,
         channel_cache: RwLock<HashMap<u16, Channel>>,
+        shard_routes: RwLock<HashMap<Shard, u16>>,
         phantom: std::marker::PhantomData<T>,
     }
 
@@ -200,6 +349,7 @@ pub mod grpc_test_helpers {
             Self {
                 cell_to_socket_path,
                 channel_cache: Default::default(),
+                shard_routes: Default::default(),
                 phantom: Default::default(),
             }
         }
@@ -211,67 +361,71 @@ pub mod grpc_test_helpers {
             Self {
                 cell_to_socket_path,
                 channel_cache: Default::default(),
+                shard_routes: Default::default(),
                 phantom: Default::default(),
             }
         }
-    }
-
-    #[async_trait]
-    impl<T: GrpcClientType> GrpcClientFactory for TestGrpcClientProvider<T> {
-        type ClientType = T;
 
-        async fn get_client(
+        async fn get_channel(
             &self,
-            request_id: &RequestId,
-            project_id: &ValidShardedId<ProjectId>,
-        ) -> Result<Self::ClientType, GrpcClientError> {
-            // do we have a channel in cache?
-            // TODO: support multiple cells
-            let _shard = Shard(0);
-            let cell: u16 = 0;
-
+            cell: u16,
+        ) -> Result<Channel, GrpcClientError> {
             let socket = self
                 .cell_to_socket_path
                 .get(&cell)
                 .expect("Cell not found!")
                 .clone();
 
-            let mut channel = None;
             {
                 let cache = self.channel_cache.read().unwrap();
                 if let Some(ch) = cache.get(&cell) {
-                    channel = Some(ch.clone());
+                    return Ok(ch.clone());
                 }
             }
-            if channel.is_none() {
-                // We attempt to create a new channel anyway because we don't
-                // want to block the write lock during
-                // connection.
-                // Connect to the server over a Unix socket. The URL will be
-                // ignored.
-                let temp_new_ch = Endpoint::try_from("http://example.url")
-                    .unwrap()
-                    .connect_with_connector(service_fn(move |_: Uri| {
-                        let socket = Arc::clone(&socket);
-                        async move { UnixStream::connect(&*socket).await }
-                    }))
-                    .await?;
-                {
-                    // Only upgrade to a write lock if we need to create a new
-                    let mut cache = self.channel_cache.write().unwrap();
-                    // check again, someone might have already created the
-                    // channel
-                    if let Some(ch) = cache.get(&cell) {
-                        channel = Some(ch.clone());
-                        // temp_new_ch dropped here
-                    } else {
-                        cache.insert(cell, temp_new_ch.clone());
-                        channel = Some(temp_new_ch);
-                    }
-                }
+            // We attempt to create a new channel anyway because we don't
+            // want to block the write lock during
+            // connection.
+            // Connect to the server over a Unix socket. The URL will be
+            // ignored.
+            let temp_new_ch = Endpoint::try_from("http://example.url")
+                .unwrap()
+                .connect_with_connector(service_fn(move |_: Uri| {
+                    let socket = Arc::clone(&socket);
+                    async move { UnixStream::connect(&*socket).await }
+                }))
+                .await?;
+            // Only upgrade to a write lock if we need to create a new
+            let mut cache = self.channel_cache.write().unwrap();
+            // check again, someone might have already created the
+            // channel
+            if let Some(ch) = cache.get(&cell) {
+                // temp_new_ch dropped here
+                Ok(ch.clone())
+            } else {
+                cache.insert(cell, temp_new_ch.clone());
+                Ok(temp_new_ch)
             }
+        }
+    }
+
+    #[async_trait]
+    impl<T: GrpcClientType> GrpcClientFactory for TestGrpcClientProvider<T> {
+        type ClientType = T;
 
-            assert!(channel.is_some());
+        async fn get_client(
+            &self,
+            request_id: &RequestId,
+            project_id: &ValidShardedId<ProjectId>,
+        ) -> Result<Self::ClientType, GrpcClientError> {
+            // Everything is on cell 0 unless the shard has been moved.
+            let cell = self
+                .shard_routes
+                .read()
+                .unwrap()
+                .get(&project_id.shard())
+                .copied()
+                .unwrap_or(0);
+            let channel = self.get_channel(cell).await?;
 
             let interceptor = GrpcRequestInterceptor {
                 project_id: Some(project_id.clone()),
@@ -281,9 +435,39 @@ pub mod grpc_test_helpers {
             Ok(T::create_scoped_client(
                 project_id.clone(),
                 request_id.clone(),
-                channel.unwrap(),
+                channel,
                 interceptor,
             ))
         }
+
+        async fn get_cell_client(
+            &self,
+            request_id: &RequestId,
+            cell_id: u64,
+        ) -> Result<T::RawGrpcClient, GrpcClientError> {
+            let cell = u16::try_from(cell_id).map_err(|_| {
+                GrpcClientError::Routing(format!("Unknown cell {cell_id}"))
+            })?;
+            let channel = self.get_channel(cell).await?;
+
+            let interceptor = GrpcRequestInterceptor {
+                project_id: None,
+                request_id: Some(request_id.clone()),
+            };
+            Ok(T::create_raw_client(channel, interceptor))
+        }
+
+        fn route_shards(&self, shards: &[Shard], cell_id: u64) {
+            let cell = u16::try_from(cell_id).expect("Cell not found!");
+            let mut routes = self.shard_routes.write().unwrap();
+            for shard in shards {
+                routes.insert(*shard, cell);
+            }
+        }
+
+        fn expire_shard_routes(&self) {
+            // Routes are only changed by `route_shards`, there is nothing to
+            // reload.
+        }
     }
 }
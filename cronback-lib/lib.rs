@@ -1,3 +1,4 @@
+mod clock;
 mod config;
 mod consts;
 mod database;
@@ -23,6 +24,7 @@ pub use shutdown::*;
 pub use crate::config::*;
 
 pub mod prelude {
+    pub use crate::clock::*;
     pub use crate::consts::*;
     pub use crate::database::*;
     pub use crate::events::e;
@@ -7,7 +7,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 use ulid::Ulid;
 
-const SHARD_COUNT: u64 = 1031;
+pub const SHARD_COUNT: u64 = 1031;
 
 #[derive(Debug, Error)]
 pub enum ModelIdError {
@@ -29,6 +29,7 @@ impl From<ModelIdError> for tonic::Status {
     Default,
     Eq,
     PartialEq,
+    Hash,
     Serialize,
     Deserialize,
     PartialOrd,
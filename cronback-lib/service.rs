@@ -2,6 +2,7 @@ use std::convert::Infallible;
 use std::error::Error;
 use std::net::SocketAddr;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -21,6 +22,7 @@ use tower::Service;
 use tower_http::trace::{MakeSpan, TraceLayer};
 use tracing::{error, error_span, info, Id, Span};
 
+use crate::clock::{Clock, SystemClock};
 use crate::config::Config;
 use crate::consts::{PARENT_SPAN_HEADER, PROJECT_ID_HEADER, REQUEST_ID_HEADER};
 use crate::database::{Database, DatabaseError, DbMigration};
@@ -87,6 +89,7 @@ pub trait CronbackService: Send + Sync + Sized + Clone + 'static {
 pub struct ServiceContext<S> {
     config: Config,
     shutdown: Shutdown,
+    clock: Arc<dyn Clock>,
     _service: std::marker::PhantomData<S>,
 }
 
@@ -98,10 +101,21 @@ where
         Self {
             config,
             shutdown,
+            clock: Arc::new(SystemClock),
             _service: Default::default(),
         }
     }
 
+    /// Replaces the system clock, tests use this to control time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
     pub fn service_name(&self) -> &'static str {
         S::ROLE
     }
@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use dto::{FromProto, IntoProto};
 use serde::{Deserialize, Serialize};
+use tracing::error;
 use validator::Validate;
 
 #[derive(
@@ -38,6 +39,133 @@ pub struct Webhook {
     pub timeout_s: std::time::Duration,
     // None means no retry
     pub retry: Option<RetryConfig>,
+    // None means that any 2xx response is a success
+    pub success_criteria: Option<SuccessCriteria>,
+}
+
+/// The response body is only checked up to this many bytes.
+pub const SUCCESS_CRITERIA_BODY_LIMIT: usize = 64 * 1024;
+
+#[derive(
+    Debug,
+    FromProto,
+    IntoProto,
+    Clone,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Default,
+)]
+#[proto(target = "proto::common::SuccessCriteria")]
+pub struct SuccessCriteria {
+    // Empty means 2xx.
+    pub status_codes: Vec<StatusCodeRange>,
+    pub json_pointer: Option<JsonPointerEquals>,
+    pub body_contains: Option<String>,
+}
+
+impl SuccessCriteria {
+    /// Whether the criteria look at the response body.
+    pub fn needs_body(&self) -> bool {
+        self.json_pointer.is_some() || self.body_contains.is_some()
+    }
+
+    /// Returns the criterion that the response doesn't match, if any. `body`
+    /// is a prefix of the response body, at most
+    /// [`SUCCESS_CRITERIA_BODY_LIMIT`] bytes long.
+    pub fn failed_criterion(
+        &self,
+        status_code: u32,
+        body: &[u8],
+    ) -> Option<String> {
+        let status_matches = if self.status_codes.is_empty() {
+            (200..=299).contains(&status_code)
+        } else {
+            self.status_codes.iter().any(|r| r.contains(status_code))
+        };
+        if !status_matches {
+            return Some(format!("Status code {status_code} is not a success"));
+        }
+
+        if let Some(json_pointer) = &self.json_pointer {
+            if json_pointer.invalid {
+                return Some(format!(
+                    "Expected value at '{}' is not valid JSON",
+                    json_pointer.pointer
+                ));
+            }
+            let value = serde_json::from_slice::<serde_json::Value>(body).ok();
+            let actual = value
+                .as_ref()
+                .and_then(|v| v.pointer(&json_pointer.pointer));
+            if actual != Some(&json_pointer.equals) {
+                return Some(format!(
+                    "Value at '{}' in the response body is {}, expected {}",
+                    json_pointer.pointer,
+                    actual.map_or_else(
+                        || "missing".to_string(),
+                        |v| v.to_string()
+                    ),
+                    json_pointer.equals,
+                ));
+            }
+        }
+
+        if let Some(substring) = &self.body_contains {
+            if !String::from_utf8_lossy(body).contains(substring.as_str()) {
+                return Some(format!(
+                    "Response body doesn't contain '{substring}'"
+                ));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, IntoProto, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[proto(target = "proto::common::JsonPointerEquals")]
+pub struct JsonPointerEquals {
+    pub pointer: String,
+    #[proto(name = "equals_json")]
+    #[into_proto(map = "json_to_string", map_by_ref)]
+    pub equals: serde_json::Value,
+    // Set if `equals` couldn't be parsed out of the proto, the criterion
+    // never matches then.
+    #[serde(skip)]
+    invalid: bool,
+}
+
+impl From<proto::common::JsonPointerEquals> for JsonPointerEquals {
+    fn from(value: proto::common::JsonPointerEquals) -> Self {
+        // The API validates the value before it gets here.
+        match serde_json::from_str(&value.equals_json) {
+            | Ok(equals) => {
+                Self {
+                    pointer: value.pointer,
+                    equals,
+                    invalid: false,
+                }
+            }
+            | Err(e) => {
+                error!(
+                    pointer = value.pointer,
+                    "Expected value '{}' of the success criteria is not valid \
+                     JSON: {e}",
+                    value.equals_json,
+                );
+                Self {
+                    pointer: value.pointer,
+                    equals: serde_json::Value::Null,
+                    invalid: true,
+                }
+            }
+        }
+    }
+}
+
+fn json_to_string(value: &serde_json::Value) -> String {
+    value.to_string()
 }
 
 #[derive(
@@ -60,6 +188,8 @@ pub struct SimpleRetry {
     #[into_proto(map = "std::time::Duration::as_secs_f64", map_by_ref)]
     #[from_proto(map = "Duration::from_secs_f64")]
     pub delay_s: Duration,
+    pub retry_on: Option<RetryOn>,
+    pub respect_retry_after: Option<RespectRetryAfter>,
 }
 
 #[derive(
@@ -74,4 +204,190 @@ pub struct ExponentialBackoffRetry {
     #[into_proto(map = "std::time::Duration::as_secs_f64", map_by_ref)]
     #[from_proto(map = "Duration::from_secs_f64")]
     pub max_delay_s: Duration,
+    pub retry_on: Option<RetryOn>,
+    pub respect_retry_after: Option<RespectRetryAfter>,
+}
+
+impl RetryConfig {
+    /// The failures that are retried, all of them if unset.
+    pub fn retry_on(&self) -> Option<&RetryOn> {
+        match self {
+            | RetryConfig::SimpleRetry(c) => c.retry_on.as_ref(),
+            | RetryConfig::ExponentialBackoffRetry(c) => c.retry_on.as_ref(),
+        }
+    }
+
+    pub fn respect_retry_after(&self) -> Option<&RespectRetryAfter> {
+        match self {
+            | RetryConfig::SimpleRetry(c) => c.respect_retry_after.as_ref(),
+            | RetryConfig::ExponentialBackoffRetry(c) => {
+                c.respect_retry_after.as_ref()
+            }
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    FromProto,
+    IntoProto,
+    Clone,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Default,
+)]
+#[proto(target = "proto::common::RetryOn")]
+pub struct RetryOn {
+    pub status_codes: Vec<StatusCodeRange>,
+    pub connection_errors: bool,
+    pub timeouts: bool,
+}
+
+impl RetryOn {
+    pub fn status_code(&self, code: u32) -> bool {
+        self.status_codes.iter().any(|r| r.contains(code))
+    }
+}
+
+// Inclusive on both ends.
+#[derive(
+    Debug, FromProto, IntoProto, Clone, Serialize, Deserialize, PartialEq, Eq,
+)]
+#[proto(target = "proto::common::StatusCodeRange")]
+pub struct StatusCodeRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl StatusCodeRange {
+    pub fn contains(&self, code: u32) -> bool {
+        (self.start..=self.end).contains(&code)
+    }
+}
+
+#[derive(
+    Debug, FromProto, IntoProto, Clone, Serialize, Deserialize, PartialEq, Eq,
+)]
+#[proto(target = "proto::common::RespectRetryAfter")]
+pub struct RespectRetryAfter {
+    // Longer delays asked by the receiver are capped to this.
+    #[into_proto(map = "std::time::Duration::as_secs_f64", map_by_ref)]
+    #[from_proto(map = "Duration::from_secs_f64")]
+    pub max_delay_s: Duration,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn criteria(
+        status_codes: Vec<StatusCodeRange>,
+        json_pointer: Option<JsonPointerEquals>,
+        body_contains: Option<&str>,
+    ) -> SuccessCriteria {
+        SuccessCriteria {
+            status_codes,
+            json_pointer,
+            body_contains: body_contains.map(ToOwned::to_owned),
+        }
+    }
+
+    #[test]
+    fn success_criteria_status_codes() {
+        let default = criteria(vec![], None, None);
+        assert_eq!(default.failed_criterion(200, b""), None);
+        assert_eq!(default.failed_criterion(204, b""), None);
+        assert_eq!(
+            default.failed_criterion(302, b""),
+            Some("Status code 302 is not a success".to_string())
+        );
+
+        let custom = criteria(
+            vec![
+                StatusCodeRange {
+                    start: 202,
+                    end: 202,
+                },
+                StatusCodeRange {
+                    start: 300,
+                    end: 399,
+                },
+            ],
+            None,
+            None,
+        );
+        assert_eq!(custom.failed_criterion(202, b""), None);
+        assert_eq!(custom.failed_criterion(302, b""), None);
+        assert!(custom.failed_criterion(200, b"").is_some());
+        assert!(!custom.needs_body());
+    }
+
+    #[test]
+    fn success_criteria_body() {
+        let json_pointer = criteria(
+            vec![],
+            Some(JsonPointerEquals {
+                pointer: "/result/ok".to_string(),
+                equals: json!(true),
+                invalid: false,
+            }),
+            None,
+        );
+        assert!(json_pointer.needs_body());
+        assert_eq!(
+            json_pointer.failed_criterion(200, br#"{"result": {"ok": true}}"#),
+            None
+        );
+        assert_eq!(
+            json_pointer.failed_criterion(200, br#"{"result": {"ok": false}}"#),
+            Some(
+                "Value at '/result/ok' in the response body is false, \
+                 expected true"
+                    .to_string()
+            )
+        );
+        // Bodies that aren't JSON, or got truncated, never match.
+        assert_eq!(
+            json_pointer.failed_criterion(200, br#"{"result": {"ok": tr"#),
+            Some(
+                "Value at '/result/ok' in the response body is missing, \
+                 expected true"
+                    .to_string()
+            )
+        );
+        // The status code is checked first.
+        assert_eq!(
+            json_pointer.failed_criterion(500, br#"{"result": {"ok": true}}"#),
+            Some("Status code 500 is not a success".to_string())
+        );
+
+        let body_contains = criteria(vec![], None, Some("DONE"));
+        assert_eq!(body_contains.failed_criterion(200, b"STATUS: DONE"), None);
+        assert_eq!(
+            body_contains.failed_criterion(200, b"STATUS: PENDING"),
+            Some("Response body doesn't contain 'DONE'".to_string())
+        );
+    }
+
+    #[test]
+    fn success_criteria_invalid_json_pointer_value() {
+        let json_pointer: JsonPointerEquals =
+            proto::common::JsonPointerEquals {
+                pointer: "/result/ok".to_string(),
+                equals_json: "{not json".to_string(),
+            }
+            .into();
+        // A corrupt expected value doesn't turn into `null`.
+        let criteria = criteria(vec![], Some(json_pointer), None);
+        assert_eq!(
+            criteria.failed_criterion(200, br#"{"result": {"ok": null}}"#),
+            Some(
+                "Expected value at '/result/ok' is not valid JSON".to_string()
+            )
+        );
+    }
 }
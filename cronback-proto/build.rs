@@ -14,6 +14,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .compile(
             &[
                 "./attempts.proto",
+                "./calendars.proto",
                 "./common.proto",
                 "./dispatcher_svc.proto",
                 "./events.proto",
@@ -33,6 +34,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Add more packages here as needed.
         .build(&[
             ".attempts",
+            ".calendars",
             ".common",
             ".events",
             ".projects",
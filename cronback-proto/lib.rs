@@ -80,6 +80,11 @@ pub mod projects {
     include!(concat!(env!("OUT_DIR"), "/projects.serde.rs"));
 }
 
+pub mod calendars {
+    tonic::include_proto!("calendars");
+    include!(concat!(env!("OUT_DIR"), "/calendars.serde.rs"));
+}
+
 pub mod notifications {
     tonic::include_proto!("notifications");
 }
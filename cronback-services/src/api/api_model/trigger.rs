@@ -22,11 +22,13 @@ impl UpsertTriggerRequest {
         self,
         trigger_name: Option<String>,
         precondition: Option<RequestPrecondition>,
+        author: String,
     ) -> scheduler_svc::UpsertTriggerRequest {
         scheduler_svc::UpsertTriggerRequest {
             precondition,
             trigger_name,
             trigger: Some(self.trigger.into()),
+            author: Some(author),
         }
     }
 }
@@ -11,6 +11,14 @@ use super::errors::ApiError;
 use super::AppState;
 
 const ON_BEHALF_OF_HEADER_NAME: &str = "X-On-Behalf-Of";
+// Recorded as the author of changes made with an admin key.
+const ADMIN_AUTHOR: &str = "admin";
+
+/// Who is making the request, it's the id of the API key used or
+/// [ADMIN_AUTHOR] for admin keys. It's injected in the request extensions of
+/// authenticated requests.
+#[derive(Clone, Debug)]
+pub struct Author(pub String);
 
 // Partial state from the main app state to facilitate writing tests for the
 // middleware.
@@ -31,18 +39,30 @@ impl FromRef<Arc<AppState>> for AuthenticationState {
 
 enum AuthenticationStatus {
     Unauthenticated,
-    Authenticated(ValidShardedId<ProjectId>),
+    Authenticated(ValidShardedId<ProjectId>, /* key_id */ String),
     Admin(Option<ValidShardedId<ProjectId>>),
 }
 
 impl AuthenticationStatus {
     fn project_id(&self) -> Option<ValidShardedId<ProjectId>> {
         match self {
-            | AuthenticationStatus::Authenticated(p) => Some(p.clone()),
+            | AuthenticationStatus::Authenticated(p, _) => Some(p.clone()),
             | AuthenticationStatus::Admin(Some(p)) => Some(p.clone()),
             | _ => None,
         }
     }
+
+    fn author(&self) -> Option<Author> {
+        match self {
+            | AuthenticationStatus::Authenticated(_, key_id) => {
+                Some(Author(key_id.clone()))
+            }
+            | AuthenticationStatus::Admin(_) => {
+                Some(Author(ADMIN_AUTHOR.to_string()))
+            }
+            | AuthenticationStatus::Unauthenticated => None,
+        }
+    }
 }
 
 /// Parses the AUTHORIZATION header to extract the user provided secret key.
@@ -120,7 +140,12 @@ async fn get_auth_status<B>(
         .authenticate(&user_provided_secret)
         .await;
     match project {
-        | Ok(project_id) => Ok(AuthenticationStatus::Authenticated(project_id)),
+        | Ok(project_id) => {
+            Ok(AuthenticationStatus::Authenticated(
+                project_id,
+                user_provided_secret.key_id().to_string(),
+            ))
+        }
         | Err(AuthError::AuthFailed(_)) => {
             Ok(AuthenticationStatus::Unauthenticated)
         }
@@ -145,7 +170,7 @@ pub async fn ensure_authenticated<B>(
                 "Super privilege header(s) missing!".to_owned(),
             ))
         }
-        | AuthenticationStatus::Authenticated(_)
+        | AuthenticationStatus::Authenticated(..)
         | AuthenticationStatus::Admin(Some(_)) => Ok(next.run(req).await),
         | AuthenticationStatus::Unauthenticated => Err(ApiError::Unauthorized),
     }
@@ -168,7 +193,7 @@ pub async fn ensure_admin_for_project<B>(
                 "Super privilege header(s) missing!".to_owned(),
             ))
         }
-        | AuthenticationStatus::Authenticated(_) => Err(ApiError::Forbidden),
+        | AuthenticationStatus::Authenticated(..) => Err(ApiError::Forbidden),
         | AuthenticationStatus::Unauthenticated => Err(ApiError::Unauthorized),
     }
 }
@@ -186,7 +211,7 @@ pub async fn ensure_admin<B>(
 
     match auth {
         | AuthenticationStatus::Admin(_) => Ok(next.run(req).await),
-        | AuthenticationStatus::Authenticated(_) => Err(ApiError::Forbidden),
+        | AuthenticationStatus::Authenticated(..) => Err(ApiError::Forbidden),
         | AuthenticationStatus::Unauthenticated => Err(ApiError::Unauthorized),
     }
 }
@@ -205,6 +230,9 @@ pub async fn authenticate<B>(
     let auth_status = get_auth_status(&state, &req).await?;
 
     let project_id = auth_status.project_id();
+    if let Some(author) = auth_status.author() {
+        req.extensions_mut().insert(author);
+    }
     req.extensions_mut().insert(auth_status);
 
     if let Some(project_id) = &project_id {
@@ -5,7 +5,7 @@ use axum::extract::rejection::JsonRejection;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use lib::GrpcClientError;
+use lib::{is_shard_moved, GrpcClientError};
 use serde::Serialize;
 use serde_with::skip_serializing_none;
 use thiserror::Error;
@@ -21,6 +21,11 @@ struct ApiErrorBody {
     params: Option<HashMap<String, Vec<String>>>,
 }
 
+/// Marks responses of requests that reached a scheduler cell which doesn't
+/// serve the project's shard anymore.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StaleShardRoutes;
+
 #[derive(Error, Debug)]
 pub enum ApiError {
     // 400
@@ -74,6 +79,12 @@ pub enum ApiError {
         "Service is currently unavailable, please retry again in a few seconds"
     )]
     ServiceUnavailable,
+    // 503, the scheduler cell doesn't serve the project's shard anymore, the
+    // shard routes of this process are stale.
+    #[error(
+        "Service is currently unavailable, please retry again in a few seconds"
+    )]
+    ShardMoved,
 
     #[error("This functionality is not implemented")]
     NotImplemented,
@@ -109,7 +120,9 @@ impl ApiError {
             | ApiError::InternalServerError => {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
-            | ApiError::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            | ApiError::ServiceUnavailable | ApiError::ShardMoved => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
             | ApiError::NotImplemented => StatusCode::NOT_IMPLEMENTED,
             | ApiError::UnprocessableContent { .. } => {
                 StatusCode::UNPROCESSABLE_ENTITY
@@ -125,6 +138,7 @@ impl IntoResponse for ApiError {
     #[tracing::instrument]
     fn into_response(self) -> Response {
         let status_code = self.status_code();
+        let shard_moved = matches!(self, Self::ShardMoved);
         let body = match self {
             | Self::UnprocessableContent { message, params } => {
                 ApiErrorBody {
@@ -149,13 +163,24 @@ impl IntoResponse for ApiError {
                 }
             }
         };
-        (status_code, Json(body)).into_response()
+        let mut response = (status_code, Json(body)).into_response();
+        if shard_moved {
+            response.extensions_mut().insert(StaleShardRoutes);
+        }
+        response
     }
 }
 
 #[allow(clippy::wildcard_in_or_patterns)]
 impl From<tonic::Status> for ApiError {
     fn from(value: tonic::Status) -> Self {
+        if is_shard_moved(&value) {
+            warn!(
+                grpc_message = ?value.message(),
+                "Shard routes are stale, they will be reloaded"
+            );
+            return ApiError::ShardMoved;
+        }
         match value.code() {
             tonic::Code::NotFound => ApiError::NotFound(value.message().to_string()),
             // Indicates a non-retryable logical error in the system.
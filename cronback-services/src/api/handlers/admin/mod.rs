@@ -1,5 +1,6 @@
 mod api_keys;
 mod projects;
+mod shards;
 
 use std::sync::Arc;
 
@@ -33,6 +34,33 @@ pub(crate) fn routes(shared_state: Arc<AppState>) -> Router {
                     "/:id/notification_settings",
                     axum::routing::get(projects::get_notification_settings),
                 )
+                .route(
+                    "/:id/trigger_retention",
+                    axum::routing::post(projects::set_trigger_retention),
+                )
+                .route(
+                    "/:id/trigger_retention",
+                    axum::routing::get(projects::get_trigger_retention),
+                )
+                .route(
+                    "/:id/signing_secrets",
+                    axum::routing::get(projects::list_signing_secrets),
+                )
+                .route(
+                    "/:id/signing_secrets",
+                    axum::routing::post(projects::create_signing_secret),
+                )
+                .route(
+                    "/:id/signing_secrets/:secret_id",
+                    axum::routing::delete(projects::delete_signing_secret),
+                )
+                .with_state(Arc::clone(&shared_state))
+                .route_layer(middleware::from_fn(ensure_admin)),
+        )
+        .nest(
+            "/shards",
+            Router::new()
+                .route("/move", axum::routing::post(shards::move_shards))
                 .with_state(Arc::clone(&shared_state))
                 .route_layer(middleware::from_fn(ensure_admin)),
         )
@@ -5,15 +5,23 @@ use axum::response::IntoResponse;
 use axum::{Extension, Json};
 use cronback_api_model::admin::{
     CreateProjectResponse as CreateProjectHttpResponse,
+    CreateSigningSecret,
     NotificationSettings,
+    SigningSecret,
+    TriggerRetention,
 };
 use hyper::StatusCode;
 use lib::prelude::*;
 use proto::metadata_svc::{
     CreateProjectRequest,
+    CreateSigningSecretRequest,
+    DeleteSigningSecretRequest,
     GetNotificationSettingsRequest,
+    GetSigningSecretsRequest,
+    GetTriggerRetentionRequest,
     SetNotificationSettingsRequest,
     SetProjectStatusRequest,
+    SetTriggerRetentionRequest,
 };
 use proto::projects::ProjectStatus;
 
@@ -145,3 +153,145 @@ pub(crate) async fn set_notification_settings(
         .await?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[tracing::instrument(skip(state))]
+pub(crate) async fn get_trigger_retention(
+    state: State<Arc<AppState>>,
+    Path(project_id_str): Path<String>,
+    Extension(request_id): Extension<RequestId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let project_id = ProjectId::from(project_id_str.clone())
+        .validated()
+        .map_err(move |_| ApiError::NotFound(project_id_str))?;
+
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project_id)
+        .await?;
+    let resp = metadata
+        .get_trigger_retention(GetTriggerRetentionRequest {
+            id: Some(project_id.into()),
+        })
+        .await?
+        .into_inner();
+
+    Ok(Json(TriggerRetention {
+        retention_s: resp.retention_s,
+    }))
+}
+
+#[tracing::instrument(skip(state))]
+pub(crate) async fn set_trigger_retention(
+    state: State<Arc<AppState>>,
+    Path(project_id_str): Path<String>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(retention): ValidatedJson<TriggerRetention>,
+) -> Result<impl IntoResponse, ApiError> {
+    let project_id = ProjectId::from(project_id_str.clone())
+        .validated()
+        .map_err(move |_| ApiError::NotFound(project_id_str))?;
+
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project_id)
+        .await?;
+    metadata
+        .set_trigger_retention(SetTriggerRetentionRequest {
+            id: Some(project_id.into()),
+            retention_s: retention.retention_s,
+        })
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn to_signing_secret(
+    secret: proto::projects::SigningSecret,
+    reveal: bool,
+) -> SigningSecret {
+    SigningSecret {
+        id: secret.id,
+        secret: reveal.then_some(secret.secret),
+        created_at: secret.created_at.unwrap().into(),
+        expires_at: secret.expires_at.map(Into::into),
+    }
+}
+
+#[tracing::instrument(skip(state))]
+pub(crate) async fn list_signing_secrets(
+    state: State<Arc<AppState>>,
+    Path(project_id_str): Path<String>,
+    Extension(request_id): Extension<RequestId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let project_id = ProjectId::from(project_id_str.clone())
+        .validated()
+        .map_err(move |_| ApiError::NotFound(project_id_str))?;
+
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project_id)
+        .await?;
+    let resp = metadata
+        .get_signing_secrets(GetSigningSecretsRequest {
+            id: Some(project_id.into()),
+        })
+        .await?
+        .into_inner();
+
+    let secrets: Vec<SigningSecret> = resp
+        .secrets
+        .into_iter()
+        .map(|s| to_signing_secret(s, false))
+        .collect();
+    Ok(Json(secrets))
+}
+
+#[tracing::instrument(skip(state))]
+pub(crate) async fn create_signing_secret(
+    state: State<Arc<AppState>>,
+    Path(project_id_str): Path<String>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(request): ValidatedJson<CreateSigningSecret>,
+) -> Result<impl IntoResponse, ApiError> {
+    let project_id = ProjectId::from(project_id_str.clone())
+        .validated()
+        .map_err(move |_| ApiError::NotFound(project_id_str))?;
+
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project_id)
+        .await?;
+    let resp = metadata
+        .create_signing_secret(CreateSigningSecretRequest {
+            id: Some(project_id.into()),
+            expire_existing_after_s: request.expire_existing_after_s,
+        })
+        .await?
+        .into_inner();
+
+    // This is the only time the secret is returned.
+    let secret = to_signing_secret(resp.secret.unwrap(), true);
+    Ok((StatusCode::CREATED, Json(secret)).into_response())
+}
+
+#[tracing::instrument(skip(state))]
+pub(crate) async fn delete_signing_secret(
+    state: State<Arc<AppState>>,
+    Path((project_id_str, secret_id)): Path<(String, String)>,
+    Extension(request_id): Extension<RequestId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let project_id = ProjectId::from(project_id_str.clone())
+        .validated()
+        .map_err(move |_| ApiError::NotFound(project_id_str))?;
+
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project_id)
+        .await?;
+    metadata
+        .delete_signing_secret(DeleteSigningSecretRequest {
+            id: Some(project_id.into()),
+            secret_id,
+        })
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
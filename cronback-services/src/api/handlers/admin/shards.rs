@@ -0,0 +1,152 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::{debug_handler, Extension, Json};
+use cronback_api_model::admin::{MoveShardsRequest, MoveShardsResponse};
+use lib::prelude::*;
+use proto::metadata_svc::AssignShardsRequest;
+use proto::scheduler_svc::{
+    AdoptShardsRequest,
+    DrainShardsRequest,
+    ReleaseShardsRequest,
+};
+use tracing::{error, info, warn};
+
+use crate::api::errors::ApiError;
+use crate::api::extractors::ValidatedJson;
+use crate::api::AppState;
+
+const RELEASE_ATTEMPTS: u32 = 3;
+const RELEASE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Moves shards between scheduler cells. The source cell stops serving the
+/// shards and hands over their triggers, revisions and pending dispatches,
+/// the target cell adopts them, and the move is recorded by the metadata
+/// service. Requests of the shards are routed to the target cell right away
+/// by this process, and by the other processes once they reload the shard
+/// assignments, which they do as soon as the source cell rejects a request of
+/// the moved shards.
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn move_shards(
+    state: State<Arc<AppState>>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(req): ValidatedJson<MoveShardsRequest>,
+) -> Result<Json<MoveShardsResponse>, ApiError> {
+    let mut source = state
+        .scheduler_clients
+        .get_cell_client(&request_id, req.from_cell)
+        .await?;
+    let mut target = state
+        .scheduler_clients
+        .get_cell_client(&request_id, req.to_cell)
+        .await?;
+
+    let drained = source
+        .drain_shards(DrainShardsRequest {
+            shards: req.shards.clone(),
+        })
+        .await?
+        .into_inner();
+    let moved_triggers = drained.triggers.len();
+
+    let adopted = target
+        .adopt_shards(AdoptShardsRequest {
+            shards: req.shards.clone(),
+            triggers: drained.triggers,
+            revisions: drained.revisions,
+            outbox: drained.outbox,
+        })
+        .await;
+    if let Err(e) = adopted {
+        error!(
+            "Cell {} failed to adopt shards {:?}, resuming them on cell {}: \
+             {e}",
+            req.to_cell, req.shards, req.from_cell
+        );
+        source
+            .release_shards(ReleaseShardsRequest {
+                shards: req.shards.clone(),
+                moved: false,
+            })
+            .await?;
+        return Err(e.into());
+    }
+
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_cell_client(&request_id, 0)
+        .await?;
+    let assigned = metadata
+        .assign_shards(AssignShardsRequest {
+            shards: req.shards.clone(),
+            cell_id: req.to_cell,
+        })
+        .await;
+    if let Err(e) = assigned {
+        error!(
+            "Failed to record the move of shards {:?} to cell {}, resuming \
+             them on cell {}: {e}",
+            req.shards, req.to_cell, req.from_cell
+        );
+        // The target cell forgets its copy, the source cell still has it.
+        target
+            .release_shards(ReleaseShardsRequest {
+                shards: req.shards.clone(),
+                moved: true,
+            })
+            .await?;
+        source
+            .release_shards(ReleaseShardsRequest {
+                shards: req.shards.clone(),
+                moved: false,
+            })
+            .await?;
+        return Err(e.into());
+    }
+
+    let shards: Vec<Shard> = req.shards.iter().copied().map(Shard).collect();
+    state.scheduler_clients.route_shards(&shards, req.to_cell);
+
+    // The move is complete at this point, failing to release the source
+    // cell leaves a stale copy of the shards there that it doesn't serve.
+    // The copy is purged once the source cell restarts otherwise.
+    for attempt in 1..=RELEASE_ATTEMPTS {
+        let released = source
+            .release_shards(ReleaseShardsRequest {
+                shards: req.shards.clone(),
+                moved: true,
+            })
+            .await;
+        match released {
+            | Ok(_) => break,
+            | Err(e) if attempt < RELEASE_ATTEMPTS => {
+                warn!(
+                    "Cell {} failed to release shards {:?}, retrying: {e}",
+                    req.from_cell, req.shards
+                );
+                tokio::time::sleep(RELEASE_RETRY_DELAY * attempt).await;
+            }
+            | Err(e) => {
+                error!(
+                    "Cell {} failed to release shards {:?}, their copy will \
+                     be purged when it restarts: {e}",
+                    req.from_cell, req.shards
+                );
+            }
+        }
+    }
+    info!(
+        "Moved {moved_triggers} triggers of shards {:?} from cell {} to cell \
+         {}",
+        req.shards, req.from_cell, req.to_cell
+    );
+
+    Ok(Json(MoveShardsResponse {
+        shards: req.shards,
+        from_cell: req.from_cell,
+        to_cell: req.to_cell,
+        moved_triggers,
+    }))
+}
@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{debug_handler, Extension, Json, Router};
+use lib::prelude::*;
+use proto::common::UpsertEffect;
+use proto::metadata_svc::{
+    DeleteCalendarRequest,
+    GetCalendarsRequest,
+    ListCalendarsRequest,
+    UpsertCalendarRequest,
+};
+use proto::scheduler_svc::RefreshCalendarRequest;
+use validator::Validate;
+
+use crate::api::api_model::Calendar;
+use crate::api::errors::ApiError;
+use crate::api::extractors::ValidatedJson;
+use crate::api::paginated::{Paginated, Pagination};
+use crate::api::AppState;
+
+pub(crate) fn routes(shared_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", axum::routing::get(list))
+        .route("/:name", axum::routing::get(get))
+        .route("/:name", axum::routing::put(put))
+        .route("/:name", axum::routing::delete(delete))
+        .with_state(shared_state)
+}
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn put(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(mut calendar): ValidatedJson<Calendar>,
+) -> Result<impl IntoResponse, ApiError> {
+    if matches!(calendar.name, Some(ref body_name) if body_name != &name) {
+        return Err(ApiError::unprocessable_content_naked(
+            "Calendar name in the request body doesn't match the URL",
+        ));
+    }
+    calendar.name = Some(name.clone());
+    // The body was validated without the name from the URL.
+    calendar.validate()?;
+
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let response = metadata
+        .upsert_calendar(UpsertCalendarRequest {
+            id: Some(project.clone().into()),
+            calendar: Some(calendar.clone().into()),
+        })
+        .await?
+        .into_inner();
+
+    refresh_triggers(&state, &request_id, &project, name).await?;
+
+    let status = match UpsertEffect::from_i32(response.effect) {
+        | Some(UpsertEffect::Created) => StatusCode::CREATED,
+        | _ => StatusCode::OK,
+    };
+    Ok((status, Json(calendar)).into_response())
+}
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn get(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let calendar = metadata
+        .get_calendars(GetCalendarsRequest {
+            id: Some(project.into()),
+            names: vec![name.clone()],
+        })
+        .await?
+        .into_inner()
+        .calendars
+        .pop()
+        .ok_or(ApiError::NotFound(name))?;
+
+    let calendar: Calendar = calendar.into();
+    Ok((StatusCode::OK, Json(calendar)).into_response())
+}
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn list(
+    Query(pagination): Query<Pagination>,
+    State(state): State<Arc<AppState>>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+) -> Result<Paginated<Calendar>, ApiError> {
+    pagination.validate()?;
+
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let response = metadata
+        .list_calendars(ListCalendarsRequest {
+            id: Some(project.into()),
+            pagination: Some(pagination.into()),
+        })
+        .await?
+        .into_inner();
+
+    Ok(Paginated::from(
+        response.calendars,
+        response.pagination.unwrap_or_default(),
+    ))
+}
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn delete(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut metadata = state
+        .metadata_svc_clients
+        .get_client(&request_id, &project)
+        .await?;
+    metadata
+        .delete_calendar(DeleteCalendarRequest {
+            id: Some(project.clone().into()),
+            name: name.clone(),
+        })
+        .await?;
+
+    refresh_triggers(&state, &request_id, &project, name).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Asks the scheduler to pick up the new dates for triggers that exclude this
+/// calendar.
+async fn refresh_triggers(
+    state: &AppState,
+    request_id: &RequestId,
+    project: &ValidShardedId<ProjectId>,
+    name: String,
+) -> Result<(), ApiError> {
+    let mut scheduler = state
+        .scheduler_clients
+        .get_client(request_id, project)
+        .await?;
+    scheduler
+        .refresh_calendar(RefreshCalendarRequest { name })
+        .await?;
+    Ok(())
+}
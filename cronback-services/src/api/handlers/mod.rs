@@ -6,6 +6,8 @@ use crate::api::auth_middleware::ensure_authenticated;
 use crate::api::AppState;
 
 pub(crate) mod admin;
+pub(crate) mod calendars;
+pub(crate) mod schedules;
 pub(crate) mod triggers;
 
 pub(crate) fn routes(shared_state: Arc<AppState>) -> Router {
@@ -16,4 +18,19 @@ pub(crate) fn routes(shared_state: Arc<AppState>) -> Router {
             triggers::routes(Arc::clone(&shared_state))
                 .route_layer(middleware::from_fn(ensure_authenticated)),
         )
+        .nest(
+            "/bulk",
+            triggers::bulk_routes(Arc::clone(&shared_state))
+                .route_layer(middleware::from_fn(ensure_authenticated)),
+        )
+        .nest(
+            "/schedules",
+            schedules::routes(Arc::clone(&shared_state))
+                .route_layer(middleware::from_fn(ensure_authenticated)),
+        )
+        .nest(
+            "/calendars",
+            calendars::routes(Arc::clone(&shared_state))
+                .route_layer(middleware::from_fn(ensure_authenticated)),
+        )
 }
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{debug_handler, Extension, Json, Router};
+use lib::prelude::*;
+use proto::scheduler_svc::PreviewScheduleRequest;
+use validator::Validate;
+
+use crate::api::api_model::{PreviewScheduleQuery, Schedule, SchedulePreview};
+use crate::api::errors::ApiError;
+use crate::api::extractors::ValidatedJson;
+use crate::api::AppState;
+
+pub(crate) fn routes(shared_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/preview", axum::routing::post(preview))
+        .with_state(shared_state)
+}
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn preview(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PreviewScheduleQuery>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(schedule): ValidatedJson<Schedule>,
+) -> Result<impl IntoResponse, ApiError> {
+    query.validate()?;
+    let mut scheduler = state
+        .scheduler_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let preview = scheduler
+        .preview_schedule(PreviewScheduleRequest {
+            schedule: Some(schedule.into()),
+            count: query.count,
+        })
+        .await?
+        .into_inner();
+
+    let preview: SchedulePreview = preview.into();
+    Ok((StatusCode::OK, Json(preview)).into_response())
+}
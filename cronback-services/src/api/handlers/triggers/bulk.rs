@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{debug_handler, Extension, Json};
+use lib::prelude::*;
+use proto::scheduler_svc::BulkUpdateTriggersRequest;
+
+use crate::api::api_model::{BulkUpdateTriggers, BulkUpdateTriggersResult};
+use crate::api::errors::ApiError;
+use crate::api::extractors::ValidatedJson;
+use crate::api::AppState;
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn bulk_update(
+    State(state): State<Arc<AppState>>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(request): ValidatedJson<BulkUpdateTriggers>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut scheduler = state
+        .scheduler_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let response = scheduler
+        .bulk_update_triggers(BulkUpdateTriggersRequest {
+            action: request.action.into(),
+            statuses: request.status.into_iter().map(Into::into).collect(),
+            name_prefix: request.name_prefix,
+            dry_run: request.dry_run,
+        })
+        .await?
+        .into_inner();
+
+    let response: BulkUpdateTriggersResult = response.into();
+    Ok((StatusCode::OK, Json(response)).into_response())
+}
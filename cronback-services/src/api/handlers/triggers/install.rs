@@ -8,6 +8,7 @@ use proto::common::{RequestPrecondition, UpsertEffect};
 use tracing::error;
 
 use crate::api::api_model::{UpsertTriggerRequest, UpsertTriggerResponse};
+use crate::api::auth_middleware::Author;
 use crate::api::errors::ApiError;
 use crate::api::extractors::ValidatedJson;
 use crate::api::AppState;
@@ -18,6 +19,7 @@ pub(crate) async fn install(
     State(state): State<Arc<AppState>>,
     Extension(project): Extension<ValidShardedId<ProjectId>>,
     Extension(request_id): Extension<RequestId>,
+    Extension(author): Extension<Author>,
     ValidatedJson(mut request): ValidatedJson<UpsertTriggerRequest>,
 ) -> Result<UpsertTriggerResponse, ApiError> {
     // Intention here is to install a new one, no updates to existing triggers
@@ -48,6 +50,7 @@ pub(crate) async fn install(
         Some(request_precondition),
         project,
         /* existing_name = */ None,
+        author,
         request,
     )
     .await
@@ -59,6 +62,7 @@ pub(crate) async fn install_or_update(
     precondition: Option<RequestPrecondition>,
     project: ValidShardedId<ProjectId>,
     existing_name: Option<String>,
+    author: Author,
     request: UpsertTriggerRequest,
 ) -> Result<UpsertTriggerResponse, ApiError> {
     // If we have an Id already, we must allow updates.
@@ -66,7 +70,8 @@ pub(crate) async fn install_or_update(
         .scheduler_clients
         .get_client(&request_id, &project)
         .await?;
-    let install_request = request.into_proto(existing_name, precondition);
+    let install_request =
+        request.into_proto(existing_name, precondition, author.0);
 
     let response = scheduler
         .upsert_trigger(install_request)
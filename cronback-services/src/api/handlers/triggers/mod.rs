@@ -1,3 +1,4 @@
+mod bulk;
 mod cancel;
 mod delete;
 mod get;
@@ -5,8 +6,11 @@ mod install;
 mod pause;
 mod put;
 mod resume;
+mod revisions;
 mod run;
 mod runs;
+mod skip;
+mod timepoints;
 
 use std::sync::Arc;
 
@@ -27,5 +31,24 @@ pub(crate) fn routes(shared_state: Arc<AppState>) -> Router {
         .route("/:name/pause", axum::routing::post(pause::pause))
         .route("/:name/cancel", axum::routing::post(cancel::cancel))
         .route("/:name/resume", axum::routing::post(resume::resume))
+        .route("/:name/skip", axum::routing::post(skip::skip))
+        .route("/:name/timepoints", axum::routing::post(timepoints::add))
+        .route(
+            "/:name/timepoints",
+            axum::routing::delete(timepoints::remove),
+        )
+        .route("/:name/revisions", axum::routing::get(revisions::list))
+        .route(
+            "/:name/revisions/:revision/restore",
+            axum::routing::post(revisions::restore),
+        )
+        .with_state(shared_state)
+}
+
+/// Routes acting on many triggers at once. They live outside of `/triggers`
+/// to not shadow the routes of triggers with the same name.
+pub(crate) fn bulk_routes(shared_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/triggers", axum::routing::post(bulk::bulk_update))
         .with_state(shared_state)
 }
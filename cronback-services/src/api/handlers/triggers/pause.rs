@@ -6,8 +6,9 @@ use axum::response::IntoResponse;
 use axum::{debug_handler, Extension, Json};
 use lib::prelude::*;
 use proto::scheduler_svc::PauseTriggerRequest;
+use validator::Validate;
 
-use crate::api::api_model::Trigger;
+use crate::api::api_model::{PauseTrigger, Trigger};
 use crate::api::errors::ApiError;
 use crate::api::AppState;
 
@@ -18,13 +19,20 @@ pub(crate) async fn pause(
     Path(name): Path<String>,
     Extension(project): Extension<ValidShardedId<ProjectId>>,
     Extension(request_id): Extension<RequestId>,
+    // The body of the request is optional, so we use Option<Json<...>>.
+    request: Option<Json<PauseTrigger>>,
 ) -> Result<impl IntoResponse, ApiError> {
+    let Json(request) = request.unwrap_or_default();
+    request.validate()?;
     let mut scheduler = state
         .scheduler_clients
         .get_client(&request_id, &project)
         .await?;
     let trigger = scheduler
-        .pause_trigger(PauseTriggerRequest { name })
+        .pause_trigger(PauseTriggerRequest {
+            name,
+            until: request.until.map(Into::into),
+        })
         .await?
         .into_inner()
         .trigger
@@ -6,6 +6,7 @@ use axum::{debug_handler, Extension};
 use lib::prelude::*;
 
 use crate::api::api_model::UpsertTriggerRequest;
+use crate::api::auth_middleware::Author;
 use crate::api::errors::ApiError;
 use crate::api::extractors::ValidatedJson;
 use crate::api::AppState;
@@ -17,6 +18,7 @@ pub(crate) async fn put(
     Path(name): Path<String>,
     Extension(project): Extension<ValidShardedId<ProjectId>>,
     Extension(request_id): Extension<RequestId>,
+    Extension(author): Extension<Author>,
     ValidatedJson(request): ValidatedJson<UpsertTriggerRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     // An important detail to note. The name in the URL is the _original_ name,
@@ -31,6 +33,7 @@ pub(crate) async fn put(
         None,
         project,
         Some(name),
+        author,
         request,
     )
     .await
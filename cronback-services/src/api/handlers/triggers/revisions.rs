@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{debug_handler, Extension, Json};
+use lib::prelude::*;
+use proto::scheduler_svc::{
+    ListTriggerRevisionsRequest,
+    RestoreTriggerRevisionRequest,
+};
+use validator::Validate;
+
+use crate::api::api_model::{Trigger, TriggerRevision};
+use crate::api::auth_middleware::Author;
+use crate::api::errors::ApiError;
+use crate::api::paginated::{Paginated, Pagination};
+use crate::api::AppState;
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn list(
+    Query(pagination): Query<Pagination>,
+    state: State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+) -> Result<Paginated<TriggerRevision>, ApiError> {
+    pagination.validate()?;
+
+    let mut scheduler = state
+        .scheduler_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let response = scheduler
+        .list_trigger_revisions(ListTriggerRevisionsRequest {
+            name,
+            pagination: Some(pagination.into()),
+        })
+        .await?
+        .into_inner();
+
+    Ok(Paginated::from(
+        response.revisions,
+        response.pagination.unwrap_or_default(),
+    ))
+}
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn restore(
+    state: State<Arc<AppState>>,
+    Path((name, revision)): Path<(String, u64)>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+    Extension(author): Extension<Author>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut scheduler = state
+        .scheduler_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let trigger = scheduler
+        .restore_trigger_revision(RestoreTriggerRevisionRequest {
+            name: name.clone(),
+            revision,
+            author: Some(author.0),
+        })
+        .await?
+        .into_inner()
+        .trigger
+        .ok_or_else(|| ApiError::NotFound(name.clone()))?;
+
+    let trigger: Trigger = trigger.into();
+
+    Ok((StatusCode::OK, Json(trigger)).into_response())
+}
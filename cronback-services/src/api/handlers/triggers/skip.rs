@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{debug_handler, Extension, Json};
+use lib::prelude::*;
+use proto::scheduler_svc::SkipTriggerRequest;
+
+use crate::api::api_model::{SkipTrigger, Trigger};
+use crate::api::errors::ApiError;
+use crate::api::extractors::ValidatedJson;
+use crate::api::AppState;
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn skip(
+    state: State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(request): ValidatedJson<SkipTrigger>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut scheduler = state
+        .scheduler_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let trigger = scheduler
+        .skip_trigger(SkipTriggerRequest {
+            name,
+            count: request.count,
+            occurrences: request
+                .occurrences
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        })
+        .await?
+        .into_inner()
+        .trigger
+        .unwrap();
+
+    let trigger: Trigger = trigger.into();
+
+    Ok((StatusCode::OK, Json(trigger)).into_response())
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{debug_handler, Extension, Json};
+use lib::prelude::*;
+use proto::scheduler_svc::UpdateTimepointsRequest;
+
+use crate::api::api_model::{Trigger, UpdateTimepoints};
+use crate::api::errors::ApiError;
+use crate::api::extractors::ValidatedJson;
+use crate::api::AppState;
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn add(
+    state: State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(request): ValidatedJson<UpdateTimepoints>,
+) -> Result<impl IntoResponse, ApiError> {
+    update(
+        state,
+        project,
+        request_id,
+        UpdateTimepointsRequest {
+            name,
+            add: request.timepoints.into_iter().map(Into::into).collect(),
+            remove: vec![],
+        },
+    )
+    .await
+}
+
+#[tracing::instrument(skip(state))]
+#[debug_handler]
+pub(crate) async fn remove(
+    state: State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Extension(project): Extension<ValidShardedId<ProjectId>>,
+    Extension(request_id): Extension<RequestId>,
+    ValidatedJson(request): ValidatedJson<UpdateTimepoints>,
+) -> Result<impl IntoResponse, ApiError> {
+    update(
+        state,
+        project,
+        request_id,
+        UpdateTimepointsRequest {
+            name,
+            add: vec![],
+            remove: request.timepoints.into_iter().map(Into::into).collect(),
+        },
+    )
+    .await
+}
+
+async fn update(
+    state: State<Arc<AppState>>,
+    project: ValidShardedId<ProjectId>,
+    request_id: RequestId,
+    request: UpdateTimepointsRequest,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut scheduler = state
+        .scheduler_clients
+        .get_client(&request_id, &project)
+        .await?;
+    let trigger = scheduler
+        .update_timepoints(request)
+        .await?
+        .into_inner()
+        .trigger
+        .unwrap();
+
+    let trigger: Trigger = trigger.into();
+
+    Ok((StatusCode::OK, Json(trigger)).into_response())
+}
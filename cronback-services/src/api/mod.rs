@@ -18,7 +18,7 @@ use std::time::Instant;
 use async_trait::async_trait;
 use auth::Authenticator;
 use auth_store::AuthStore;
-use axum::extract::MatchedPath;
+use axum::extract::{MatchedPath, State};
 use axum::http::{Request, StatusCode};
 use axum::middleware::{self, Next};
 use axum::response::IntoResponse;
@@ -47,6 +47,7 @@ use tower_http::trace::TraceLayer;
 use tracing::{error, info, warn};
 
 use self::config::ApiSvcConfig;
+use self::errors::StaleShardRoutes;
 
 /// The primary service data type for the service.
 #[derive(Clone)]
@@ -123,6 +124,10 @@ impl CronbackService for ApiService {
                 Arc::clone(&shared_state),
                 auth_middleware::authenticate,
             ))
+            .route_layer(middleware::from_fn_with_state(
+                Arc::clone(&shared_state),
+                expire_stale_shard_routes,
+            ))
             .route_layer(middleware::from_fn(inject_request_id))
             .route_layer(middleware::from_fn(track_metrics))
             .fallback(fallback);
@@ -215,6 +220,19 @@ async fn inject_request_id<B>(
     response
 }
 
+async fn expire_stale_shard_routes<B>(
+    State(state): State<Arc<AppState>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> impl IntoResponse {
+    let response = next.run(req).await;
+    // The next request of a moved shard will be routed to its new cell.
+    if response.extensions().get::<StaleShardRoutes>().is_some() {
+        state.scheduler_clients.expire_shard_routes();
+    }
+    response
+}
+
 async fn track_metrics<B>(req: Request<B>, next: Next<B>) -> impl IntoResponse {
     let start = Instant::now();
     let path = if let Some(matched_path) = req.extensions().get::<MatchedPath>()
@@ -95,6 +95,14 @@ mod tests {
                     response_code: Some(404),
                     response_latency_s: Duration::from_secs(10),
                     error_message: None,
+                    error_kind: None,
+                    retry_after_s: None,
+                    retry_reason: None,
+                    failed_criterion: None,
+                    response_headers: Default::default(),
+                    response_body: None,
+                    response_body_truncated: false,
+                    error_cause: None,
                 },
             ),
             attempt_num: 5,
@@ -8,6 +8,12 @@ pub struct DispatcherSvcConfig {
     pub port: u16,
     pub request_processing_timeout_s: u64,
     pub database_uri: String,
+    // How much of the response body is kept on the attempts.
+    pub response_body_limit_bytes: usize,
+    // How much of the response headers is kept on the attempts.
+    pub response_headers_limit_bytes: usize,
+    // How long the signing secrets of a project are cached for.
+    pub signing_secrets_cache_ttl_s: u64,
 }
 
 impl From<DispatcherSvcConfig> for ConnectOptions {
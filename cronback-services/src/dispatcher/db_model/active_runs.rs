@@ -0,0 +1,19 @@
+use lib::prelude::*;
+use sea_orm::entity::prelude::*;
+
+/// The run that the concurrency policy of a trigger lets attempt. Only
+/// triggers that forbid or replace concurrent runs have one.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "active_runs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub project_id: ValidShardedId<ProjectId>,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub trigger_id: TriggerId,
+    pub run_id: RunId,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
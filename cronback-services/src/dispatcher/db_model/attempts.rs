@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+use std::collections::HashMap;
 use std::time::Duration;
 
 use chrono::{DateTime, Utc};
@@ -59,22 +60,63 @@ pub struct WebhookAttemptDetails {
     #[into_proto(map = "Duration::as_secs_f64", map_by_ref)]
     pub response_latency_s: Duration,
     pub error_message: Option<String>,
+    #[serde(default)]
+    #[proto(skip)]
+    pub error_kind: Option<WebhookErrorKind>,
+    #[serde(default)]
+    pub retry_after_s: Option<u64>,
+    #[serde(default)]
+    pub retry_reason: Option<String>,
+    #[serde(default)]
+    pub failed_criterion: Option<String>,
+    #[serde(default)]
+    pub response_headers: HashMap<String, String>,
+    #[serde(default)]
+    pub response_body: Option<String>,
+    #[serde(default)]
+    pub response_body_truncated: bool,
+    #[serde(default)]
+    pub error_cause: Option<String>,
 }
 
 impl WebhookAttemptDetails {
+    /// Whether the response matched the success criteria of the webhook.
     pub fn is_success(&self) -> bool {
-        (200..=299).contains(&self.response_code.unwrap_or(500))
+        self.response_code.is_some() && self.failed_criterion.is_none()
     }
 
     pub fn with_error(err: String) -> Self {
+        Self::with_error_kind(WebhookErrorKind::Other, err)
+    }
+
+    pub fn with_error_kind(kind: WebhookErrorKind, err: String) -> Self {
         Self {
             response_code: None,
             response_latency_s: Duration::default(),
             error_message: Some(err),
+            error_kind: Some(kind),
+            retry_after_s: None,
+            retry_reason: None,
+            failed_criterion: None,
+            response_headers: HashMap::new(),
+            response_body: None,
+            response_body_truncated: false,
+            error_cause: None,
         }
     }
 }
 
+/// Why an attempt didn't get a response.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookErrorKind {
+    Connection,
+    Timeout,
+    // We couldn't sign the request, the metadata service was unreachable.
+    Signing,
+    // Failures that don't come from the receiver, like invalid requests.
+    Other,
+}
+
 #[derive(
     Debug,
     Clone,
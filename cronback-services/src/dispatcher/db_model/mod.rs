@@ -1,5 +1,7 @@
+pub mod active_runs;
 pub mod attempts;
 pub mod runs;
 
+pub use active_runs::{Entity as ActiveRuns, Model as ActiveRun};
 pub use attempts::{Entity as Attempts, Model as Attempt};
 pub use runs::{Entity as Runs, Model as Run};
@@ -33,6 +33,15 @@ pub struct Model {
     #[from_proto(always_none)]
     #[sea_orm(ignore)]
     pub latest_attempt: Option<attempts::Model>,
+    // The tick of the trigger's schedule that this run is for.
+    pub scheduled_at: Option<DateTime<Utc>>,
+    // The number of attempts that were completed so far. Runs resume their
+    // retries from here after a restart.
+    #[proto(skip)]
+    pub attempt_count: u32,
+    // When the next attempt is due, if the run is waiting to be retried.
+    #[proto(skip)]
+    pub next_attempt_at: Option<DateTime<Utc>>,
 }
 
 impl PaginatedEntity for Entity {
@@ -96,4 +105,6 @@ pub enum RunStatus {
     Succeeded,
     #[sea_orm(string_value = "Failed")]
     Failed,
+    #[sea_orm(string_value = "Skipped")]
+    Skipped,
 }
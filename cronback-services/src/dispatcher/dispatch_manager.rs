@@ -1,11 +1,16 @@
 use std::debug_assert;
+use std::sync::Arc;
 use std::time::Duration;
 
 use chrono::Utc;
 use dispatcher_svc::DispatchMode;
+use lib::clients::ScopedSchedulerSvcClient;
 use lib::prelude::*;
-use metrics::{decrement_gauge, increment_gauge};
+use lib::{is_shard_moved, GrpcClientFactory, GrpcClientProvider};
+use metrics::{counter, decrement_gauge, increment_gauge};
+use proto::common::ConcurrencyPolicy;
 use proto::dispatcher_svc;
+use proto::scheduler_svc::RunCompletedRequest;
 use thiserror::Error;
 use tracing::{error, info};
 
@@ -13,8 +18,13 @@ use super::attempt_store::AttemptStore;
 use super::db_model::runs::RunStatus;
 use super::db_model::Run;
 use super::run_store::{RunStore, RunStoreError};
+use super::signature::SigningSecrets;
 use super::webhook_action::WebhookActionJob;
 
+// How many times a run tries to become the active run of its trigger when
+// racing with other runs of the same trigger.
+const ACTIVE_RUN_ATTEMPTS: usize = 3;
+
 #[derive(Error, Debug)]
 pub enum DispatcherManagerError {
     #[error("store error: {0}")]
@@ -25,6 +35,10 @@ pub struct DispatchManager {
     _cell_id: u32,
     attempt_store: AttemptStore,
     run_store: RunStore,
+    scheduler_clients: Arc<GrpcClientProvider<ScopedSchedulerSvcClient>>,
+    signing_secrets: Arc<SigningSecrets>,
+    response_body_limit_bytes: usize,
+    response_headers_limit_bytes: usize,
 }
 
 impl DispatchManager {
@@ -32,11 +46,19 @@ impl DispatchManager {
         cell_id: u32,
         run_store: RunStore,
         attempt_store: AttemptStore,
+        scheduler_clients: Arc<GrpcClientProvider<ScopedSchedulerSvcClient>>,
+        signing_secrets: Arc<SigningSecrets>,
+        response_body_limit_bytes: usize,
+        response_headers_limit_bytes: usize,
     ) -> Self {
         Self {
             _cell_id: cell_id,
             run_store,
             attempt_store,
+            scheduler_clients,
+            signing_secrets,
+            response_body_limit_bytes,
+            response_headers_limit_bytes,
         }
     }
 
@@ -58,6 +80,10 @@ impl DispatchManager {
                     r,
                     self.run_store.clone(),
                     self.attempt_store.clone(),
+                    self.scheduler_clients.clone(),
+                    self.signing_secrets.clone(),
+                    self.response_body_limit_bytes,
+                    self.response_headers_limit_bytes,
                 )
                 .run(),
             );
@@ -68,15 +94,23 @@ impl DispatchManager {
 
     pub async fn run(
         &self,
-        run: Run,
+        mut run: Run,
         mode: DispatchMode,
+        concurrency_policy: ConcurrencyPolicy,
     ) -> Result<Run, DispatcherManagerError> {
-        self.run_store.store_run(run.clone()).await?;
+        let admitted = self.admit_run(&mut run, concurrency_policy).await?;
+        if !admitted {
+            return Ok(run);
+        }
 
         let run_job = RunJob::from(
             run,
             self.run_store.clone(),
             self.attempt_store.clone(),
+            self.scheduler_clients.clone(),
+            self.signing_secrets.clone(),
+            self.response_body_limit_bytes,
+            self.response_headers_limit_bytes,
         );
 
         Ok(match mode {
@@ -92,12 +126,143 @@ impl DispatchManager {
             | DispatchMode::Sync => run_job.run().await,
         })
     }
+
+    /// Stores the run, and marks it as skipped if the concurrency policy
+    /// doesn't allow it to run. Returns whether the run should be attempted.
+    async fn admit_run(
+        &self,
+        run: &mut Run,
+        concurrency_policy: ConcurrencyPolicy,
+    ) -> Result<bool, DispatcherManagerError> {
+        if !self.run_store.store_new_run(run.clone()).await? {
+            // A concurrent dispatch with the same run id got there first, the
+            // run is attempted by that dispatch.
+            if let Some(existing) =
+                self.run_store.get_run(&run.project_id, &run.id).await?
+            {
+                *run = existing;
+            }
+            return Ok(false);
+        }
+
+        if self
+            .enforce_concurrency_policy(run, concurrency_policy)
+            .await?
+        {
+            return Ok(true);
+        }
+        info!(
+            run_id = %run.id,
+            trigger_id = %run.trigger_id,
+            "Skipping run because a previous run is still attempting",
+        );
+        self.run_store
+            .skip_attempting_run(&run.project_id, &run.id)
+            .await?;
+        run.status = RunStatus::Skipped;
+        emit_run_skipped(run, concurrency_policy);
+        Ok(false)
+    }
+
+    /// Makes the run the active run of its trigger if the concurrency policy
+    /// allows it. Returns false if the new run must be skipped.
+    ///
+    /// The active run of a trigger is only changed if it's still the one we
+    /// looked at, so that concurrent dispatches of the same trigger (even by
+    /// different dispatchers) agree on a single active run.
+    async fn enforce_concurrency_policy(
+        &self,
+        run: &Run,
+        concurrency_policy: ConcurrencyPolicy,
+    ) -> Result<bool, DispatcherManagerError> {
+        if !matches!(
+            concurrency_policy,
+            ConcurrencyPolicy::Forbid | ConcurrencyPolicy::Replace
+        ) {
+            return Ok(true);
+        }
+
+        for _ in 0..ACTIVE_RUN_ATTEMPTS {
+            let active = self
+                .run_store
+                .get_active_run(&run.project_id, &run.trigger_id)
+                .await?;
+            let active_run = match active {
+                | Some(ref active) => {
+                    self.run_store.get_run(&run.project_id, active).await?
+                }
+                | None => None,
+            };
+            match active_run {
+                | Some(active_run)
+                    if active_run.status == RunStatus::Attempting =>
+                {
+                    if concurrency_policy == ConcurrencyPolicy::Forbid {
+                        return Ok(false);
+                    }
+                    // Replace: The active run will stop at its next attempt.
+                    // Only the status is updated since its job keeps
+                    // recording its progress until then, and a run that
+                    // finished in the meantime is left alone.
+                    if self
+                        .run_store
+                        .skip_attempting_run(
+                            &active_run.project_id,
+                            &active_run.id,
+                        )
+                        .await?
+                    {
+                        info!(
+                            run_id = %active_run.id,
+                            trigger_id = %active_run.trigger_id,
+                            "Skipping run as it's being replaced by run {}",
+                            run.id,
+                        );
+                        emit_run_skipped(&active_run, concurrency_policy);
+                    }
+                }
+                | _ => {}
+            }
+            if self
+                .run_store
+                .set_active_run(
+                    &run.project_id,
+                    &run.trigger_id,
+                    &run.id,
+                    active.as_ref(),
+                )
+                .await?
+            {
+                return Ok(true);
+            }
+            // Another run of the trigger became active in the meantime, we
+            // look at it again.
+        }
+        // We keep losing to other runs of the trigger, the latest of them
+        // wins.
+        Ok(false)
+    }
+}
+
+fn emit_run_skipped(run: &Run, concurrency_policy: ConcurrencyPolicy) {
+    counter!("dispatcher.runs_skipped_total", 1);
+    e!(
+        project_id = run.project_id.clone(),
+        RunSkipped {
+            meta: run.meta().into(),
+            concurrency_policy: concurrency_policy.into(),
+        }
+    );
 }
 
 pub struct RunJob {
     pub run: Run,
     run_store: RunStore,
     attempt_store: AttemptStore,
+    scheduler_clients: Arc<GrpcClientProvider<ScopedSchedulerSvcClient>>,
+    signing_secrets: Arc<SigningSecrets>,
+    response_body_limit_bytes: usize,
+    response_headers_limit_bytes: usize,
 }
 
 impl RunJob {
@@ -105,11 +270,19 @@ impl RunJob {
         run: Run,
         run_store: RunStore,
         attempt_store: AttemptStore,
+        scheduler_clients: Arc<GrpcClientProvider<ScopedSchedulerSvcClient>>,
+        signing_secrets: Arc<SigningSecrets>,
+        response_body_limit_bytes: usize,
+        response_headers_limit_bytes: usize,
     ) -> Self {
         Self {
             run,
             run_store,
             attempt_store,
+            scheduler_clients,
+            signing_secrets,
+            response_body_limit_bytes,
+            response_headers_limit_bytes,
         }
     }
 
@@ -123,6 +296,10 @@ impl RunJob {
                     run: self.run,
                     run_store: self.run_store.clone(),
                     attempt_store: self.attempt_store,
+                    signing_secrets: self.signing_secrets.clone(),
+                    response_body_limit_bytes: self.response_body_limit_bytes,
+                    response_headers_limit_bytes: self
+                        .response_headers_limit_bytes,
                 };
                 e.run().await
             }
@@ -161,6 +338,51 @@ impl RunJob {
                 }
             );
         }
+
+        if matches!(run.status, RunStatus::Succeeded | RunStatus::Failed) {
+            notify_run_completed(&self.scheduler_clients, &run).await;
+        }
         run
     }
 }
+
+/// Lets the scheduler run the triggers that depend on the trigger of this run.
+async fn notify_run_completed(
+    scheduler_clients: &GrpcClientProvider<ScopedSchedulerSvcClient>,
+    run: &Run,
+) {
+    let request = RunCompletedRequest {
+        trigger_id: Some(run.trigger_id.clone().into()),
+        run_id: Some(run.id.clone().into()),
+        status: proto::runs::RunStatus::from(run.status.clone()).into(),
+    };
+
+    let mut result = Ok(());
+    // The shard might have moved to another cell since we loaded the shard
+    // routes, in which case we try again with fresh routes.
+    for _ in 0..2 {
+        // This is a system-generated request.
+        let client = scheduler_clients
+            .get_client(&RequestId::new(), &run.project_id)
+            .await;
+        result = match client {
+            | Ok(mut client) => {
+                client.run_completed(request.clone()).await.map(|_| ())
+            }
+            | Err(e) => Err(tonic::Status::unavailable(e.to_string())),
+        };
+        match result {
+            | Err(ref e) if is_shard_moved(e) => {
+                scheduler_clients.expire_shard_routes();
+            }
+            | _ => break,
+        }
+    }
+    if let Err(e) = result {
+        error!(
+            run_id = %run.id,
+            trigger_id = %run.trigger_id,
+            "Failed to notify the scheduler that the run has completed: {}", e
+        );
+    }
+}
@@ -53,10 +53,28 @@ impl DispatcherSvc for DispatcherSvcHandler {
         let request = request.into_inner();
 
         let dispatch_mode = request.mode();
-        let run_id = RunId::generate(&ctx.project_id);
+        let concurrency_policy = request.concurrency_policy();
+        let run_id: RunId = match request.run_id {
+            | Some(run_id) => run_id.into(),
+            | None => RunId::generate(&ctx.project_id).into(),
+        };
+
+        // Callers retry with the same run id, the run might have already been
+        // created by a request whose response was lost. Concurrent retries
+        // are caught when the run gets stored.
+        let existing_run = self
+            .run_store
+            .get_run(&ctx.project_id, &run_id)
+            .map_err(DispatcherHandlerError::Store)
+            .await?;
+        if let Some(run) = existing_run {
+            return Ok(Response::new(DispatchResponse {
+                run: Some(run.into()),
+            }));
+        }
 
         let run = Run {
-            id: run_id.into(),
+            id: run_id,
             trigger_id: request.trigger_id.unwrap().into(),
             project_id: ctx.project_id.clone(),
             created_at: Utc::now(),
@@ -65,6 +83,9 @@ impl DispatcherSvc for DispatcherSvcHandler {
             status: RunStatus::Attempting,
             latest_attempt_id: None,
             latest_attempt: None,
+            scheduled_at: request.scheduled_at.map(Into::into),
+            attempt_count: 0,
+            next_attempt_at: None,
         };
 
         counter!("dispatcher.runs_total", 1);
@@ -76,7 +97,7 @@ impl DispatcherSvc for DispatcherSvcHandler {
         );
         let run = self
             .dispatch_manager
-            .run(run, dispatch_mode)
+            .run(run, dispatch_mode, concurrency_policy)
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
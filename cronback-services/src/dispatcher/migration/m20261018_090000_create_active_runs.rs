@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ActiveRuns::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ActiveRuns::ProjectId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ActiveRuns::TriggerId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ActiveRuns::RunId).string().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(ActiveRuns::ProjectId)
+                            .col(ActiveRuns::TriggerId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ActiveRuns::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ActiveRuns {
+    Table,
+    ProjectId,
+    TriggerId,
+    RunId,
+}
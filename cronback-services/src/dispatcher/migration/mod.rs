@@ -2,6 +2,9 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20230520_213613_create_attempts;
 mod m20230521_221728_create_runs;
+mod m20261016_150000_add_run_scheduled_at;
+mod m20261017_100000_add_run_retry_state;
+mod m20261018_090000_create_active_runs;
 
 pub struct Migrator;
 
@@ -11,6 +14,9 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20230520_213613_create_attempts::Migration),
             Box::new(m20230521_221728_create_runs::Migration),
+            Box::new(m20261016_150000_add_run_scheduled_at::Migration),
+            Box::new(m20261017_100000_add_run_retry_state::Migration),
+            Box::new(m20261018_090000_create_active_runs::Migration),
         ]
     }
 }
@@ -6,13 +6,17 @@ mod handler;
 mod migration;
 mod retry;
 mod run_store;
+mod signature;
 mod webhook_action;
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
 use attempt_store::AttemptStore;
 use dispatch_manager::DispatchManager;
 use lib::prelude::*;
-use lib::{netutils, service};
+use lib::{netutils, service, GrpcClientProvider};
 use metrics::{describe_counter, describe_gauge, Unit};
 use proto::dispatcher_svc::dispatcher_svc_server::DispatcherSvcServer;
 use run_store::RunStore;
@@ -64,10 +68,21 @@ impl CronbackService for DispatcherService {
 
         let run_store = RunStore::new(db);
 
+        let scheduler_clients =
+            Arc::new(GrpcClientProvider::new(context.config().clone()));
+        let signing_secrets = Arc::new(signature::signing_secrets(
+            Box::new(GrpcClientProvider::new(context.config().clone())),
+            Duration::from_secs(svc_config.signing_secrets_cache_ttl_s),
+        ));
+
         let dispatch_manager = DispatchManager::new(
             svc_config.cell_id,
             run_store.clone(),
             attempt_store,
+            scheduler_clients,
+            signing_secrets,
+            svc_config.response_body_limit_bytes,
+            svc_config.response_headers_limit_bytes,
         );
         dispatch_manager.start().await?;
 
@@ -5,6 +5,8 @@ use std::time::Duration;
 
 use lib::prelude::*;
 
+use super::db_model::attempts::{WebhookAttemptDetails, WebhookErrorKind};
+
 // Inclusive of first attempt? Yes. the initial delay will be zero.
 #[derive(Debug)]
 pub struct Retry {
@@ -44,6 +46,81 @@ impl Retry {
         }
     }
 
+    /// Skips the first `attempts` attempts, for runs that already made them
+    /// before the dispatcher restarted.
+    pub fn resume_after(mut self, attempts: u32) -> Self {
+        self.current_attempt =
+            std::cmp::min(attempts, self.total_attempts_limit);
+        self
+    }
+
+    /// Decides whether a failed attempt is retried according to the policy.
+    /// Returns the delay of the next attempt, if there will be one, and the
+    /// reason for the decision.
+    pub fn next_after_failure(
+        &mut self,
+        response: &WebhookAttemptDetails,
+    ) -> (Option<Delay>, String) {
+        if let Err(reason) = self.is_retryable(response) {
+            return (None, reason);
+        }
+        let Some(mut delay) = self.next() else {
+            return (None, "No attempts left".to_string());
+        };
+
+        let retry_after = self
+            .config
+            .as_ref()
+            .and_then(RetryConfig::respect_retry_after)
+            .zip(response.retry_after_s);
+        if let Some((respect_retry_after, retry_after_s)) = retry_after {
+            delay.reschedule(std::cmp::min(
+                Duration::from_secs(retry_after_s),
+                respect_retry_after.max_delay_s,
+            ));
+            let reason = format!(
+                "Retrying after {}s as asked by Retry-After",
+                delay.duration().as_secs_f32()
+            );
+            (Some(delay), reason)
+        } else {
+            let reason =
+                format!("Retrying after {}s", delay.duration().as_secs_f32());
+            (Some(delay), reason)
+        }
+    }
+
+    fn is_retryable(
+        &self,
+        response: &WebhookAttemptDetails,
+    ) -> Result<(), String> {
+        let Some(retry_on) =
+            self.config.as_ref().and_then(RetryConfig::retry_on)
+        else {
+            return Ok(());
+        };
+        match (response.response_code, response.error_kind) {
+            | (Some(code), _) if !retry_on.status_code(code as u32) => {
+                Err(format!("Status code {code} is not retried"))
+            }
+            | (None, Some(WebhookErrorKind::Connection))
+                if !retry_on.connection_errors =>
+            {
+                Err("Connection errors are not retried".to_string())
+            }
+            | (None, Some(WebhookErrorKind::Timeout)) if !retry_on.timeouts => {
+                Err("Timeouts are not retried".to_string())
+            }
+            // The request never left, like with connection errors.
+            | (None, Some(WebhookErrorKind::Signing))
+                if !retry_on.connection_errors =>
+            {
+                Err("Signing errors are not retried".to_string())
+            }
+            | _ => Ok(()),
+        }
+    }
+
     /// Returns the duration to sleep if a retry should be done
     /// or None if we should no longer retry
     fn next_duration(&mut self) -> Option<Duration> {
@@ -100,6 +177,12 @@ impl Delay {
         self.duration
     }
 
+    /// Replaces the delay, restarting it from now.
+    pub fn reschedule(&mut self, duration: Duration) {
+        self.duration = duration;
+        self.sleep = Box::pin(tokio::time::sleep(duration));
+    }
+
     pub fn remaining(&self) -> u32 {
         self.remaining_attempts
     }
@@ -202,6 +285,8 @@ mod tests {
         let config = RetryConfig::SimpleRetry(SimpleRetry {
             max_num_attempts: 3,
             delay_s: dur,
+            retry_on: None,
+            respect_retry_after: None,
         });
 
         let mut retry_policy = Retry::with_config(config);
@@ -249,6 +334,8 @@ mod tests {
                 max_num_attempts: 5,
                 delay_s: min_dur,
                 max_delay_s: max_dur,
+                retry_on: None,
+                respect_retry_after: None,
             });
 
         let mut retry_policy = Retry::with_config(config);
@@ -331,4 +418,174 @@ mod tests {
         // No more attempts
         assert!(retry_policy.next().is_none());
     }
+
+    #[tokio::test]
+    async fn resumed_retry_policy() {
+        let dur = Duration::from_secs(10);
+        let config =
+            RetryConfig::ExponentialBackoffRetry(ExponentialBackoffRetry {
+                max_num_attempts: 4,
+                delay_s: dur,
+                max_delay_s: Duration::from_secs(100),
+                retry_on: None,
+                respect_retry_after: None,
+            });
+
+        // Two attempts were made before the restart.
+        let mut retry_policy =
+            Retry::with_config(config.clone()).resume_after(2);
+        {
+            let mut delay = retry_policy.next().unwrap();
+            assert_eq!(3, delay.attempt_number());
+            assert_eq!(4, delay.attempts_limit());
+            assert!(!delay.first_attempt());
+            // The backoff continues from where it was.
+            assert!(
+                delay.duration().as_millis()
+                    - Duration::from_secs(20).as_millis()
+                    <= 1000
+            );
+
+            delay.reschedule(Duration::from_secs(3));
+            assert_eq!(Duration::from_secs(3), delay.duration());
+        }
+        {
+            let delay = retry_policy.next().unwrap();
+            assert_eq!(4, delay.attempt_number());
+            assert!(delay.last_attempt());
+        }
+        assert!(retry_policy.next().is_none());
+
+        // Runs that exhausted their attempts don't get more.
+        let mut retry_policy = Retry::with_config(config).resume_after(4);
+        assert!(retry_policy.next().is_none());
+    }
+
+    fn response(
+        code: Option<i32>,
+        retry_after_s: Option<u64>,
+    ) -> WebhookAttemptDetails {
+        WebhookAttemptDetails {
+            response_code: code,
+            response_latency_s: Duration::from_millis(100),
+            error_message: None,
+            error_kind: None,
+            retry_after_s,
+            retry_reason: None,
+            failed_criterion: None,
+            response_headers: Default::default(),
+            response_body: None,
+            response_body_truncated: false,
+            error_cause: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_only_configured_failures() {
+        let config = RetryConfig::SimpleRetry(SimpleRetry {
+            max_num_attempts: 10,
+            delay_s: Duration::from_secs(30),
+            retry_on: Some(RetryOn {
+                status_codes: vec![
+                    StatusCodeRange {
+                        start: 429,
+                        end: 429,
+                    },
+                    StatusCodeRange {
+                        start: 500,
+                        end: 599,
+                    },
+                ],
+                connection_errors: true,
+                timeouts: false,
+            }),
+            respect_retry_after: None,
+        });
+        let mut retry_policy = Retry::with_config(config);
+        retry_policy.next().unwrap();
+
+        for code in [429, 500, 503] {
+            let (delay, reason) =
+                retry_policy.next_after_failure(&response(Some(code), None));
+            assert!(delay.is_some(), "Code: {code}");
+            assert!(reason.starts_with("Retrying after 30"), "{reason}");
+        }
+        for code in [400, 404, 302] {
+            let (delay, reason) =
+                retry_policy.next_after_failure(&response(Some(code), None));
+            assert!(delay.is_none(), "Code: {code}");
+            assert_eq!(reason, format!("Status code {code} is not retried"));
+        }
+
+        let connection_error = WebhookAttemptDetails::with_error_kind(
+            WebhookErrorKind::Connection,
+            "Connection Failed".to_string(),
+        );
+        assert!(retry_policy
+            .next_after_failure(&connection_error)
+            .0
+            .is_some());
+
+        // Requests we couldn't sign didn't reach the receiver either.
+        let signing_error = WebhookAttemptDetails::with_error_kind(
+            WebhookErrorKind::Signing,
+            "Failed to sign the request".to_string(),
+        );
+        assert!(retry_policy.next_after_failure(&signing_error).0.is_some());
+
+        let timeout = WebhookAttemptDetails::with_error_kind(
+            WebhookErrorKind::Timeout,
+            "Request timeout".to_string(),
+        );
+        let (delay, reason) = retry_policy.next_after_failure(&timeout);
+        assert!(delay.is_none());
+        assert_eq!(reason, "Timeouts are not retried");
+
+        // Failures that don't come from the receiver are always retried.
+        let other = WebhookAttemptDetails::with_error("Failed".to_string());
+        assert!(retry_policy.next_after_failure(&other).0.is_some());
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_respected_up_to_the_cap() {
+        let config =
+            RetryConfig::ExponentialBackoffRetry(ExponentialBackoffRetry {
+                max_num_attempts: 4,
+                delay_s: Duration::from_secs(10),
+                max_delay_s: Duration::from_secs(100),
+                retry_on: None,
+                respect_retry_after: Some(RespectRetryAfter {
+                    max_delay_s: Duration::from_secs(120),
+                }),
+            });
+        let mut retry_policy = Retry::with_config(config);
+        retry_policy.next().unwrap();
+
+        let (delay, reason) =
+            retry_policy.next_after_failure(&response(Some(429), Some(45)));
+        let delay = delay.unwrap();
+        assert_eq!(2, delay.attempt_number());
+        assert_eq!(Duration::from_secs(45), delay.duration());
+        assert_eq!(reason, "Retrying after 45s as asked by Retry-After");
+
+        // Capped to the max delay.
+        let (delay, _) =
+            retry_policy.next_after_failure(&response(Some(503), Some(3600)));
+        assert_eq!(Duration::from_secs(120), delay.unwrap().duration());
+
+        // Without the header, the policy's backoff applies.
+        let (delay, reason) =
+            retry_policy.next_after_failure(&response(Some(503), None));
+        let delay = delay.unwrap();
+        assert!(
+            delay.duration().as_millis() - Duration::from_secs(40).as_millis()
+                <= 1000
+        );
+        assert!(reason.starts_with("Retrying after 40"), "{reason}");
+
+        let (delay, reason) =
+            retry_policy.next_after_failure(&response(Some(503), Some(1)));
+        assert!(delay.is_none());
+        assert_eq!(reason, "No attempts left");
+    }
 }
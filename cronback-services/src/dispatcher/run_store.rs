@@ -1,9 +1,17 @@
 use lib::prelude::*;
 use proto::common::PaginationIn;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    DbErr,
+    EntityTrait,
+    QueryFilter,
+    Set,
+};
 
 use super::db_model::runs::RunStatus;
-use super::db_model::{runs, Attempts, Run, Runs};
+use super::db_model::{active_runs, runs, ActiveRuns, Attempts, Run, Runs};
 
 pub type RunStoreError = DatabaseError;
 
@@ -23,6 +31,76 @@ impl RunStore {
         Ok(())
     }
 
+    /// Stores the run unless a run with the same id exists already. Returns
+    /// false in that case, the stored run is left untouched.
+    pub async fn store_new_run(&self, run: Run) -> Result<bool, RunStoreError> {
+        let active_model: runs::ActiveModel = run.into();
+        let inserted = Runs::insert(active_model)
+            .on_conflict(
+                OnConflict::columns([
+                    runs::Column::Id,
+                    runs::Column::ProjectId,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(&self.db.orm)
+            .await?;
+        Ok(inserted > 0)
+    }
+
+    /// The run that the concurrency policy of the trigger last let attempt,
+    /// if any.
+    pub async fn get_active_run(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+    ) -> Result<Option<RunId>, RunStoreError> {
+        let res = ActiveRuns::find_by_id((project.clone(), trigger_id.clone()))
+            .one(&self.db.orm)
+            .await?;
+        Ok(res.map(|active| active.run_id))
+    }
+
+    /// Makes `run_id` the active run of the trigger if the active run is
+    /// still `current`, `None` meaning that the trigger has none. Returns
+    /// false if another run became active in the meantime.
+    pub async fn set_active_run(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+        run_id: &RunId,
+        current: Option<&RunId>,
+    ) -> Result<bool, RunStoreError> {
+        let Some(current) = current else {
+            let active = active_runs::ActiveModel {
+                project_id: Set(project.clone()),
+                trigger_id: Set(trigger_id.clone()),
+                run_id: Set(run_id.clone()),
+            };
+            let inserted = ActiveRuns::insert(active)
+                .on_conflict(
+                    OnConflict::columns([
+                        active_runs::Column::ProjectId,
+                        active_runs::Column::TriggerId,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
+                )
+                .exec_without_returning(&self.db.orm)
+                .await?;
+            return Ok(inserted > 0);
+        };
+        let res = ActiveRuns::update_many()
+            .col_expr(active_runs::Column::RunId, Expr::value(run_id.clone()))
+            .filter(active_runs::Column::ProjectId.eq(project.clone()))
+            .filter(active_runs::Column::TriggerId.eq(trigger_id.clone()))
+            .filter(active_runs::Column::RunId.eq(current.clone()))
+            .exec(&self.db.orm)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
     pub async fn update_run(&self, run: Run) -> Result<(), RunStoreError> {
         let project = run.project_id.clone();
         let active_model: runs::ActiveModel = run.into();
@@ -35,6 +113,47 @@ impl RunStore {
         Ok(())
     }
 
+    /// Same as `update_run`, but only if the run is still attempting in the
+    /// database. Returns false if the run has been marked as skipped (or
+    /// finished) in the meantime, the run is left untouched in that case.
+    pub async fn update_attempting_run(
+        &self,
+        run: Run,
+    ) -> Result<bool, RunStoreError> {
+        let project = run.project_id.clone();
+        let active_model: runs::ActiveModel = run.into();
+        // Mark all the fields as dirty
+        let active_model = active_model.reset_all();
+        let res = Runs::update(active_model)
+            .filter(runs::Column::ProjectId.eq(project))
+            .filter(runs::Column::Status.eq(RunStatus::Attempting))
+            .exec(&self.db.orm)
+            .await;
+        match res {
+            | Ok(_) => Ok(true),
+            | Err(DbErr::RecordNotUpdated) => Ok(false),
+            | Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Marks the run as skipped if it's still attempting. Only the status is
+    /// written so that the progress recorded by the run's job is kept.
+    /// Returns false if the run wasn't attempting anymore.
+    pub async fn skip_attempting_run(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        id: &RunId,
+    ) -> Result<bool, RunStoreError> {
+        let res = Runs::update_many()
+            .col_expr(runs::Column::Status, Expr::value(RunStatus::Skipped))
+            .filter(runs::Column::Id.eq(id.clone()))
+            .filter(runs::Column::ProjectId.eq(project.clone()))
+            .filter(runs::Column::Status.eq(RunStatus::Attempting))
+            .exec(&self.db.orm)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
     pub async fn get_run(
         &self,
         project: &ValidShardedId<ProjectId>,
@@ -75,6 +194,28 @@ impl RunStore {
         Ok(PaginatedResponse::paginate(res, &pagination))
     }
 
+    pub async fn get_runs_by_trigger_and_status(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+        status: RunStatus,
+    ) -> Result<Vec<Run>, RunStoreError> {
+        let result = Runs::find()
+            .find_also_related(Attempts)
+            .filter(runs::Column::TriggerId.eq(trigger_id.value()))
+            .filter(runs::Column::ProjectId.eq(project.value()))
+            .filter(runs::Column::Status.eq(status))
+            .all(&self.db.orm)
+            .await?
+            .into_iter()
+            .map(|mut r| {
+                r.0.latest_attempt = r.1;
+                r.0
+            })
+            .collect::<Vec<_>>();
+        Ok(result)
+    }
+
     // Leaving this under cfg(test) until someone actually need it.
     #[cfg(test)]
     pub async fn get_runs_by_project(
@@ -148,11 +289,15 @@ mod tests {
                 http_method: HttpMethod::Get,
                 timeout_s: Duration::from_secs(5),
                 retry: None,
+                success_criteria: None,
             }),
             payload: None,
             status: RunStatus::Attempting,
             latest_attempt_id: None,
             latest_attempt: None,
+            scheduled_at: None,
+            attempt_count: 0,
+            next_attempt_at: None,
         }
     }
 
@@ -191,6 +336,15 @@ mod tests {
         // Test fetching a run with wrong project
         assert_eq!(store.get_run(&project2, &i1.id).await?, None);
 
+        // Storing a run with an existing id keeps the existing run.
+        let mut duplicate = i1.clone();
+        duplicate.status = RunStatus::Skipped;
+        assert!(!store.store_new_run(duplicate).await?);
+        assert_eq!(store.get_run(&project1, &i1.id).await?, Some(i1.clone()));
+        let i4 = build_run(t2.clone(), project2.clone());
+        assert!(store.store_new_run(i4.clone()).await?);
+        assert_eq!(store.get_run(&project2, &i4.id).await?, Some(i4));
+
         // Test get runs by trigger
         let mut results = store
             .get_runs_by_trigger(&project1, &t1, PaginationIn::default())
@@ -217,6 +371,8 @@ mod tests {
         assert_eq!(results.data, expected);
 
         i1.status = RunStatus::Failed;
+        i1.attempt_count = 2;
+        i1.next_attempt_at = Some(Utc::now().with_nanosecond(0).unwrap());
 
         // Update the run
         store.update_run(i1.clone()).await?;
@@ -240,6 +396,95 @@ mod tests {
         let results = store.get_runs_by_status(RunStatus::Failed).await?;
         let expected = vec![i1.clone()];
         assert_eq!(results, expected);
+
+        // Test get_runs_by_trigger_and_status
+        let results = store
+            .get_runs_by_trigger_and_status(
+                &project1,
+                &t1,
+                RunStatus::Attempting,
+            )
+            .await?;
+        let expected = vec![i3.clone()];
+        assert_eq!(results, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skipped_runs_are_not_overwritten() -> anyhow::Result<()> {
+        let db = DispatcherService::in_memory_database().await?;
+        let store = RunStore::new(db);
+
+        let project = ProjectId::generate();
+        let trigger = TriggerId::generate(&project);
+        let mut run = build_run(trigger, project.clone());
+        store.store_run(run.clone()).await?;
+
+        // The job records its progress while the run is attempting.
+        run.attempt_count = 1;
+        run.next_attempt_at = Some(Utc::now().with_nanosecond(0).unwrap());
+        assert!(store.update_attempting_run(run.clone()).await?);
+
+        // Skipping only touches the status.
+        assert!(store.skip_attempting_run(&project, &run.id).await?);
+        let mut expected = run.clone();
+        expected.status = RunStatus::Skipped;
+        assert_eq!(store.get_run(&project, &run.id).await?, Some(expected));
+
+        // The job can't override the skipped status anymore.
+        run.status = RunStatus::Failed;
+        run.attempt_count = 2;
+        assert!(!store.update_attempting_run(run.clone()).await?);
+        let stored = store.get_run(&project, &run.id).await?.unwrap();
+        assert_eq!(stored.status, RunStatus::Skipped);
+        assert_eq!(stored.attempt_count, 1);
+
+        // And runs that aren't attempting can't be skipped.
+        assert!(!store.skip_attempting_run(&project, &run.id).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn active_runs_change_only_from_the_expected_run(
+    ) -> anyhow::Result<()> {
+        let db = DispatcherService::in_memory_database().await?;
+        let store = RunStore::new(db);
+
+        let project = ProjectId::generate();
+        let trigger = TriggerId::generate(&project);
+        let r1: RunId = RunId::generate(&project).into();
+        let r2: RunId = RunId::generate(&project).into();
+        let r3: RunId = RunId::generate(&project).into();
+        assert_eq!(store.get_active_run(&project, &trigger).await?, None);
+
+        // Only one of the runs that saw no active run becomes active.
+        assert!(store.set_active_run(&project, &trigger, &r1, None).await?);
+        assert!(!store.set_active_run(&project, &trigger, &r2, None).await?);
+        assert_eq!(
+            store.get_active_run(&project, &trigger).await?,
+            Some(r1.clone())
+        );
+
+        // Same for the runs that take over from the active run.
+        assert!(
+            store
+                .set_active_run(&project, &trigger, &r2, Some(&r1))
+                .await?
+        );
+        assert!(
+            !store
+                .set_active_run(&project, &trigger, &r3, Some(&r1))
+                .await?
+        );
+        assert_eq!(store.get_active_run(&project, &trigger).await?, Some(r2));
+
+        // Triggers have their own active run.
+        let other_trigger = TriggerId::generate(&project);
+        assert!(
+            store
+                .set_active_run(&project, &other_trigger, &r3, None)
+                .await?
+        );
         Ok(())
     }
 }
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use lib::clients::ScopedMetadataSvcClient;
+use lib::prelude::*;
+use lib::GrpcClientFactory;
+use proto::metadata_svc::GetSigningSecretsRequest;
+use sha2::Sha256;
+use tracing::warn;
+
+type HmacSha256 = Hmac<Sha256>;
+
+type SecretsFetcher =
+    fn(
+        &ValidShardedId<ProjectId>,
+        ScopedMetadataSvcClient,
+    ) -> BoxFuture<'static, Result<Vec<String>, tonic::Status>>;
+
+/// The active signing secrets of projects, newest first. They are cached for
+/// a short while so that rotations take up to the TTL to apply.
+pub(crate) struct SigningSecrets {
+    secrets: ProjectSetting<Vec<String>, SecretsFetcher>,
+    // The secrets we last fetched for each project. They are used while the
+    // metadata service is unreachable, so that attempts (and especially those
+    // of projects without secrets) don't all fail in the meantime.
+    last_known: Mutex<HashMap<ProjectId, Vec<String>>>,
+}
+
+impl SigningSecrets {
+    pub async fn get(
+        &self,
+        project_id: &ValidShardedId<ProjectId>,
+    ) -> Result<Vec<String>, ProjectSettingError> {
+        match self.secrets.get(project_id).await {
+            | Ok(secrets) => {
+                self.last_known
+                    .lock()
+                    .unwrap()
+                    .insert(project_id.inner().clone(), secrets.clone());
+                Ok(secrets)
+            }
+            | Err(e) => {
+                let last_known = self
+                    .last_known
+                    .lock()
+                    .unwrap()
+                    .get(project_id.inner())
+                    .cloned();
+                match last_known {
+                    | Some(secrets) => {
+                        warn!(
+                            %project_id,
+                            "Failed to refresh the signing secrets of the \
+                             project, using the last known ones: {e}"
+                        );
+                        Ok(secrets)
+                    }
+                    | None => Err(e),
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn signing_secrets(
+    metadata_clients: Box<
+        dyn GrpcClientFactory<ClientType = ScopedMetadataSvcClient> + Send,
+    >,
+    ttl: Duration,
+) -> SigningSecrets {
+    SigningSecrets {
+        secrets: ProjectSetting::new(
+            Arc::new(metadata_clients),
+            fetch_signing_secrets as SecretsFetcher,
+            ttl,
+        ),
+        last_known: Default::default(),
+    }
+}
+
+fn fetch_signing_secrets(
+    project_id: &ValidShardedId<ProjectId>,
+    mut client: ScopedMetadataSvcClient,
+) -> BoxFuture<'static, Result<Vec<String>, tonic::Status>> {
+    let request = GetSigningSecretsRequest {
+        id: Some(project_id.clone().into()),
+    };
+    Box::pin(async move {
+        let resp = client.get_signing_secrets(request).await?.into_inner();
+        Ok(resp.secrets.into_iter().map(|s| s.secret).collect())
+    })
+}
+
+/// Builds the value of the signature header of a webhook attempt. The request
+/// is signed with every secret so that receivers can verify it with either
+/// the old or the new secret while rotating them.
+///
+/// The value looks like `t=<timestamp>,v1=<signature>[,v1=<signature>]`, where
+/// each signature is the hex-encoded HMAC-SHA256 of
+/// `<timestamp>.<project id>.<run id>.<attempt num>.<body>`.
+pub(crate) fn sign_attempt(
+    secrets: &[String],
+    timestamp: i64,
+    project_id: &ValidShardedId<ProjectId>,
+    run_id: &RunId,
+    attempt_num: u32,
+    body: &str,
+) -> String {
+    let message = format!("{timestamp}.{project_id}.{run_id}.{attempt_num}.");
+    let mut value = format!("t={timestamp}");
+    for secret in secrets {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+            .expect("HMAC can take a key of any size");
+        mac.update(message.as_bytes());
+        mac.update(body.as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+        value.push_str(",v1=");
+        value.push_str(&signature);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids() -> (ValidShardedId<ProjectId>, RunId) {
+        let project_id = ProjectId::generate();
+        let run_id = RunId::generate(&project_id).inner().clone();
+        (project_id, run_id)
+    }
+
+    #[test]
+    fn signs_with_every_secret() {
+        let (project_id, run_id) = ids();
+        let secrets = vec!["whsec_new".to_string(), "whsec_old".to_string()];
+
+        let value =
+            sign_attempt(&secrets, 1700000000, &project_id, &run_id, 1, "{}");
+        let parts: Vec<&str> = value.split(',').collect();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], "t=1700000000");
+
+        // Each signature matches the one made with the secret on its own.
+        for (secret, part) in secrets.iter().zip(&parts[1..]) {
+            let single = sign_attempt(
+                &[secret.clone()],
+                1700000000,
+                &project_id,
+                &run_id,
+                1,
+                "{}",
+            );
+            assert_eq!(single, format!("t=1700000000,{part}"));
+        }
+        assert_ne!(parts[1], parts[2]);
+    }
+
+    #[test]
+    fn signature_covers_the_request() {
+        let (project_id, run_id) = ids();
+        let secrets = vec!["whsec_secret".to_string()];
+        let sign = |timestamp, attempt_num, body| {
+            sign_attempt(
+                &secrets,
+                timestamp,
+                &project_id,
+                &run_id,
+                attempt_num,
+                body,
+            )
+        };
+
+        let signature =
+            |value: String| value.split_once(",v1=").unwrap().1.to_string();
+
+        let original = signature(sign(1700000000, 1, "hello"));
+        assert_eq!(original, signature(sign(1700000000, 1, "hello")));
+        assert_ne!(original, signature(sign(1700000001, 1, "hello")));
+        assert_ne!(original, signature(sign(1700000000, 2, "hello")));
+        assert_ne!(original, signature(sign(1700000000, 1, "hello!")));
+    }
+
+    #[test]
+    fn signature_is_hmac_sha256() {
+        let project_id = ValidShardedId::<ProjectId>::from_string_unsafe(
+            "prj_026660KA3V4DPVYYS1FMRWGGHKXP0B".to_string(),
+        );
+        let run_id = RunId::from(
+            "run_026660KA3V4DPVYYS1FMRWGGHKXP0B.01H4YV3S2AA4A3F3JPNS9J7HAV"
+                .to_string(),
+        );
+        let timestamp = 1700000000;
+        let value = sign_attempt(
+            &["key".to_string()],
+            timestamp,
+            &project_id,
+            &run_id,
+            3,
+            "body",
+        );
+
+        let mut mac = HmacSha256::new_from_slice(b"key").unwrap();
+        mac.update(
+            format!("{timestamp}.{project_id}.{run_id}.3.body").as_bytes(),
+        );
+        let expected = hex::encode(mac.finalize().into_bytes());
+        assert_eq!(value, format!("t={timestamp},v1={expected}"));
+        assert_eq!(expected.len(), 64);
+    }
+
+    #[test]
+    fn no_secrets_yields_only_the_timestamp() {
+        let (project_id, run_id) = ids();
+        assert_eq!(
+            sign_attempt(&[], 1700000000, &project_id, &run_id, 1, ""),
+            "t=1700000000"
+        );
+    }
+}
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use lib::prelude::*;
 use metrics::counter;
 use proto::events::AttemptMeta;
@@ -14,11 +16,13 @@ use super::db_model::attempts::{
     AttemptDetails,
     AttemptStatus,
     WebhookAttemptDetails,
+    WebhookErrorKind,
 };
 use super::db_model::runs::RunStatus;
 use super::db_model::*;
 use super::retry::Retry;
 use super::run_store::RunStore;
+use super::signature::{sign_attempt, SigningSecrets};
 
 fn to_reqwest_http_method(method: &HttpMethod) -> reqwest::Method {
     match method {
@@ -35,6 +39,9 @@ pub struct WebhookActionJob {
     pub run: Run,
     pub run_store: RunStore,
     pub attempt_store: AttemptStore,
+    pub signing_secrets: Arc<SigningSecrets>,
+    pub response_body_limit_bytes: usize,
+    pub response_headers_limit_bytes: usize,
 }
 
 impl WebhookActionJob {
@@ -45,22 +52,36 @@ impl WebhookActionJob {
         } else {
             Retry::no_retry()
         };
+        // Runs that were reloaded after a restart continue from the attempts
+        // they already made.
+        let mut retry = retry.resume_after(self.run.attempt_count);
 
         info!(
             run_id = %self.run.id,
+            attempt_count = self.run.attempt_count,
             "Executing webhook action",
         );
 
-        for delay in retry {
-            if self.run.status == RunStatus::Succeeded {
-                // No need for further attempts;
-                break;
-            }
+        let mut next_delay = retry.next();
+        if let (Some(delay), Some(next_attempt_at)) =
+            (next_delay.as_mut(), self.run.next_attempt_at)
+        {
+            // The run was backing off when the dispatcher stopped, only the
+            // rest of the delay is left.
+            let remaining = next_attempt_at
+                .signed_duration_since(Utc::now())
+                .to_std()
+                .unwrap_or_default();
+            delay.reschedule(remaining);
+        }
+
+        while let Some(delay) = next_delay.take() {
             // Wait for the delay before retrying
             let attempt_num = delay.attempt_number();
             let attempt_limit = delay.attempts_limit();
+            let first_attempt = delay.first_attempt();
 
-            if !delay.first_attempt() {
+            if !first_attempt {
                 info!(
                     run_id = %self.run.id,
                     project_id = %self.run.project_id,
@@ -73,6 +94,18 @@ impl WebhookActionJob {
             }
             delay.await;
 
+            // A newer run might have replaced this one while we were waiting.
+            if !first_attempt && self.is_skipped().await {
+                info!(
+                    run_id = %self.run.id,
+                    project_id = %self.run.project_id,
+                    trigger_id = %self.run.trigger_id,
+                    "Run was skipped, no further attempts will be made",
+                );
+                self.run.status = RunStatus::Skipped;
+                return self.run;
+            }
+
             info!(
                 run_id = %self.run.id,
                 project_id = %self.run.project_id,
@@ -102,17 +135,65 @@ impl WebhookActionJob {
                     webhook: Some(webhook.clone().into()),
                 }
             );
-            // Actually dispatch the webhook
-            let response = dispatch_webhook(
-                &self.run.trigger_id,
-                &self.run.project_id,
-                &self.run.id,
-                &attempt_id,
-                attempt_num,
-                webhook,
-                &self.run.payload,
-            )
-            .await;
+            // Secrets are looked up on every attempt (through a short-lived
+            // cache) so that rotations apply to the retries of runs that are
+            // already in flight.
+            let mut response = match self
+                .signing_secrets
+                .get(&self.run.project_id)
+                .await
+            {
+                | Ok(signing_secrets) => {
+                    // Actually dispatch the webhook
+                    dispatch_webhook(
+                        &self.run.trigger_id,
+                        &self.run.project_id,
+                        &self.run.id,
+                        &attempt_id,
+                        attempt_num,
+                        webhook,
+                        &self.run.payload,
+                        &signing_secrets,
+                        self.response_body_limit_bytes,
+                        self.response_headers_limit_bytes,
+                    )
+                    .await
+                }
+                | Err(e) => {
+                    error!(
+                        run_id = %self.run.id,
+                        project_id = %self.run.project_id,
+                        "Failed to load the signing secrets of the project: {}",
+                        e
+                    );
+                    // Sending the request unsigned would let receivers that
+                    // verify signatures reject it, so we fail the attempt
+                    // and let it be retried instead.
+                    let mut details = WebhookAttemptDetails::with_error_kind(
+                        WebhookErrorKind::Signing,
+                        "Failed to sign the request".to_string(),
+                    );
+                    details.error_cause = Some(e.to_string());
+                    details
+                }
+            };
+
+            if !response.is_success() {
+                let (delay, reason) = retry.next_after_failure(&response);
+                if delay.is_none() {
+                    info!(
+                        run_id = %self.run.id,
+                        project_id = %self.run.project_id,
+                        trigger_id = %self.run.trigger_id,
+                        "Attempt {}/{} has failed and won't be retried: {}",
+                        attempt_num,
+                        attempt_limit,
+                        reason,
+                    );
+                }
+                next_delay = delay;
+                response.retry_reason = Some(reason);
+            }
 
             // Record the attempt
             let attempt = Attempt {
@@ -166,33 +247,78 @@ impl WebhookActionJob {
                 );
             }
 
-            if let Err(e) = self.run_store.update_run(self.run.clone()).await {
-                // What will happen in case? We will not retry the webhook, but
-                // run will be stuck in "attempting" forever!
-                // A potential recovery mechanism is to look at the Attempts
-                // table (if the attempt was persisted successfully and fix up
-                // the run status.
-                error!(
-                    "Failed to persist run status for run {} for action : {}",
-                    self.run.id, e
-                );
+            self.run.attempt_count = attempt_num;
+            // Persisting when the next attempt is due lets a restarted
+            // dispatcher wait for the rest of the backoff instead of starting
+            // over.
+            self.run.next_attempt_at = next_delay.as_ref().map(|delay| {
+                Utc::now()
+                    + chrono::Duration::from_std(delay.duration()).unwrap()
+            });
+
+            // What will happen in case of an error? We will not retry the
+            // webhook, but run will be stuck in "attempting" forever!
+            // A potential recovery mechanism is to look at the Attempts
+            // table (if the attempt was persisted successfully and fix up
+            // the run status.
+            if !self.persist_run().await {
+                return self.run;
             }
         }
         // Exhausted all retries, or we succeeded.
         if self.run.status != RunStatus::Succeeded {
             self.run.status = RunStatus::Failed;
-            if let Err(e) = self.run_store.update_run(self.run.clone()).await {
+            self.persist_run().await;
+        }
+        self.run
+    }
+
+    /// Writes the run unless it has been skipped in the meantime, in which
+    /// case the run is marked as skipped and false is returned.
+    async fn persist_run(&mut self) -> bool {
+        match self.run_store.update_attempting_run(self.run.clone()).await {
+            | Ok(true) => true,
+            | Ok(false) => {
+                info!(
+                    run_id = %self.run.id,
+                    project_id = %self.run.project_id,
+                    trigger_id = %self.run.trigger_id,
+                    "Run was skipped, no further attempts will be made",
+                );
+                self.run.status = RunStatus::Skipped;
+                false
+            }
+            | Err(e) => {
                 error!(
                     "Failed to persist run status for run {} for action : {}",
                     self.run.id, e
                 );
+                true
+            }
+        }
+    }
+
+    async fn is_skipped(&self) -> bool {
+        match self
+            .run_store
+            .get_run(&self.run.project_id, &self.run.id)
+            .await
+        {
+            | Ok(Some(run)) => run.status == RunStatus::Skipped,
+            | Ok(None) => false,
+            | Err(e) => {
+                error!(
+                    "Failed to load the status of run {} : {}",
+                    self.run.id, e
+                );
+                false
             }
         }
-        self.run
     }
 }
 
-#[tracing::instrument(skip(payload))]
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip(payload, signing_secrets))]
 async fn dispatch_webhook(
     trigger_id: &TriggerId,
     project_id: &ValidShardedId<ProjectId>,
@@ -201,6 +327,9 @@ async fn dispatch_webhook(
     attempt_num: u32,
     webhook: &Webhook,
     payload: &Option<Payload>,
+    signing_secrets: &[String],
+    response_body_limit_bytes: usize,
+    response_headers_limit_bytes: usize,
 ) -> WebhookAttemptDetails {
     let validation_result = webhook.validate();
 
@@ -228,18 +357,7 @@ async fn dispatch_webhook(
         .unwrap();
 
     let http_method = to_reqwest_http_method(&webhook.http_method);
-    // Custom Cronback headers
     let mut http_headers = reqwest::header::HeaderMap::new();
-    http_headers.insert(RUN_ID_HEADER, run_id.to_string().parse().unwrap());
-
-    http_headers
-        .insert(PROJECT_ID_HEADER, project_id.to_string().parse().unwrap());
-
-    http_headers.insert(
-        DELIVERY_ATTEMPT_NUM_HEADER,
-        attempt_num.to_string().parse().unwrap(),
-    );
-
     if let Some(payload) = payload {
         let Ok(user_headers) =
             reqwest::header::HeaderMap::try_from(&payload.headers)
@@ -248,7 +366,6 @@ async fn dispatch_webhook(
                 "Bad request: Invalid header map".to_string(),
             );
         };
-        // The user headers take precedence over the cronback headers.
         http_headers.extend(user_headers);
 
         let Ok(content_type) = HeaderValue::from_str(&payload.content_type)
@@ -261,6 +378,32 @@ async fn dispatch_webhook(
         http_headers.insert(reqwest::header::CONTENT_TYPE, content_type);
     }
 
+    // Custom Cronback headers, added after the user headers so that they
+    // can't be overridden by them. Receivers rely on these to deduplicate
+    // and verify deliveries.
+    http_headers.insert(RUN_ID_HEADER, run_id.to_string().parse().unwrap());
+
+    http_headers
+        .insert(PROJECT_ID_HEADER, project_id.to_string().parse().unwrap());
+
+    http_headers.insert(
+        DELIVERY_ATTEMPT_NUM_HEADER,
+        attempt_num.to_string().parse().unwrap(),
+    );
+
+    if !signing_secrets.is_empty() {
+        let body = payload.as_ref().map(|p| p.body.as_str()).unwrap_or("");
+        let signature = sign_attempt(
+            signing_secrets,
+            Utc::now().timestamp(),
+            project_id,
+            run_id,
+            attempt_num,
+            body,
+        );
+        http_headers.insert(SIGNATURE_HEADER, signature.parse().unwrap());
+    }
+
     let request_start_time = Instant::now();
     let mut request = http_client
         .request(http_method, webhook.url.clone())
@@ -276,25 +419,456 @@ async fn dispatch_webhook(
 
     match response {
         | Ok(resp) => {
+            let status_code = resp.status().as_u16();
+            let retry_after_s = resp
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| parse_retry_after(value, Utc::now()));
+
+            let response_headers =
+                capture_headers(resp.headers(), response_headers_limit_bytes);
+
+            let default_criteria = SuccessCriteria::default();
+            let criteria = webhook
+                .success_criteria
+                .as_ref()
+                .unwrap_or(&default_criteria);
+            // The body is read once for both the success criteria and the
+            // part of it that we keep.
+            let criteria_body_limit = if criteria.needs_body() {
+                SUCCESS_CRITERIA_BODY_LIMIT
+            } else {
+                0
+            };
+            let body = read_body_prefix(
+                resp,
+                std::cmp::max(criteria_body_limit, response_body_limit_bytes),
+            )
+            .await;
+            let failed_criterion = criteria.failed_criterion(
+                status_code.into(),
+                &body[..std::cmp::min(body.len(), criteria_body_limit)],
+            );
+            let (response_body, response_body_truncated) =
+                capture_body(&body, response_body_limit_bytes);
+
             WebhookAttemptDetails {
-                response_code: Some(resp.status().as_u16() as i32),
+                response_code: Some(status_code as i32),
                 response_latency_s: latency,
                 error_message: None,
+                error_kind: None,
+                retry_after_s,
+                retry_reason: None,
+                failed_criterion,
+                response_headers,
+                response_body,
+                response_body_truncated,
+                error_cause: None,
             }
         }
         | Err(e) => {
-            let message = if e.is_connect() {
-                "Connection Failed"
+            let (kind, message) = if e.is_connect() {
+                (WebhookErrorKind::Connection, "Connection Failed")
             } else if e.is_timeout() {
-                "Request timeout"
+                (WebhookErrorKind::Timeout, "Request timeout")
             } else {
-                "Request failed"
-            }
-            .to_string();
+                // The connection broke while the request was in flight.
+                (WebhookErrorKind::Connection, "Request failed")
+            };
 
             debug!("Request for attempt '{}' failed with: {:?}", attempt_id, e);
 
-            WebhookAttemptDetails::with_error(message)
+            WebhookAttemptDetails {
+                error_cause: Some(error_cause(&e)),
+                ..WebhookAttemptDetails::with_error_kind(
+                    kind,
+                    message.to_string(),
+                )
+            }
+        }
+    }
+}
+
+/// Response headers whose values are credentials, they are kept redacted.
+const SENSITIVE_HEADERS: [reqwest::header::HeaderName; 4] = [
+    reqwest::header::AUTHORIZATION,
+    reqwest::header::COOKIE,
+    reqwest::header::PROXY_AUTHORIZATION,
+    reqwest::header::SET_COOKIE,
+];
+
+/// Returns the response headers, with the sensitive ones redacted. Headers
+/// are dropped once their names and values add up to more than `limit`
+/// bytes.
+fn capture_headers(
+    headers: &reqwest::header::HeaderMap,
+    limit: usize,
+) -> HashMap<String, String> {
+    let mut captured: HashMap<String, String> = HashMap::new();
+    let mut size = 0;
+    for (name, value) in headers {
+        let value = if SENSITIVE_HEADERS.contains(name) {
+            "[REDACTED]".into()
+        } else {
+            String::from_utf8_lossy(value.as_bytes())
+        };
+        size += name.as_str().len() + value.len();
+        if size > limit {
+            break;
+        }
+        captured
+            .entry(name.to_string())
+            .and_modify(|v| {
+                // Repeated headers are combined like in RFC 9110.
+                v.push_str(", ");
+                v.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    captured
+}
+
+/// Returns the first `limit` bytes of the body, if any, and whether the body
+/// is longer than that.
+fn capture_body(body: &[u8], limit: usize) -> (Option<String>, bool) {
+    let truncated = body.len() > limit;
+    let body = &body[..std::cmp::min(body.len(), limit)];
+    let captured =
+        (!body.is_empty()).then(|| String::from_utf8_lossy(body).into_owned());
+    (captured, truncated)
+}
+
+/// Describes an error with all its causes, e.g. the DNS, TLS or IO errors
+/// behind a failed request.
+fn error_cause(e: &reqwest::Error) -> String {
+    let mut cause = e.to_string();
+    let mut source = std::error::Error::source(e);
+    while let Some(e) = source {
+        cause.push_str(": ");
+        cause.push_str(&e.to_string());
+        source = e.source();
+    }
+    cause
+}
+
+/// Reads up to `limit + 1` bytes of the response body, so that callers can
+/// tell whether the body is longer than `limit`. A body that fails to be read
+/// is cut where it failed.
+async fn read_body_prefix(
+    mut resp: reqwest::Response,
+    limit: usize,
+) -> Vec<u8> {
+    let mut body = Vec::new();
+    while body.len() <= limit {
+        match resp.chunk().await {
+            | Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            | Ok(None) => break,
+            | Err(e) => {
+                debug!("Failed to read the response body: {:?}", e);
+                break;
+            }
+        }
+    }
+    body.truncate(limit + 1);
+    body
+}
+
+/// Parses the delay in seconds of a `Retry-After` header, which is either a
+/// number of seconds or an HTTP date.
+fn parse_retry_after(value: &HeaderValue, now: DateTime<Utc>) -> Option<u64> {
+    let value = value.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(seconds);
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    // Dates in the past mean that the request can be retried right away.
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(now)
+            .num_seconds()
+            .max(0) as u64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::TimeZone;
+    use lib::{ConfigBuilder, Shutdown};
+    use proto::common::PaginationIn;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::dispatcher::signature::signing_secrets;
+    use crate::dispatcher::DispatcherService;
+    use crate::metadata::{self, MetadataService};
+
+    fn build_run(project: &ValidShardedId<ProjectId>) -> Run {
+        Run {
+            id: RunId::generate(project).into(),
+            trigger_id: TriggerId::generate(project).into(),
+            project_id: project.clone(),
+            created_at: Utc::now(),
+            action: Action::Webhook(Webhook {
+                url: "http://test".to_string(),
+                http_method: HttpMethod::Get,
+                timeout_s: Duration::from_secs(5),
+                // Retrying after an hour lets the test observe a run that is
+                // backing off.
+                retry: Some(RetryConfig::SimpleRetry(SimpleRetry {
+                    max_num_attempts: 2,
+                    delay_s: Duration::from_secs(3600),
+                    retry_on: None,
+                    respect_retry_after: None,
+                })),
+                success_criteria: None,
+            }),
+            payload: None,
+            status: RunStatus::Attempting,
+            latest_attempt_id: None,
+            latest_attempt: None,
+            scheduled_at: None,
+            attempt_count: 0,
+            next_attempt_at: None,
         }
     }
+
+    #[test]
+    fn retry_after_header() {
+        let now = Utc.with_ymd_and_hms(2023, 7, 1, 9, 0, 0).unwrap();
+        let parse =
+            |value: &str| parse_retry_after(&value.parse().unwrap(), now);
+
+        assert_eq!(parse("120"), Some(120));
+        assert_eq!(parse(" 0 "), Some(0));
+        assert_eq!(parse("Sat, 01 Jul 2023 09:01:30 GMT"), Some(90));
+        // Dates in the past
+        assert_eq!(parse("Sat, 01 Jul 2023 08:00:00 GMT"), Some(0));
+        assert_eq!(parse("-5"), None);
+        assert_eq!(parse("soon"), None);
+    }
+
+    #[tokio::test]
+    async fn response_is_captured() {
+        let resp = hyper::Response::builder()
+            .status(503)
+            .header("content-type", "text/plain")
+            .header("x-reason", "maintenance")
+            .header("x-reason", "overloaded")
+            .header("set-cookie", "session=secret")
+            .body("Service Unavailable")
+            .unwrap();
+        let resp = reqwest::Response::from(resp);
+
+        let headers = capture_headers(resp.headers(), 1024);
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers["content-type"], "text/plain");
+        assert_eq!(headers["x-reason"], "maintenance, overloaded");
+        // Credentials aren't kept.
+        assert_eq!(headers["set-cookie"], "[REDACTED]");
+        // Headers past the limit are dropped.
+        let headers = capture_headers(resp.headers(), 30);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["content-type"], "text/plain");
+
+        let body = read_body_prefix(resp, 7).await;
+        assert_eq!(body, b"Service ");
+        assert_eq!(capture_body(&body, 7), (Some("Service".to_string()), true));
+        assert_eq!(
+            capture_body(b"Service", 7),
+            (Some("Service".to_string()), false)
+        );
+        assert_eq!(capture_body(b"", 7), (None, false));
+        // Nothing is kept if the limit is zero.
+        assert_eq!(capture_body(b"Service", 0), (None, true));
+    }
+
+    #[tokio::test]
+    async fn error_causes_are_captured() {
+        // Nothing listens on port 1.
+        let e = reqwest::get("http://127.0.0.1:1/").await.unwrap_err();
+        assert!(e.is_connect());
+        let cause = error_cause(&e);
+        assert!(cause.starts_with(&e.to_string()), "{cause}");
+        assert!(cause.len() > e.to_string().len(), "{cause}");
+    }
+
+    #[tokio::test]
+    async fn cronback_headers_are_not_overridden() -> anyhow::Result<()> {
+        // Answers a single request and hands over its head.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/", listener.local_addr()?);
+        let receiver = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0; 4096];
+            while !head.ends_with(b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                assert!(n > 0, "connection closed mid-request");
+                head.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(head).unwrap()
+        });
+
+        let project = ProjectId::generate();
+        let mut run = build_run(&project);
+        let Action::Webhook(ref mut webhook) = run.action;
+        webhook.url = url;
+        let payload = Some(Payload {
+            headers: [
+                (RUN_ID_HEADER, "run_forged"),
+                (PROJECT_ID_HEADER, "prj_forged"),
+                (DELIVERY_ATTEMPT_NUM_HEADER, "42"),
+                (SIGNATURE_HEADER, "t=0,v1=forged"),
+                ("x-custom", "kept"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            content_type: "application/json".to_string(),
+            body: String::new(),
+        });
+        let response = dispatch_webhook(
+            &run.trigger_id,
+            &project,
+            &run.id,
+            &AttemptId::generate(&project),
+            3,
+            webhook,
+            &payload,
+            &["whsec_secret".to_string()],
+            1024,
+        )
+        .await;
+        assert!(response.is_success());
+
+        let head = receiver.await?;
+        let values = |name: &str| {
+            head.lines()
+                .filter_map(|line| line.split_once(": "))
+                .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values(RUN_ID_HEADER), vec![run.id.to_string()]);
+        assert_eq!(values(PROJECT_ID_HEADER), vec![project.to_string()]);
+        assert_eq!(values(DELIVERY_ATTEMPT_NUM_HEADER), vec!["3"]);
+        let signature = values(SIGNATURE_HEADER);
+        assert_eq!(signature.len(), 1);
+        assert!(!signature[0].contains("forged"));
+        assert_eq!(values("x-custom"), vec!["kept"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retries_resume_after_a_restart() -> anyhow::Result<()> {
+        let db = DispatcherService::in_memory_database().await?;
+        let run_store = RunStore::new(db.clone());
+        let attempt_store = AttemptStore::new(db);
+        let config = ConfigBuilder::default()
+            .register_service::<MetadataService>()
+            .build_once()?;
+        let context =
+            MetadataService::make_context(config, Shutdown::default());
+        let (_serve_future, metadata_clients) =
+            metadata::test_helpers::test_server_and_client(context).await;
+        let signing_secrets = Arc::new(signing_secrets(
+            Box::new(metadata_clients),
+            Duration::from_secs(60),
+        ));
+        let make_job = |run| {
+            WebhookActionJob {
+                run,
+                run_store: run_store.clone(),
+                attempt_store: attempt_store.clone(),
+                signing_secrets: signing_secrets.clone(),
+                response_body_limit_bytes: 1024,
+                response_headers_limit_bytes: 1024,
+            }
+        };
+
+        let project = ProjectId::generate();
+        let mut run = build_run(&project);
+        // Nothing listens on port 1, so every attempt fails.
+        let Action::Webhook(ref mut webhook) = run.action;
+        webhook.url = "http://127.0.0.1:1/".to_string();
+        run_store.store_run(run.clone()).await?;
+
+        let attempt_nums = |run_id: RunId| {
+            let attempt_store = attempt_store.clone();
+            let project = project.clone();
+            async move {
+                let attempts = attempt_store
+                    .get_attempts_for_run(
+                        &project,
+                        &run_id,
+                        PaginationIn::default(),
+                    )
+                    .await
+                    .unwrap();
+                let mut nums: Vec<u32> =
+                    attempts.data.iter().map(|a| a.attempt_num).collect();
+                nums.sort();
+                nums
+            }
+        };
+
+        // The dispatcher stops while the run is backing off after its first
+        // attempt.
+        let job = tokio::spawn(make_job(run.clone()).run());
+        let run = loop {
+            let run = run_store.get_run(&project, &run.id).await?.unwrap();
+            if run.next_attempt_at.is_some() {
+                break run;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        job.abort();
+
+        assert_eq!(run.status, RunStatus::Attempting);
+        assert_eq!(run.attempt_count, 1);
+        let backoff = run.next_attempt_at.unwrap() - Utc::now();
+        assert!(backoff > chrono::Duration::minutes(59));
+        assert_eq!(attempt_nums(run.id.clone()).await, vec![1]);
+
+        // After the restart, the run keeps waiting for the rest of its
+        // backoff instead of retrying right away.
+        let mut pending =
+            run_store.get_runs_by_status(RunStatus::Attempting).await?;
+        assert_eq!(pending.len(), 1);
+        let run = pending.remove(0);
+        assert!(run.next_attempt_at.unwrap() > Utc::now());
+        let job = tokio::spawn(make_job(run.clone()).run());
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!job.is_finished());
+        job.abort();
+
+        let stored = run_store.get_run(&project, &run.id).await?.unwrap();
+        assert_eq!(stored.attempt_count, 1);
+        assert_eq!(stored.next_attempt_at, run.next_attempt_at);
+        assert_eq!(attempt_nums(run.id.clone()).await, vec![1]);
+
+        // Once the backoff has elapsed, the run continues with its second and
+        // last attempt.
+        let mut run = stored;
+        run.next_attempt_at = Some(Utc::now());
+        let run =
+            tokio::time::timeout(Duration::from_secs(10), make_job(run).run())
+                .await?;
+
+        assert_eq!(run.status, RunStatus::Failed);
+        assert_eq!(run.attempt_count, 2);
+        assert_eq!(run.next_attempt_at, None);
+        assert_eq!(
+            run_store.get_run(&project, &run.id).await?.unwrap().status,
+            RunStatus::Failed
+        );
+        assert_eq!(attempt_nums(run.id.clone()).await, vec![1, 2]);
+        Ok(())
+    }
 }
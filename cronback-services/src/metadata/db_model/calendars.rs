@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use dto::{FromProto, IntoProto};
+use lib::prelude::*;
+use sea_orm::entity::prelude::*;
+use sea_orm::FromJsonQueryResult;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "calendars")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub project_id: ValidShardedId<ProjectId>,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+    pub description: Option<String>,
+    pub timezone: String,
+    pub dates: CalendarDates,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl PaginatedEntity for Entity {
+    fn cursor_column() -> Self::Column {
+        Column::Name
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(
+    Clone,
+    Default,
+    Debug,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    FromJsonQueryResult,
+)]
+pub struct CalendarDates(pub Vec<DateRange>);
+
+#[derive(
+    Clone, Debug, Serialize, Deserialize, PartialEq, Eq, FromProto, IntoProto,
+)]
+#[proto(target = "proto::calendars::DateRange")]
+pub struct DateRange {
+    pub start: String,
+    pub end: String,
+}
+
+impl From<Model> for proto::calendars::Calendar {
+    fn from(value: Model) -> Self {
+        Self {
+            name: value.name,
+            description: value.description,
+            timezone: value.timezone,
+            dates: value.dates.0.into_iter().map(Into::into).collect(),
+        }
+    }
+}
@@ -1,4 +1,13 @@
+pub mod calendars;
 pub mod notifications;
 pub mod projects;
+pub mod shard_assignments;
+pub mod signing_secrets;
 
+pub use calendars::{Entity as Calendars, Model as Calendar};
 pub use projects::{Entity as Projects, Model as Project, ProjectStatus};
+pub use shard_assignments::{
+    Entity as ShardAssignments,
+    Model as ShardAssignment,
+};
+pub use signing_secrets::{Entity as SigningSecrets, Model as SigningSecret};
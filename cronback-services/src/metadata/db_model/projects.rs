@@ -16,6 +16,9 @@ pub struct Model {
     pub changed_at: DateTime<Utc>,
     pub status: ProjectStatus,
     pub notification_settings: NotificationSettings,
+    /// Seconds after which cancelled and expired triggers are deleted. The
+    /// scheduler's default applies if unset.
+    pub trigger_retention_s: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
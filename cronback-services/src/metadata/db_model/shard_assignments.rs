@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+
+/// The scheduler cell serving a shard, for shards that were moved away from
+/// the cell the config assigns them to.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "shard_assignments")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub shard: i64,
+    pub cell_id: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<Model> for proto::metadata_svc::ShardAssignment {
+    fn from(value: Model) -> Self {
+        Self {
+            shard: value.shard as u64,
+            cell_id: value.cell_id as u64,
+        }
+    }
+}
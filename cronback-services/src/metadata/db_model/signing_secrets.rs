@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use lib::prelude::*;
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "signing_secrets")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub project_id: ValidShardedId<ProjectId>,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: String,
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Whether deliveries are still signed with this secret at `now`.
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        match self.expires_at {
+            | Some(expires_at) => expires_at > now,
+            | None => true,
+        }
+    }
+}
+
+impl From<Model> for proto::projects::SigningSecret {
+    fn from(value: Model) -> Self {
+        Self {
+            id: value.id,
+            secret: value.secret,
+            created_at: Some(value.created_at.into()),
+            expires_at: value.expires_at.map(Into::into),
+        }
+    }
+}
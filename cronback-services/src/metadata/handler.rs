@@ -1,28 +1,57 @@
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use lib::prelude::*;
 use lib::service::ServiceContext;
+use proto::common::UpsertEffect;
 use proto::metadata_svc::metadata_svc_server::MetadataSvc;
 use proto::metadata_svc::{
+    AssignShardsRequest,
+    AssignShardsResponse,
     CreateProjectRequest,
     CreateProjectResponse,
+    CreateSigningSecretRequest,
+    CreateSigningSecretResponse,
+    DeleteCalendarRequest,
+    DeleteCalendarResponse,
+    DeleteSigningSecretRequest,
+    DeleteSigningSecretResponse,
+    GetCalendarsRequest,
+    GetCalendarsResponse,
     GetNotificationSettingsRequest,
     GetNotificationSettingsResponse,
     GetProjectStatusRequest,
     GetProjectStatusResponse,
+    GetShardAssignmentsRequest,
+    GetShardAssignmentsResponse,
+    GetSigningSecretsRequest,
+    GetSigningSecretsResponse,
+    GetTriggerRetentionRequest,
+    GetTriggerRetentionResponse,
+    ListCalendarsRequest,
+    ListCalendarsResponse,
     ProjectExistsRequest,
     ProjectExistsResponse,
     SetNotificationSettingsRequest,
     SetNotificationSettingsResponse,
     SetProjectStatusRequest,
     SetProjectStatusResponse,
+    SetTriggerRetentionRequest,
+    SetTriggerRetentionResponse,
+    UpsertCalendarRequest,
+    UpsertCalendarResponse,
 };
 use thiserror::Error;
 use tonic::{Request, Response, Status};
+use uuid::Uuid;
 
-use super::db_model::{Project, ProjectStatus};
+use super::db_model::calendars::CalendarDates;
+use super::db_model::{Calendar, Project, ProjectStatus, SigningSecret};
 use super::metadata_store::MetadataStore;
 use super::MetadataService;
 
+/// Deliveries are signed with every active secret, two are enough to rotate
+/// secrets without downtime.
+const MAX_ACTIVE_SIGNING_SECRETS: usize = 2;
+
 pub(crate) struct MetadataSvcHandler {
     #[allow(unused)]
     context: ServiceContext<MetadataService>,
@@ -58,6 +87,7 @@ impl MetadataSvc for MetadataSvcHandler {
             changed_at: Utc::now(),
             status: ProjectStatus::Enabled,
             notification_settings: Default::default(),
+            trigger_retention_s: None,
         };
 
         self.project_store
@@ -209,6 +239,71 @@ impl MetadataSvc for MetadataSvcHandler {
         }))
     }
 
+    async fn get_trigger_retention(
+        &self,
+        request: Request<GetTriggerRetentionRequest>,
+    ) -> Result<Response<GetTriggerRetentionResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let retention = self
+            .project_store
+            .get_trigger_retention(&project_id)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+
+        let Some(retention_s) = retention else {
+            return Err(ProjectStoreHandlerError::NotFound(
+                project_id.to_string(),
+            )
+            .into());
+        };
+
+        Ok(Response::new(GetTriggerRetentionResponse {
+            retention_s: retention_s.map(|s| s as u64),
+        }))
+    }
+
+    async fn set_trigger_retention(
+        &self,
+        request: Request<SetTriggerRetentionRequest>,
+    ) -> Result<Response<SetTriggerRetentionResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let retention_s = req
+            .retention_s
+            .map(i64::try_from)
+            .transpose()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let old_retention = self
+            .project_store
+            .get_trigger_retention(&project_id)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+
+        let Some(old_retention_s) = old_retention else {
+            return Err(ProjectStoreHandlerError::NotFound(
+                project_id.to_string(),
+            )
+            .into());
+        };
+
+        self.project_store
+            .set_trigger_retention(&project_id, retention_s)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+
+        Ok(Response::new(SetTriggerRetentionResponse {
+            old_retention_s: old_retention_s.map(|s| s as u64),
+        }))
+    }
+
     async fn project_exists(
         &self,
         request: Request<ProjectExistsRequest>,
@@ -225,12 +320,270 @@ impl MetadataSvc for MetadataSvcHandler {
             .map_err(ProjectStoreHandlerError::Store)?;
         Ok(Response::new(ProjectExistsResponse { exists }))
     }
+
+    async fn upsert_calendar(
+        &self,
+        request: Request<UpsertCalendarRequest>,
+    ) -> Result<Response<UpsertCalendarResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let calendar = req.calendar.unwrap();
+
+        let created = self
+            .project_store
+            .upsert_calendar(Calendar {
+                project_id,
+                name: calendar.name,
+                description: calendar.description,
+                timezone: calendar.timezone,
+                dates: CalendarDates(
+                    calendar.dates.into_iter().map(Into::into).collect(),
+                ),
+                created_at: Utc::now(),
+                updated_at: None,
+            })
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+
+        let effect = if created {
+            UpsertEffect::Created
+        } else {
+            UpsertEffect::Modified
+        };
+        Ok(Response::new(UpsertCalendarResponse {
+            effect: effect.into(),
+        }))
+    }
+
+    async fn get_calendars(
+        &self,
+        request: Request<GetCalendarsRequest>,
+    ) -> Result<Response<GetCalendarsResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let calendars = self
+            .project_store
+            .get_calendars(&project_id, &req.names)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        Ok(Response::new(GetCalendarsResponse {
+            calendars: calendars.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn list_calendars(
+        &self,
+        request: Request<ListCalendarsRequest>,
+    ) -> Result<Response<ListCalendarsResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let PaginatedResponse { data, pagination } = self
+            .project_store
+            .list_calendars(&project_id, &req.pagination.unwrap_or_default())
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        Ok(Response::new(ListCalendarsResponse {
+            calendars: data.into_iter().map(Into::into).collect(),
+            pagination: Some(pagination),
+        }))
+    }
+
+    async fn delete_calendar(
+        &self,
+        request: Request<DeleteCalendarRequest>,
+    ) -> Result<Response<DeleteCalendarResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let deleted = self
+            .project_store
+            .delete_calendar(&project_id, &req.name)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        if !deleted {
+            return Err(
+                ProjectStoreHandlerError::CalendarNotFound(req.name).into()
+            );
+        }
+        Ok(Response::new(DeleteCalendarResponse {}))
+    }
+
+    async fn create_signing_secret(
+        &self,
+        request: Request<CreateSigningSecretRequest>,
+    ) -> Result<Response<CreateSigningSecretResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let expire_existing_after = req
+            .expire_existing_after_s
+            .map(i64::try_from)
+            .transpose()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?
+            .map(Duration::seconds);
+
+        let exists = self
+            .project_store
+            .exists(&project_id)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        if !exists {
+            return Err(ProjectStoreHandlerError::NotFound(
+                project_id.to_string(),
+            )
+            .into());
+        }
+
+        let now = Utc::now();
+        self.project_store
+            .delete_expired_signing_secrets(&project_id, now)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        let active = self
+            .project_store
+            .get_active_signing_secrets(&project_id, now)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        // The existing secrets remain active during the grace period.
+        let remaining = match expire_existing_after {
+            | Some(after) if after == Duration::zero() => 0,
+            | _ => active.len(),
+        };
+        if remaining >= MAX_ACTIVE_SIGNING_SECRETS {
+            return Err(ProjectStoreHandlerError::TooManySigningSecrets(
+                MAX_ACTIVE_SIGNING_SECRETS,
+            )
+            .into());
+        }
+
+        if let Some(after) = expire_existing_after {
+            self.project_store
+                .expire_signing_secrets(&project_id, now + after)
+                .await
+                .map_err(ProjectStoreHandlerError::Store)?;
+        }
+        let secret = SigningSecret {
+            project_id,
+            id: Uuid::new_v4().simple().to_string(),
+            secret: generate_signing_secret(),
+            created_at: now,
+            expires_at: None,
+        };
+        self.project_store
+            .add_signing_secret(secret.clone())
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+
+        Ok(Response::new(CreateSigningSecretResponse {
+            secret: Some(secret.into()),
+        }))
+    }
+
+    async fn get_signing_secrets(
+        &self,
+        request: Request<GetSigningSecretsRequest>,
+    ) -> Result<Response<GetSigningSecretsResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let secrets = self
+            .project_store
+            .get_active_signing_secrets(&project_id, Utc::now())
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        Ok(Response::new(GetSigningSecretsResponse {
+            secrets: secrets.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn delete_signing_secret(
+        &self,
+        request: Request<DeleteSigningSecretRequest>,
+    ) -> Result<Response<DeleteSigningSecretResponse>, Status> {
+        let req = request.into_inner();
+        let project_id: ProjectId = req.id.unwrap().into();
+        let project_id = project_id
+            .validated()
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let deleted = self
+            .project_store
+            .delete_signing_secret(&project_id, &req.secret_id)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        if !deleted {
+            return Err(ProjectStoreHandlerError::SigningSecretNotFound(
+                req.secret_id,
+            )
+            .into());
+        }
+        Ok(Response::new(DeleteSigningSecretResponse {}))
+    }
+
+    async fn assign_shards(
+        &self,
+        request: Request<AssignShardsRequest>,
+    ) -> Result<Response<AssignShardsResponse>, Status> {
+        let req = request.into_inner();
+        if let Some(shard) = req.shards.iter().find(|s| **s >= SHARD_COUNT) {
+            return Err(Status::invalid_argument(format!(
+                "Shard {shard} is out of range"
+            )));
+        }
+        self.project_store
+            .assign_shards(&req.shards, req.cell_id)
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        Ok(Response::new(AssignShardsResponse {}))
+    }
+
+    async fn get_shard_assignments(
+        &self,
+        _request: Request<GetShardAssignmentsRequest>,
+    ) -> Result<Response<GetShardAssignmentsResponse>, Status> {
+        let assignments = self
+            .project_store
+            .get_shard_assignments()
+            .await
+            .map_err(ProjectStoreHandlerError::Store)?;
+        Ok(Response::new(GetShardAssignmentsResponse {
+            assignments: assignments.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+fn generate_signing_secret() -> String {
+    let bytes: [u8; 32] = rand::random();
+    format!("whsec_{}", hex::encode(bytes))
 }
 
 #[derive(Error, Debug)]
 pub(crate) enum ProjectStoreHandlerError {
     #[error("Project '{0}' is unknown to the store!")]
     NotFound(String),
+    #[error("Calendar '{0}' doesn't exist")]
+    CalendarNotFound(String),
+    #[error("Signing secret '{0}' doesn't exist")]
+    SigningSecretNotFound(String),
+    #[error(
+        "A project can't have more than {0} active signing secrets, delete \
+         one or expire the existing ones"
+    )]
+    TooManySigningSecrets(usize),
     #[error("Operation on underlying database failed: {0}")]
     Store(#[from] DatabaseError),
 }
@@ -240,6 +593,13 @@ impl From<ProjectStoreHandlerError> for Status {
         // match variants of TriggerError
         match e {
             | ProjectStoreHandlerError::NotFound(e) => Status::not_found(e),
+            | e @ (ProjectStoreHandlerError::CalendarNotFound(_)
+            | ProjectStoreHandlerError::SigningSecretNotFound(_)) => {
+                Status::not_found(e.to_string())
+            }
+            | e @ ProjectStoreHandlerError::TooManySigningSecrets(_) => {
+                Status::failed_precondition(e.to_string())
+            }
             | e => Status::invalid_argument(e.to_string()),
         }
     }
@@ -1,9 +1,33 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use lib::prelude::*;
-use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use proto::common::PaginationIn;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{
+    ActiveModelTrait,
+    ColumnTrait,
+    Condition,
+    EntityTrait,
+    QueryFilter,
+    QueryOrder,
+    Set,
+};
 
 use super::db_model::notifications::NotificationSettings;
-use super::db_model::{projects, Project, ProjectStatus, Projects};
+use super::db_model::{
+    calendars,
+    projects,
+    shard_assignments,
+    signing_secrets,
+    Calendar,
+    Calendars,
+    Project,
+    ProjectStatus,
+    Projects,
+    ShardAssignment,
+    ShardAssignments,
+    SigningSecret,
+    SigningSecrets,
+};
 
 pub type MetadataStoreError = DatabaseError;
 
@@ -78,12 +102,227 @@ impl MetadataStore {
             .map(|p| p.notification_settings))
     }
 
+    pub async fn set_trigger_retention(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+        retention_s: Option<i64>,
+    ) -> Result<(), MetadataStoreError> {
+        let active_model = projects::ActiveModel {
+            id: Set(id.clone()),
+            trigger_retention_s: Set(retention_s),
+            changed_at: Set(Utc::now()),
+            ..Default::default()
+        };
+
+        active_model.update(&self.db.orm).await?;
+        Ok(())
+    }
+
+    /// Returns `None` if the project doesn't exist, and `Some(None)` if the
+    /// project doesn't override the default retention.
+    pub async fn get_trigger_retention(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+    ) -> Result<Option<Option<i64>>, MetadataStoreError> {
+        Ok(Projects::find_by_id(id.clone())
+            .one(&self.db.orm)
+            .await?
+            .map(|p| p.trigger_retention_s))
+    }
+
     pub async fn exists(
         &self,
         id: &ValidShardedId<ProjectId>,
     ) -> Result<bool, MetadataStoreError> {
         Ok(self.get_status(id).await?.is_some())
     }
+
+    /// Inserts the calendar or replaces the existing calendar with the same
+    /// name. Returns true if the calendar didn't exist before.
+    pub async fn upsert_calendar(
+        &self,
+        calendar: Calendar,
+    ) -> Result<bool, MetadataStoreError> {
+        let existing = Calendars::find_by_id((
+            calendar.project_id.clone(),
+            calendar.name.clone(),
+        ))
+        .one(&self.db.orm)
+        .await?;
+
+        let mut active_model: calendars::ActiveModel = calendar.into();
+        let Some(existing) = existing else {
+            active_model.insert(&self.db.orm).await?;
+            return Ok(true);
+        };
+
+        active_model.created_at = Set(existing.created_at);
+        active_model.updated_at = Set(Some(Utc::now()));
+        // Mark all the fields as dirty
+        active_model.reset_all().update(&self.db.orm).await?;
+        Ok(false)
+    }
+
+    /// Returns the calendars of the project with the given names, or all of
+    /// them if `names` is empty.
+    pub async fn get_calendars(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+        names: &[String],
+    ) -> Result<Vec<Calendar>, MetadataStoreError> {
+        let mut query = Calendars::find()
+            .filter(calendars::Column::ProjectId.eq(id.clone()))
+            .order_by_asc(calendars::Column::Name);
+        if !names.is_empty() {
+            query = query.filter(calendars::Column::Name.is_in(names));
+        }
+        Ok(query.all(&self.db.orm).await?)
+    }
+
+    /// Lists the calendars of the project, a page at a time.
+    pub async fn list_calendars(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+        pagination: &PaginationIn,
+    ) -> Result<PaginatedResponse<Calendar>, MetadataStoreError> {
+        let calendars = Calendars::find()
+            .filter(calendars::Column::ProjectId.eq(id.clone()))
+            .with_pagination(pagination)
+            .all(&self.db.orm)
+            .await?;
+        Ok(PaginatedResponse::paginate(calendars, pagination))
+    }
+
+    /// Returns false if the calendar didn't exist.
+    pub async fn delete_calendar(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+        name: &str,
+    ) -> Result<bool, MetadataStoreError> {
+        let res = Calendars::delete_by_id((id.clone(), name.to_owned()))
+            .exec(&self.db.orm)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    pub async fn add_signing_secret(
+        &self,
+        secret: SigningSecret,
+    ) -> Result<(), MetadataStoreError> {
+        let active_model: signing_secrets::ActiveModel = secret.into();
+        active_model.insert(&self.db.orm).await?;
+        Ok(())
+    }
+
+    /// Returns the secrets of the project that are still used to sign
+    /// deliveries at `now`, newest first.
+    pub async fn get_active_signing_secrets(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<SigningSecret>, MetadataStoreError> {
+        Ok(SigningSecrets::find()
+            .filter(signing_secrets::Column::ProjectId.eq(id.clone()))
+            .filter(
+                Condition::any()
+                    .add(signing_secrets::Column::ExpiresAt.is_null())
+                    .add(signing_secrets::Column::ExpiresAt.gt(now)),
+            )
+            .order_by_desc(signing_secrets::Column::CreatedAt)
+            .all(&self.db.orm)
+            .await?)
+    }
+
+    /// Makes the secrets of the project expire at `expires_at`, unless they
+    /// expire earlier already.
+    pub async fn expire_signing_secrets(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), MetadataStoreError> {
+        SigningSecrets::update_many()
+            .col_expr(
+                signing_secrets::Column::ExpiresAt,
+                Expr::value(expires_at),
+            )
+            .filter(signing_secrets::Column::ProjectId.eq(id.clone()))
+            .filter(
+                Condition::any()
+                    .add(signing_secrets::Column::ExpiresAt.is_null())
+                    .add(signing_secrets::Column::ExpiresAt.gt(expires_at)),
+            )
+            .exec(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the secrets of the project that expired at or before `now`.
+    pub async fn delete_expired_signing_secrets(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+        now: DateTime<Utc>,
+    ) -> Result<(), MetadataStoreError> {
+        SigningSecrets::delete_many()
+            .filter(signing_secrets::Column::ProjectId.eq(id.clone()))
+            .filter(signing_secrets::Column::ExpiresAt.lte(now))
+            .exec(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns false if the secret didn't exist.
+    pub async fn delete_signing_secret(
+        &self,
+        id: &ValidShardedId<ProjectId>,
+        secret_id: &str,
+    ) -> Result<bool, MetadataStoreError> {
+        let res =
+            SigningSecrets::delete_by_id((id.clone(), secret_id.to_owned()))
+                .exec(&self.db.orm)
+                .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    /// Assigns the shards to a scheduler cell, replacing their previous
+    /// assignment.
+    pub async fn assign_shards(
+        &self,
+        shards: &[u64],
+        cell_id: u64,
+    ) -> Result<(), MetadataStoreError> {
+        if shards.is_empty() {
+            return Ok(());
+        }
+        let now = Utc::now();
+        let models = shards.iter().map(|shard| {
+            shard_assignments::ActiveModel {
+                shard: Set(*shard as i64),
+                cell_id: Set(cell_id as i64),
+                updated_at: Set(now),
+            }
+        });
+        ShardAssignments::insert_many(models)
+            .on_conflict(
+                OnConflict::column(shard_assignments::Column::Shard)
+                    .update_columns([
+                        shard_assignments::Column::CellId,
+                        shard_assignments::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_shard_assignments(
+        &self,
+    ) -> Result<Vec<ShardAssignment>, MetadataStoreError> {
+        Ok(ShardAssignments::find()
+            .order_by_asc(shard_assignments::Column::Shard)
+            .all(&self.db.orm)
+            .await?)
+    }
 }
 
 #[cfg(test)]
@@ -91,7 +330,10 @@ mod tests {
 
     use std::collections::HashMap;
 
+    use chrono::Timelike;
+
     use super::*;
+    use crate::metadata::db_model::calendars::{CalendarDates, DateRange};
     use crate::metadata::db_model::notifications::{
         EmailNotification,
         NotificationChannel,
@@ -110,6 +352,7 @@ mod tests {
             changed_at: now,
             status,
             notification_settings: Default::default(),
+            trigger_retention_s: None,
         }
     }
 
@@ -185,6 +428,175 @@ mod tests {
             assert_eq!(found, Some(setting));
         }
 
+        // Test trigger retention setters / getters
+        {
+            assert_eq!(
+                store.get_trigger_retention(&project1.id).await?,
+                Some(None)
+            );
+            store
+                .set_trigger_retention(&project1.id, Some(3600))
+                .await?;
+            assert_eq!(
+                store.get_trigger_retention(&project1.id).await?,
+                Some(Some(3600))
+            );
+            store.set_trigger_retention(&project1.id, None).await?;
+            assert_eq!(
+                store.get_trigger_retention(&project1.id).await?,
+                Some(None)
+            );
+            assert_eq!(
+                store.get_trigger_retention(&ProjectId::generate()).await?,
+                None
+            );
+        }
+
+        // Test calendars
+        {
+            let holidays = Calendar {
+                project_id: project1.id.clone(),
+                name: "holidays".to_string(),
+                description: None,
+                timezone: "Europe/London".to_string(),
+                dates: CalendarDates(vec![DateRange {
+                    start: "2023-12-25".to_string(),
+                    end: "2023-12-26".to_string(),
+                }]),
+                created_at: Utc::now().with_nanosecond(0).unwrap(),
+                updated_at: None,
+            };
+            let mut maintenance = holidays.clone();
+            maintenance.name = "maintenance".to_string();
+
+            assert!(store.upsert_calendar(holidays.clone()).await?);
+            assert!(store.upsert_calendar(maintenance.clone()).await?);
+            assert_eq!(
+                store.get_calendars(&project1.id, &[]).await?,
+                vec![holidays.clone(), maintenance.clone()]
+            );
+            assert!(store.get_calendars(&project2.id, &[]).await?.is_empty());
+
+            // Listing pages through the calendars.
+            let first_page = PaginationIn {
+                limit: 1,
+                cursor: None,
+            };
+            let page = store.list_calendars(&project1.id, &first_page).await?;
+            assert_eq!(page.data, vec![maintenance.clone()]);
+            assert!(page.pagination.has_more);
+            let next_page = PaginationIn {
+                limit: 1,
+                cursor: page.pagination.next_cursor,
+            };
+            let page = store.list_calendars(&project1.id, &next_page).await?;
+            assert_eq!(page.data, vec![holidays.clone()]);
+            assert!(!page.pagination.has_more);
+
+            // Replacing keeps the creation time.
+            let mut new_holidays = holidays.clone();
+            new_holidays.dates = CalendarDates::default();
+            new_holidays.created_at = Utc::now().with_nanosecond(0).unwrap()
+                + chrono::Duration::days(1);
+            assert!(!store.upsert_calendar(new_holidays).await?);
+            let found = store
+                .get_calendars(&project1.id, &["holidays".to_string()])
+                .await?;
+            assert_eq!(1, found.len());
+            assert_eq!(holidays.created_at, found[0].created_at);
+            assert!(found[0].dates.0.is_empty());
+            assert!(found[0].updated_at.is_some());
+
+            assert!(store.delete_calendar(&project1.id, "holidays").await?);
+            assert!(!store.delete_calendar(&project1.id, "holidays").await?);
+            assert_eq!(
+                store.get_calendars(&project1.id, &[]).await?,
+                vec![maintenance]
+            );
+        }
+
+        // Test signing secrets
+        {
+            let now = Utc::now().with_nanosecond(0).unwrap();
+            let secret = |id: &str, created_at| {
+                SigningSecret {
+                    project_id: project1.id.clone(),
+                    id: id.to_string(),
+                    secret: format!("whsec_{id}"),
+                    created_at,
+                    expires_at: None,
+                }
+            };
+            let old = secret("old", now - chrono::Duration::days(1));
+            let new = secret("new", now);
+            store.add_signing_secret(old.clone()).await?;
+            store.add_signing_secret(new.clone()).await?;
+            assert_eq!(
+                store.get_active_signing_secrets(&project1.id, now).await?,
+                vec![new.clone(), old.clone()]
+            );
+            assert!(store
+                .get_active_signing_secrets(&project2.id, now)
+                .await?
+                .is_empty());
+
+            // Expiring keeps the secrets active until they expire.
+            let expires_at = now + chrono::Duration::hours(1);
+            store
+                .expire_signing_secrets(&project1.id, expires_at)
+                .await?;
+            let active =
+                store.get_active_signing_secrets(&project1.id, now).await?;
+            assert_eq!(2, active.len());
+            assert!(active.iter().all(|s| s.expires_at == Some(expires_at)));
+            // An earlier expiry is kept.
+            store
+                .expire_signing_secrets(
+                    &project1.id,
+                    expires_at + chrono::Duration::hours(1),
+                )
+                .await?;
+            assert!(store
+                .get_active_signing_secrets(&project1.id, now)
+                .await?
+                .iter()
+                .all(|s| s.expires_at == Some(expires_at)));
+            assert!(store
+                .get_active_signing_secrets(&project1.id, expires_at)
+                .await?
+                .is_empty());
+
+            store
+                .delete_expired_signing_secrets(&project1.id, expires_at)
+                .await?;
+            assert!(!store.delete_signing_secret(&project1.id, "old").await?);
+            store.add_signing_secret(old.clone()).await?;
+            assert!(store.delete_signing_secret(&project1.id, "old").await?);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_shard_assignments() -> anyhow::Result<()> {
+        let db = MetadataService::in_memory_database().await?;
+        let store = MetadataStore::new(db);
+
+        assert!(store.get_shard_assignments().await?.is_empty());
+        store.assign_shards(&[], 1).await?;
+        assert!(store.get_shard_assignments().await?.is_empty());
+
+        store.assign_shards(&[7, 3], 1).await?;
+        // Moving a shard again replaces its assignment.
+        store.assign_shards(&[7], 2).await?;
+        let assignments: Vec<_> = store
+            .get_shard_assignments()
+            .await?
+            .into_iter()
+            .map(|a| (a.shard, a.cell_id))
+            .collect();
+        assert_eq!(vec![(3, 1), (7, 2)], assignments);
+
         Ok(())
     }
 }
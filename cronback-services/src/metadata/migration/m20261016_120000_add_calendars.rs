@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Calendars::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Calendars::ProjectId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Calendars::Name).string().not_null())
+                    .col(ColumnDef::new(Calendars::Description).string())
+                    .col(
+                        ColumnDef::new(Calendars::Timezone).string().not_null(),
+                    )
+                    .col(ColumnDef::new(Calendars::Dates).json().not_null())
+                    .col(
+                        ColumnDef::new(Calendars::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Calendars::UpdatedAt).date_time())
+                    .primary_key(
+                        Index::create()
+                            .col(Calendars::ProjectId)
+                            .col(Calendars::Name),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Calendars::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Calendars {
+    Table,
+    ProjectId,
+    Name,
+    Description,
+    Timezone,
+    Dates,
+    CreatedAt,
+    UpdatedAt,
+}
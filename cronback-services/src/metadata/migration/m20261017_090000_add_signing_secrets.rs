@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SigningSecrets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SigningSecrets::ProjectId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SigningSecrets::Id).string().not_null())
+                    .col(
+                        ColumnDef::new(SigningSecrets::Secret)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(SigningSecrets::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(SigningSecrets::ExpiresAt).date_time())
+                    .primary_key(
+                        Index::create()
+                            .col(SigningSecrets::ProjectId)
+                            .col(SigningSecrets::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SigningSecrets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum SigningSecrets {
+    Table,
+    ProjectId,
+    Id,
+    Secret,
+    CreatedAt,
+    ExpiresAt,
+}
@@ -2,6 +2,10 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20230712_205649_add_projects_model;
 mod m20230726_115454_add_notification_settings;
+mod m20261016_120000_add_calendars;
+mod m20261016_140000_add_shard_assignments;
+mod m20261016_180000_add_trigger_retention;
+mod m20261017_090000_add_signing_secrets;
 
 pub struct Migrator;
 
@@ -11,6 +15,10 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20230712_205649_add_projects_model::Migration),
             Box::new(m20230726_115454_add_notification_settings::Migration),
+            Box::new(m20261016_120000_add_calendars::Migration),
+            Box::new(m20261016_140000_add_shard_assignments::Migration),
+            Box::new(m20261016_180000_add_trigger_retention::Migration),
+            Box::new(m20261017_090000_add_signing_secrets::Migration),
         ]
     }
 }
@@ -54,3 +54,52 @@ impl CronbackService for MetadataService {
         Ok(())
     }
 }
+
+pub mod test_helpers {
+    use std::sync::Arc;
+
+    use lib::clients::ScopedMetadataSvcClient;
+    use lib::grpc_test_helpers::TestGrpcClientProvider;
+    use lib::service::{self, ServiceContext};
+    use tempfile::NamedTempFile;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+
+    pub async fn test_server_and_client(
+        mut context: ServiceContext<MetadataService>,
+    ) -> (
+        JoinHandle<()>,
+        TestGrpcClientProvider<ScopedMetadataSvcClient>,
+    ) {
+        let socket = NamedTempFile::new().unwrap();
+        let socket = Arc::new(socket.into_temp_path());
+        std::fs::remove_file(&*socket).unwrap();
+
+        let db = MetadataService::in_memory_database().await.unwrap();
+        let store = MetadataStore::new(db);
+
+        let handler = handler::MetadataSvcHandler::new(context.clone(), store);
+        let svc = MetadataSvcServer::new(handler);
+
+        let cloned_socket = Arc::clone(&socket);
+
+        let serve_future = tokio::spawn(async move {
+            let request_processing_timeout_s = 3;
+            service::grpc_serve_unix(
+                &mut context,
+                &*cloned_socket,
+                svc,
+                request_processing_timeout_s,
+            )
+            .await;
+        });
+
+        // Give the server time to start.
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+
+        let client_provider = TestGrpcClientProvider::new_single_shard(socket);
+
+        (serve_future, client_provider)
+    }
+}
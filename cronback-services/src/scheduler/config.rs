@@ -13,6 +13,23 @@ pub struct SchedulerSvcConfig {
     pub database_uri: String,
     pub db_flush_s: u64,
     pub dangerous_fast_forward: bool,
+    // Replicas of the same cell elect a leader through a lease in the
+    // database, only the leader runs the spinner.
+    pub leader_election: bool,
+    pub lease_ttl_s: u64,
+    // The address other replicas and clients use to reach this replica,
+    // defaults to `http://{address}:{port}`.
+    pub advertised_address: Option<String>,
+    // Dispatches that failed to reach the dispatcher are retried from the
+    // outbox, starting after `outbox_retry_delay_s` and doubling up to
+    // `outbox_retry_max_delay_s` between attempts.
+    pub outbox_poll_s: u64,
+    pub outbox_retry_delay_s: u64,
+    pub outbox_retry_max_delay_s: u64,
+    // Cancelled and expired triggers are deleted after `trigger_retention_s`
+    // unless their project overrides it, checked every `retention_gc_s`.
+    pub trigger_retention_s: u64,
+    pub retention_gc_s: u64,
 }
 
 impl From<SchedulerSvcConfig> for ConnectOptions {
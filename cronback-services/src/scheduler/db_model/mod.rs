@@ -1,4 +1,10 @@
+pub mod leases;
+pub mod outbox;
+pub mod revisions;
 pub mod schedule;
 pub mod triggers;
 
+pub use leases::{Entity as Leases, Model as Lease};
+pub use outbox::{Entity as DispatchOutbox, Model as OutboxEntry};
+pub use revisions::{Entity as TriggerRevisions, Model as TriggerRevision};
 pub use triggers::{Entity as Triggers, Model as Trigger};
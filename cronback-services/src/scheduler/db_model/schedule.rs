@@ -1,17 +1,36 @@
 use std::cmp::Reverse;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
 use std::iter::Peekable;
 use std::str::FromStr;
 
-use chrono::{DateTime, FixedOffset, Utc};
+use chrono::{
+    DateTime,
+    Duration,
+    FixedOffset,
+    LocalResult,
+    NaiveDate,
+    NaiveDateTime,
+    Offset,
+    TimeZone,
+    Utc,
+};
 use chrono_tz::{Tz, UTC};
 use cron::{OwnedScheduleIterator, Schedule as CronSchedule};
 use dto::{FromProto, IntoProto};
+use lib::prelude::*;
+use proto::runs::RunStatus;
 use sea_orm::FromJsonQueryResult;
 use serde::{Deserialize, Serialize};
 
 use crate::scheduler::error::TriggerError;
 
+/// The most upcoming timepoints a `RunAt` schedule can have, the API puts
+/// the same limit on the timepoints of a single request.
+pub const MAX_TIMEPOINTS: usize = 5000;
+/// How far back we look for local times that are repeated when the clocks
+/// move back, it must cover the largest change of UTC offset.
+const DST_LOOKBACK_HOURS: i64 = 3;
+
 #[derive(
     Debug,
     Clone,
@@ -27,25 +46,79 @@ use crate::scheduler::error::TriggerError;
 pub enum Schedule {
     Recurring(Recurring),
     RunAt(RunAt),
+    Interval(Interval),
+    OnCompletionOf(OnCompletionOf),
 }
 impl Schedule {
     pub fn estimated_future_runs(
         &self,
+        trigger_id: &TriggerId,
         after: DateTime<Utc>,
         count: usize,
     ) -> Vec<DateTime<Utc>> {
-        let schedule_iter = ScheduleIter::from_schedule(self, Some(after))
-            .expect("Failed to parse schedule");
+        // We can't tell when the upstream trigger will run.
+        if self.on_completion_of().is_some() {
+            return vec![];
+        }
+        let schedule_iter = ScheduleIter::from_schedule(self, after)
+            .expect("Failed to parse schedule")
+            .with_jitter(self.jitter(trigger_id));
         schedule_iter
             .take(count)
             .map(|x| x.with_timezone(&Utc))
             .collect()
     }
 
+    /// The next `count` ticks after `after`, noting where the UTC offset of
+    /// the schedule's timezone changes. Unlike the trigger's own ticks, those
+    /// are not jittered.
+    pub fn preview(
+        &self,
+        after: DateTime<Utc>,
+        count: usize,
+    ) -> Result<Vec<Occurrence>, TriggerError> {
+        let tz: Tz = match self {
+            | Self::Recurring(recurring) => {
+                recurring.timezone.parse().map_err(|_| {
+                    TriggerError::InvalidTimezone(recurring.timezone.clone())
+                })?
+            }
+            | _ => UTC,
+        };
+        let schedule_iter = ScheduleIter::from_schedule(self, after)?;
+        let mut previous_offset = None;
+        Ok(schedule_iter
+            .take(count)
+            .map(|at| {
+                let at = at.with_timezone(&tz);
+                let offset = at.offset().fix();
+                let previous = previous_offset
+                    .replace(offset)
+                    .unwrap_or_else(|| after.with_timezone(&tz).offset().fix());
+                Occurrence {
+                    at,
+                    dst_transition: (previous != offset)
+                        .then_some((previous, offset)),
+                }
+            })
+            .collect())
+    }
+
     pub fn limit(&self) -> Option<u64> {
         match self {
             | Self::Recurring(recurring) => recurring.limit,
             | Self::RunAt(run_at) => Some(run_at.timepoints.len() as u64),
+            | Self::Interval(interval) => interval.limit,
+            | Self::OnCompletionOf(_) => None,
+        }
+    }
+
+    /// Returns the dependency if this trigger runs on completion of another
+    /// trigger instead of running on its own ticks.
+    pub fn on_completion_of(&self) -> Option<&OnCompletionOf> {
+        match self {
+            | Self::OnCompletionOf(on_completion_of) => Some(on_completion_of),
+            | _ => None,
         }
     }
 
@@ -53,10 +126,169 @@ impl Schedule {
         match self {
             | Self::Recurring(recurring) => recurring.remaining = remaining,
             | Self::RunAt(run_at) => run_at.remaining = remaining,
+            | Self::Interval(interval) => interval.remaining = remaining,
+            | Self::OnCompletionOf(_) => {}
+        }
+    }
+
+    /// A delay in `[0, jitter_s]` that spreads out the runs of triggers
+    /// sharing the same cron pattern. It's derived from the trigger id so it
+    /// remains stable across restarts.
+    pub fn jitter(&self, trigger_id: &TriggerId) -> Duration {
+        let max_jitter_ms = match self {
+            | Self::Recurring(recurring) => {
+                recurring.jitter_s.unwrap_or_default() * 1000
+            }
+            | _ => 0,
+        };
+        if max_jitter_ms == 0 {
+            return Duration::zero();
+        }
+        // FNV-1a, unlike std's hasher, it's guaranteed to be stable across
+        // releases.
+        let hash = trigger_id
+            .value()
+            .bytes()
+            .fold(0xcbf29ce484222325_u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+        Duration::milliseconds((hash % (max_jitter_ms + 1)) as i64)
+    }
+
+    pub fn misfire_policy(&self) -> MisfirePolicy {
+        match self {
+            | Self::Recurring(recurring) => recurring.misfire_policy,
+            | Self::RunAt(run_at) => run_at.misfire_policy,
+            | Self::Interval(interval) => interval.misfire_policy,
+            | Self::OnCompletionOf(_) => None,
+        }
+        .unwrap_or_default()
+    }
+
+    pub fn max_missed_runs(&self) -> Option<u64> {
+        match self {
+            | Self::Recurring(recurring) => recurring.max_missed_runs,
+            | Self::RunAt(run_at) => run_at.max_missed_runs,
+            | Self::Interval(interval) => interval.max_missed_runs,
+            | Self::OnCompletionOf(_) => None,
+        }
+    }
+}
+
+/// An upcoming tick of a schedule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub at: DateTime<Tz>,
+    /// The UTC offsets before and after, if the offset changed since the
+    /// previous occurrence.
+    pub dst_transition: Option<(FixedOffset, FixedOffset)>,
+}
+
+impl From<Occurrence> for proto::scheduler_svc::ScheduleOccurrence {
+    fn from(value: Occurrence) -> Self {
+        Self {
+            at: Some(value.at.into()),
+            dst_transition: value.dst_transition.map(|(from, to)| {
+                proto::scheduler_svc::DstTransition {
+                    from_offset: from.to_string(),
+                    to_offset: to.to_string(),
+                }
+            }),
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    IntoProto,
+    FromProto,
+    Default,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+)]
+#[proto(target = "proto::triggers::MisfirePolicy")]
+pub enum MisfirePolicy {
+    Skip,
+    RunOnce,
+    // Triggers always caught up on every missed tick after a restart.
+    #[default]
+    RunAllMissed,
+}
+
+/// What happens to the ticks whose local time doesn't exist (clocks move
+/// forward) or happens twice (clocks move back). Unless skipped, the local
+/// times that don't exist run as soon as the clocks move past them.
+#[derive(
+    Debug,
+    IntoProto,
+    FromProto,
+    Default,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+)]
+#[proto(target = "proto::triggers::DstPolicy")]
+pub enum DstPolicy {
+    Skip,
+    #[default]
+    RunOnceEarliest,
+    RunOnceLatest,
+    RunBoth,
+}
+
+impl DstPolicy {
+    /// The points in time at which a tick at the `local` time runs.
+    fn resolve(&self, tz: &Tz, local: &NaiveDateTime) -> Vec<DateTime<Utc>> {
+        match (tz.from_local_datetime(local), self) {
+            | (LocalResult::Single(at), _) => vec![at.with_timezone(&Utc)],
+            | (LocalResult::Ambiguous(..), DstPolicy::Skip)
+            | (LocalResult::None, DstPolicy::Skip) => vec![],
+            | (
+                LocalResult::Ambiguous(earliest, _),
+                DstPolicy::RunOnceEarliest,
+            ) => {
+                vec![earliest.with_timezone(&Utc)]
+            }
+            | (LocalResult::Ambiguous(_, latest), DstPolicy::RunOnceLatest) => {
+                vec![latest.with_timezone(&Utc)]
+            }
+            | (
+                LocalResult::Ambiguous(earliest, latest),
+                DstPolicy::RunBoth,
+            ) => {
+                vec![earliest.with_timezone(&Utc), latest.with_timezone(&Utc)]
+            }
+            // The clocks moved forward past `local`, reading it with the UTC
+            // offset from before the change lands right after the gap.
+            | (LocalResult::None, _) => {
+                let offset = offset_before(tz, local);
+                vec![Utc.from_utc_datetime(&(*local - offset))]
+            }
         }
     }
 }
 
+/// The UTC offset in effect a day before the `local` time. Offsets don't
+/// change more than once a day.
+fn offset_before(tz: &Tz, local: &NaiveDateTime) -> Duration {
+    let offset = tz.offset_from_utc_datetime(&(*local - Duration::days(1)));
+    Duration::seconds(offset.fix().local_minus_utc() as i64)
+}
+
+/// The largest UTC offset of `tz` around the `local` time.
+fn max_offset_around(tz: &Tz, local: &NaiveDateTime) -> Duration {
+    let after = tz.offset_from_utc_datetime(&(*local + Duration::days(1)));
+    offset_before(tz, local)
+        .max(Duration::seconds(after.fix().local_minus_utc() as i64))
+}
+
 #[derive(
     Debug,
     IntoProto,
@@ -72,6 +304,8 @@ impl Schedule {
 pub struct RunAt {
     pub timepoints: Vec<DateTime<FixedOffset>>,
     pub remaining: Option<u64>,
+    pub misfire_policy: Option<MisfirePolicy>,
+    pub max_missed_runs: Option<u64>,
 }
 
 #[derive(
@@ -83,25 +317,150 @@ pub struct Recurring {
     pub timezone: String,
     pub limit: Option<u64>,
     pub remaining: Option<u64>,
+    pub misfire_policy: Option<MisfirePolicy>,
+    pub max_missed_runs: Option<u64>,
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub jitter_s: Option<u64>,
+    // Triggers created before calendars existed don't exclude any.
+    #[serde(default)]
+    pub exclude_calendars: Vec<String>,
+    // Triggers created before DST policies existed don't have one.
+    #[serde(default)]
+    pub dst_policy: Option<DstPolicy>,
+    /// A snapshot of `exclude_calendars`, taken when the trigger is installed
+    /// and refreshed whenever one of the calendars changes.
+    #[proto(skip)]
+    #[serde(default)]
+    pub calendars: Vec<Calendar>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Calendar {
+    pub name: String,
+    pub timezone: String,
+    pub dates: Vec<DateRange>,
+}
+
+/// An inclusive range of days.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DateRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl From<proto::calendars::Calendar> for Calendar {
+    fn from(value: proto::calendars::Calendar) -> Self {
+        Self {
+            name: value.name,
+            timezone: value.timezone,
+            // Dates are validated by the API before they are stored.
+            dates: value
+                .dates
+                .into_iter()
+                .filter_map(|range| {
+                    Some(DateRange {
+                        start: range.start.parse().ok()?,
+                        end: range.end.parse().ok()?,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(
+    Debug, IntoProto, FromProto, Clone, Serialize, Deserialize, PartialEq, Eq,
+)]
+#[proto(target = "proto::triggers::Interval")]
+pub struct Interval {
+    pub every_s: u64,
+    pub anchor: Option<DateTime<Utc>>,
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub misfire_policy: Option<MisfirePolicy>,
+    pub max_missed_runs: Option<u64>,
+}
+
+#[derive(
+    Debug, IntoProto, FromProto, Clone, Serialize, Deserialize, PartialEq, Eq,
+)]
+#[proto(target = "proto::triggers::OnCompletionOf")]
+pub struct OnCompletionOf {
+    /// The name of the upstream trigger.
+    pub trigger: String,
+    pub outcome: Option<CompletionOutcome>,
+}
+
+impl OnCompletionOf {
+    /// Whether an upstream run that finished with `status` fires the
+    /// dependent trigger.
+    pub fn fires_on(&self, status: RunStatus) -> bool {
+        match self.outcome.unwrap_or_default() {
+            | CompletionOutcome::Succeeded => status == RunStatus::Succeeded,
+            | CompletionOutcome::Failed => status == RunStatus::Failed,
+            | CompletionOutcome::Any => {
+                matches!(status, RunStatus::Succeeded | RunStatus::Failed)
+            }
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    IntoProto,
+    FromProto,
+    Default,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+)]
+#[proto(target = "proto::triggers::CompletionOutcome")]
+pub enum CompletionOutcome {
+    #[default]
+    Succeeded,
+    Failed,
+    Any,
 }
 
 /// A type that abstracts the generator for future ticks, whether it's backed
-/// by a cron patter, or a list of time points.
+/// by a cron patter, a list of time points, or a fixed interval.
 pub enum ScheduleIter {
     CronPattern {
-        next_ticks: Peekable<OwnedScheduleIterator<Tz>>,
+        cron: CronSchedule,
+        tz: Tz,
+        dst_policy: DstPolicy,
+        next_ticks: Peekable<LocalTicks>,
+        // Missed ticks that we decided to run before moving to `next_ticks`.
+        missed: VecDeque<DateTime<Tz>>,
         remaining: Option<u64>,
+        end_at: Option<DateTime<Utc>>,
+        // Added to every tick.
+        jitter: Duration,
+        // Ticks that fall on these days (in the calendar's timezone) are
+        // skipped.
+        excluded: Vec<(Tz, Vec<DateRange>)>,
     },
     RunAt {
         run_at: BinaryHeap<Reverse<DateTime<FixedOffset>>>,
         remaining: u64,
     },
+    Interval {
+        next_tick: DateTime<Tz>,
+        every: Duration,
+        remaining: Option<u64>,
+    },
 }
 
 impl ScheduleIter {
+    /// The ticks of the schedule that come after `after`, usually the last
+    /// time the trigger ran.
     pub fn from_schedule(
         schedule_raw: &Schedule,
-        last_ran_at: Option<DateTime<Utc>>,
+        after: DateTime<Utc>,
     ) -> Result<Self, TriggerError> {
         match schedule_raw {
             | Schedule::Recurring(cron) => {
@@ -110,27 +469,51 @@ impl ScheduleIter {
                 let tz: Tz = cron.timezone.parse().map_err(|_| {
                     TriggerError::InvalidTimezone(cron.timezone.clone())
                 })?;
-                let next_ticks = if let Some(last_ran_at) = last_ran_at {
-                    cron_schedule
-                        .after_owned(last_ran_at.with_timezone(&UTC))
-                        .peekable()
-                } else {
-                    cron_schedule.upcoming_owned(tz).peekable()
+                let dst_policy = cron.dst_policy.unwrap_or_default();
+                // We don't look for ticks before the start of the schedule's
+                // window. A tick that lands exactly on `start_at` runs.
+                let after = match cron.start_at {
+                    | Some(start_at) => {
+                        after.max(start_at - Duration::seconds(1))
+                    }
+                    | None => after,
                 };
+                let next_ticks =
+                    LocalTicks::after(&cron_schedule, tz, dst_policy, after)
+                        .peekable();
                 let remaining = cron.remaining.or(cron.limit);
+                let excluded = cron
+                    .calendars
+                    .iter()
+                    .map(|calendar| {
+                        let tz: Tz =
+                            calendar.timezone.parse().map_err(|_| {
+                                TriggerError::InvalidTimezone(
+                                    calendar.timezone.clone(),
+                                )
+                            })?;
+                        Ok((tz, calendar.dates.clone()))
+                    })
+                    .collect::<Result<Vec<_>, TriggerError>>()?;
                 Ok(ScheduleIter::CronPattern {
+                    cron: cron_schedule,
+                    tz,
+                    dst_policy,
                     next_ticks,
+                    missed: VecDeque::new(),
                     remaining,
+                    end_at: cron.end_at,
+                    jitter: Duration::zero(),
+                    excluded,
                 })
             }
             | Schedule::RunAt(run_at) => {
                 let mut ticks = BinaryHeap::new();
-                let last_ran_at =
-                    last_ran_at.unwrap_or(Utc::now()).with_timezone(&UTC);
+                let after = after.with_timezone(&UTC);
                 let mut remaining = 0;
 
                 for ts in run_at.timepoints.iter() {
-                    if *ts > last_ran_at {
+                    if *ts > after {
                         remaining += 1;
                         // Reversed to make this min-heap
                         ticks.push(Reverse(*ts));
@@ -141,19 +524,186 @@ impl ScheduleIter {
                     remaining,
                 })
             }
+            | Schedule::Interval(interval) => {
+                if interval.every_s == 0 || interval.every_s > u32::MAX as u64 {
+                    return Err(TriggerError::InvalidInterval(
+                        interval.every_s,
+                    ));
+                }
+                let every_s = interval.every_s;
+                let anchor = interval.anchor.unwrap_or(after);
+                // The first tick that comes strictly after `after`, ticks are
+                // aligned to the anchor.
+                let next_tick = if anchor > after {
+                    anchor
+                } else {
+                    let elapsed = (after - anchor).num_seconds() as u64;
+                    anchor
+                        + Duration::seconds(
+                            ((elapsed / every_s + 1) * every_s) as i64,
+                        )
+                };
+                Ok(ScheduleIter::Interval {
+                    next_tick: next_tick.with_timezone(&UTC),
+                    every: Duration::seconds(every_s as i64),
+                    remaining: interval.remaining.or(interval.limit),
+                })
+            }
+            | Schedule::OnCompletionOf(on_completion_of) => {
+                Err(TriggerError::DependentSchedule(
+                    on_completion_of.trigger.clone(),
+                ))
+            }
         }
     }
 
+    /// Delays every tick by `jitter`. Only cron patterns are jittered.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        if let Self::CronPattern {
+            jitter: ref mut current,
+            ..
+        } = self
+        {
+            *current = jitter;
+        }
+        self
+    }
+
+    /// Consumes the ticks that were due at or before `now` according to the
+    /// misfire `policy`. The ticks that are kept will be yielded first.
+    /// Returns the number of missed ticks that were dropped.
+    pub fn apply_misfire_policy(
+        &mut self,
+        policy: MisfirePolicy,
+        max_missed_runs: Option<u64>,
+        now: DateTime<Utc>,
+    ) -> u64 {
+        let keep = match (policy, max_missed_runs) {
+            | (MisfirePolicy::Skip, _) => 0,
+            | (MisfirePolicy::RunOnce, _) => 1,
+            | (MisfirePolicy::RunAllMissed, Some(max)) => max as usize,
+            // Without a cap, the missed ticks are yielded as they come.
+            | (MisfirePolicy::RunAllMissed, None) => return 0,
+        };
+        let mut dropped = 0;
+        match self {
+            | Self::CronPattern {
+                cron,
+                tz,
+                dst_policy,
+                next_ticks,
+                missed,
+                jitter,
+                excluded,
+                ..
+            } => {
+                skip_excluded(cron, *tz, *dst_policy, next_ticks, excluded);
+                while let Some(tick) =
+                    next_ticks.next_if(|t| *t + *jitter <= now)
+                {
+                    skip_excluded(cron, *tz, *dst_policy, next_ticks, excluded);
+                    missed.push_back(tick);
+                    // We keep the most recent ticks.
+                    if missed.len() > keep {
+                        missed.pop_front();
+                        dropped += 1;
+                    }
+                }
+            }
+            | Self::RunAt { run_at, remaining } => {
+                let mut missed = VecDeque::new();
+                while let Some(Reverse(tick)) = run_at.peek().cloned() {
+                    if tick > now {
+                        break;
+                    }
+                    run_at.pop();
+                    missed.push_back(tick);
+                    if missed.len() > keep {
+                        missed.pop_front();
+                        dropped += 1;
+                    }
+                }
+                // Unlike cron patterns, dropped timepoints will never come
+                // back.
+                *remaining -= dropped;
+                for tick in missed {
+                    run_at.push(Reverse(tick));
+                }
+            }
+            | Self::Interval {
+                next_tick, every, ..
+            } => {
+                if *next_tick <= now {
+                    let missed = (now - next_tick.with_timezone(&Utc))
+                        .num_seconds()
+                        / every.num_seconds()
+                        + 1;
+                    // We keep the most recent ticks.
+                    let skipped = missed.saturating_sub(keep as i64);
+                    *next_tick +=
+                        Duration::seconds(every.num_seconds() * skipped);
+                    dropped = skipped as u64;
+                }
+            }
+        }
+        dropped
+    }
+
     // Looks at the next tick. You don't need a Peekable<Iterator> for this
     // iterator.
     pub fn peek(&mut self) -> Option<DateTime<Tz>> {
         self.peek_or_next(false)
     }
 
+    /// Adds timepoints to, and removes timepoints from, the upcoming ones of a
+    /// `RunAt` schedule. Returns the upcoming timepoints in order.
+    pub fn update_timepoints(
+        &mut self,
+        add: &[DateTime<FixedOffset>],
+        remove: &[DateTime<FixedOffset>],
+    ) -> Result<Vec<DateTime<FixedOffset>>, TriggerError> {
+        let Self::RunAt { run_at, remaining } = self else {
+            return Err(TriggerError::NotRunAt);
+        };
+        // Equality of timepoints is about the instant, not the offset.
+        let mut timepoints: HashSet<_> = run_at
+            .iter()
+            .map(|Reverse(t)| *t)
+            .filter(|t| !remove.contains(t))
+            .collect();
+        timepoints.extend(add.iter().copied());
+        if timepoints.len() > MAX_TIMEPOINTS {
+            return Err(TriggerError::TooManyTimepoints(MAX_TIMEPOINTS));
+        }
+        let mut timepoints: Vec<_> = timepoints.into_iter().collect();
+        timepoints.sort();
+        *run_at = timepoints.iter().copied().map(Reverse).collect();
+        *remaining = timepoints.len() as u64;
+        Ok(timepoints)
+    }
+
+    /// Gives back the run that the last tick consumed from the run limit, for
+    /// ticks that didn't run. The timepoints of a `RunAt` schedule are gone
+    /// once passed, so they are left as is.
+    pub fn restore_run(&mut self) {
+        match self {
+            | Self::CronPattern {
+                remaining: Some(remaining),
+                ..
+            }
+            | Self::Interval {
+                remaining: Some(remaining),
+                ..
+            } => *remaining += 1,
+            | _ => {}
+        }
+    }
+
     pub fn remaining(&self) -> Option<u64> {
         match self {
             | Self::CronPattern { remaining, .. } => *remaining,
             | Self::RunAt { remaining, .. } => Some(*remaining),
+            | Self::Interval { remaining, .. } => *remaining,
         }
     }
 
@@ -166,12 +716,29 @@ impl ScheduleIter {
             } if *events_limit == 0 => None,
             // We might have more runs
             | Self::CronPattern {
+                cron,
+                tz,
+                dst_policy,
                 next_ticks,
+                missed,
                 remaining: remaining_events_limit,
-                ..
+                end_at,
+                jitter,
+                excluded,
             } => {
-                if next {
-                    let n = next_ticks.next();
+                skip_excluded(cron, *tz, *dst_policy, next_ticks, excluded);
+                let upcoming = missed
+                    .front()
+                    .cloned()
+                    .or_else(|| next_ticks.peek().cloned());
+                // Nothing runs past the end of the schedule's window.
+                if let (Some(tick), Some(end_at)) = (upcoming, end_at) {
+                    if tick > *end_at {
+                        return None;
+                    }
+                }
+                let upcoming = if next {
+                    let n = missed.pop_front().or_else(|| next_ticks.next());
                     if n.is_some() {
                         if let Some(events_limit) = remaining_events_limit {
                             // consume events.
@@ -180,8 +747,10 @@ impl ScheduleIter {
                     }
                     n
                 } else {
-                    next_ticks.peek().cloned()
-                }
+                    upcoming
+                };
+                // Internally, we convert everything to UTC.
+                upcoming.map(|tick| (tick + *jitter).with_timezone(&UTC))
             }
             | Self::RunAt { run_at, remaining } => {
                 let next_point = if next {
@@ -196,6 +765,134 @@ impl ScheduleIter {
                 // Internally, we convert everything to UTC.
                 next_point.map(|f| f.with_timezone(&UTC))
             }
+            // We hit the run limit.
+            | Self::Interval {
+                remaining: Some(events_limit),
+                ..
+            } if *events_limit == 0 => None,
+            | Self::Interval {
+                next_tick,
+                every,
+                remaining,
+            } => {
+                let tick = *next_tick;
+                if next {
+                    *next_tick = tick + *every;
+                    if let Some(events_limit) = remaining {
+                        *events_limit -= 1;
+                    }
+                }
+                Some(tick)
+            }
+        }
+    }
+}
+
+/// Moves `next_ticks` past any tick that falls on an excluded day. Instead of
+/// walking every tick of an excluded range, the iterator restarts at the
+/// beginning of the day after it.
+fn skip_excluded(
+    cron: &CronSchedule,
+    tz: Tz,
+    dst_policy: DstPolicy,
+    next_ticks: &mut Peekable<LocalTicks>,
+    excluded: &[(Tz, Vec<DateRange>)],
+) {
+    while let Some(tick) = next_ticks.peek().cloned() {
+        let resume_at = excluded.iter().find_map(|(tz, dates)| {
+            let day = tick.with_timezone(tz).date_naive();
+            let range =
+                dates.iter().find(|r| r.start <= day && day <= r.end)?;
+            let next_day = range.end.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            // Midnight might not exist when DST starts.
+            tz.from_local_datetime(&next_day).earliest().or_else(|| {
+                tz.from_local_datetime(&(next_day + Duration::hours(1)))
+                    .earliest()
+            })
+        });
+        let Some(resume_at) = resume_at else {
+            break;
+        };
+        // `LocalTicks` only yields ticks that come strictly after.
+        *next_ticks = LocalTicks::after(
+            cron,
+            tz,
+            dst_policy,
+            resume_at.with_timezone(&Utc) - Duration::seconds(1),
+        )
+        .peekable();
+    }
+}
+
+/// The ticks of a cron pattern on the wall clock of a timezone. The pattern is
+/// matched against local times, which are mapped to points in time according
+/// to the DST policy.
+pub struct LocalTicks {
+    // Local times are represented in UTC since it has no offset changes.
+    local_ticks: Peekable<OwnedScheduleIterator<Utc>>,
+    tz: Tz,
+    dst_policy: DstPolicy,
+    after: DateTime<Utc>,
+    // The second occurrence of a repeated local time comes after the first
+    // occurrence of the following local times.
+    pending: BinaryHeap<Reverse<DateTime<Utc>>>,
+}
+
+impl LocalTicks {
+    /// The ticks that come strictly after `after`.
+    fn after(
+        cron: &CronSchedule,
+        tz: Tz,
+        dst_policy: DstPolicy,
+        after: DateTime<Utc>,
+    ) -> Self {
+        // A local time that came before `after` might happen again after it.
+        let local_after = after.with_timezone(&tz).naive_local()
+            - Duration::hours(DST_LOOKBACK_HOURS);
+        Self {
+            local_ticks: cron
+                .after_owned(Utc.from_utc_datetime(&local_after))
+                .peekable(),
+            tz,
+            dst_policy,
+            after,
+            pending: BinaryHeap::new(),
+        }
+    }
+}
+
+impl Iterator for LocalTicks {
+    type Item = DateTime<Tz>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next_local = self.local_ticks.peek().map(|t| t.naive_utc());
+            let ready = match (self.pending.peek(), next_local) {
+                | (Some(Reverse(tick)), Some(local)) => {
+                    // The next local time can't run before this.
+                    let earliest = local - max_offset_around(&self.tz, &local);
+                    *tick < Utc.from_utc_datetime(&earliest)
+                }
+                | (Some(_), None) => true,
+                | (None, Some(_)) => false,
+                | (None, None) => return None,
+            };
+            if ready {
+                return self
+                    .pending
+                    .pop()
+                    .map(|Reverse(tick)| tick.with_timezone(&self.tz));
+            }
+
+            let local = self.local_ticks.next().unwrap().naive_utc();
+            let after = self.after;
+            self.pending.extend(
+                self.dst_policy
+                    .resolve(&self.tz, &local)
+                    .into_iter()
+                    .filter(|at| *at > after)
+                    .map(Reverse),
+            );
         }
     }
 }
@@ -223,12 +920,20 @@ mod tests {
             timezone: "Etc/UTC".into(),
             limit: None,
             remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: None,
+            end_at: None,
+            jitter_s: None,
+            exclude_calendars: vec![],
+            calendars: vec![],
+            dst_policy: None,
         });
         assert!(schedule.limit().is_none());
 
         let after = Utc.with_ymd_and_hms(2021, 10, 27, 0, 5, 0).unwrap();
 
-        let result = ScheduleIter::from_schedule(&schedule, Some(after))?;
+        let result = ScheduleIter::from_schedule(&schedule, after)?;
         // TODO: Consider removing this check.
         assert!(matches!(result, ScheduleIter::CronPattern { .. }));
         assert!(result.remaining().is_none());
@@ -267,11 +972,19 @@ mod tests {
             timezone: "Etc/UTC".into(),
             limit: Some(4),
             remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: None,
+            end_at: None,
+            jitter_s: None,
+            exclude_calendars: vec![],
+            calendars: vec![],
+            dst_policy: None,
         });
         assert_eq!(4, schedule.limit().unwrap());
 
         let after = Utc.with_ymd_and_hms(2021, 10, 27, 0, 5, 0).unwrap();
-        let mut result = ScheduleIter::from_schedule(&schedule, Some(after))?;
+        let mut result = ScheduleIter::from_schedule(&schedule, after)?;
 
         assert_eq!(4, result.remaining().unwrap());
 
@@ -292,6 +1005,380 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn future_ticks_parsing_cron_with_window() -> Result<(), TriggerError> {
+        let at = |h, m| chrono_tz::UTC.with_ymd_and_hms(2021, 10, 27, h, m, 0);
+        let schedule = Schedule::Recurring(Recurring {
+            cron: "0 5 * * * *".to_owned(), // fifth minute of every hour
+            timezone: "Etc/UTC".into(),
+            limit: None,
+            remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: Some(at(3, 5).unwrap().with_timezone(&Utc)),
+            end_at: Some(at(5, 10).unwrap().with_timezone(&Utc)),
+            jitter_s: None,
+            exclude_calendars: vec![],
+            calendars: vec![],
+            dst_policy: None,
+        });
+
+        let after = Utc.with_ymd_and_hms(2021, 10, 27, 0, 5, 0).unwrap();
+        let mut result = ScheduleIter::from_schedule(&schedule, after)?;
+        // A tick on start_at runs.
+        assert_eq!(at(3, 5).unwrap(), result.next().unwrap());
+        assert_eq!(at(4, 5).unwrap(), result.next().unwrap());
+        assert_eq!(at(5, 5).unwrap(), result.next().unwrap());
+        // Nothing after end_at.
+        assert!(result.peek().is_none());
+        assert!(result.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cron_ticks_skip_excluded_calendars() -> Result<(), TriggerError> {
+        let day = |d| NaiveDate::from_ymd_opt(2023, 12, d).unwrap();
+        let schedule = Schedule::Recurring(Recurring {
+            cron: "0 0 12 * * *".to_owned(), // noon every day
+            timezone: "Europe/London".into(),
+            limit: Some(3),
+            remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: None,
+            end_at: None,
+            jitter_s: None,
+            exclude_calendars: vec!["holidays".into()],
+            calendars: vec![Calendar {
+                name: "holidays".into(),
+                // Noon in London is already the next day in Auckland, so
+                // this excludes the 24th to the 26th London time.
+                timezone: "Pacific/Auckland".into(),
+                dates: vec![
+                    DateRange {
+                        start: day(25),
+                        end: day(26),
+                    },
+                    DateRange {
+                        start: day(27),
+                        end: day(27),
+                    },
+                ],
+            }],
+            dst_policy: None,
+        });
+        let tz = chrono_tz::Europe::London;
+        let after = Utc.with_ymd_and_hms(2023, 12, 23, 13, 0, 0).unwrap();
+        let result: Vec<_> =
+            ScheduleIter::from_schedule(&schedule, after)?.collect();
+        // Excluded ticks don't count against the limit.
+        assert_eq!(
+            vec![
+                tz.with_ymd_and_hms(2023, 12, 27, 12, 0, 0).unwrap(),
+                tz.with_ymd_and_hms(2023, 12, 28, 12, 0, 0).unwrap(),
+                tz.with_ymd_and_hms(2023, 12, 29, 12, 0, 0).unwrap(),
+            ],
+            result
+        );
+
+        // Missed ticks on excluded days are not caught up on.
+        let mut iter = ScheduleIter::from_schedule(&schedule, after)?;
+        let now = Utc.with_ymd_and_hms(2023, 12, 26, 13, 0, 0).unwrap();
+        let dropped = iter.apply_misfire_policy(
+            MisfirePolicy::RunAllMissed,
+            Some(100),
+            now,
+        );
+        assert_eq!(0, dropped);
+        assert_eq!(
+            Some(tz.with_ymd_and_hms(2023, 12, 27, 12, 0, 0).unwrap()),
+            iter.next()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn preview_annotates_dst_transitions() -> Result<(), TriggerError> {
+        let schedule = Schedule::Recurring(Recurring {
+            cron: "0 0 9 * * Sun".to_owned(), // 9am every sunday
+            timezone: "Europe/London".into(),
+            limit: None,
+            remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: None,
+            end_at: None,
+            jitter_s: None,
+            exclude_calendars: vec![],
+            calendars: vec![],
+            dst_policy: None,
+        });
+        // Clocks go forward on the 26th of March 2023.
+        let after = Utc.with_ymd_and_hms(2023, 3, 13, 0, 0, 0).unwrap();
+        let preview = schedule.preview(after, 3)?;
+        let tz = chrono_tz::Europe::London;
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let bst = FixedOffset::east_opt(3600).unwrap();
+        assert_eq!(
+            vec![
+                Occurrence {
+                    at: tz.with_ymd_and_hms(2023, 3, 19, 9, 0, 0).unwrap(),
+                    dst_transition: None,
+                },
+                Occurrence {
+                    at: tz.with_ymd_and_hms(2023, 3, 26, 9, 0, 0).unwrap(),
+                    dst_transition: Some((utc, bst)),
+                },
+                Occurrence {
+                    at: tz.with_ymd_and_hms(2023, 4, 2, 9, 0, 0).unwrap(),
+                    dst_transition: None,
+                },
+            ],
+            preview
+        );
+        Ok(())
+    }
+
+    fn with_dst_policy(
+        cron: &str,
+        timezone: &str,
+        dst_policy: DstPolicy,
+    ) -> Schedule {
+        Schedule::Recurring(Recurring {
+            cron: cron.to_owned(),
+            timezone: timezone.into(),
+            limit: None,
+            remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: None,
+            end_at: None,
+            jitter_s: None,
+            exclude_calendars: vec![],
+            calendars: vec![],
+            dst_policy: Some(dst_policy),
+        })
+    }
+
+    fn utc_ticks(
+        schedule: &Schedule,
+        after: DateTime<Utc>,
+        count: usize,
+    ) -> Result<Vec<DateTime<Utc>>, TriggerError> {
+        Ok(ScheduleIter::from_schedule(schedule, after)?
+            .take(count)
+            .map(|t| t.with_timezone(&Utc))
+            .collect())
+    }
+
+    #[test]
+    fn dst_policies_for_skipped_local_times() -> Result<(), TriggerError> {
+        let at = |d, h, m| Utc.with_ymd_and_hms(2023, 3, d, h, m, 0).unwrap();
+
+        // New York skips from 2am to 3am on the 12th of March 2023.
+        let after = at(11, 12, 0);
+        let skip = with_dst_policy(
+            "0 30 2 * * *",
+            "America/New_York",
+            DstPolicy::Skip,
+        );
+        assert_eq!(vec![at(13, 6, 30)], utc_ticks(&skip, after, 1)?);
+        // Any other policy runs at 3:30am EDT, right after the gap.
+        for policy in [
+            DstPolicy::RunOnceEarliest,
+            DstPolicy::RunOnceLatest,
+            DstPolicy::RunBoth,
+        ] {
+            let schedule =
+                with_dst_policy("0 30 2 * * *", "America/New_York", policy);
+            assert_eq!(
+                vec![at(12, 7, 30), at(13, 6, 30)],
+                utc_ticks(&schedule, after, 2)?
+            );
+        }
+
+        // London skips from 1am to 2am on the 26th of March 2023.
+        let after = at(25, 12, 0);
+        let skip =
+            with_dst_policy("0 30 1 * * *", "Europe/London", DstPolicy::Skip);
+        assert_eq!(vec![at(27, 0, 30)], utc_ticks(&skip, after, 1)?);
+        let latest = with_dst_policy(
+            "0 30 1 * * *",
+            "Europe/London",
+            DstPolicy::RunOnceLatest,
+        );
+        assert_eq!(
+            vec![at(26, 1, 30), at(27, 0, 30)],
+            utc_ticks(&latest, after, 2)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dst_policies_for_repeated_local_times() -> Result<(), TriggerError> {
+        let at = |d, h, m| Utc.with_ymd_and_hms(2023, 11, d, h, m, 0).unwrap();
+        let ny = |policy| {
+            with_dst_policy("0 30 1 * * *", "America/New_York", policy)
+        };
+
+        // New York goes through 1am twice on the 5th of November 2023, first
+        // in EDT then in EST.
+        let after = at(4, 12, 0);
+        assert_eq!(
+            vec![at(6, 6, 30)],
+            utc_ticks(&ny(DstPolicy::Skip), after, 1)?
+        );
+        assert_eq!(
+            vec![at(5, 5, 30), at(6, 6, 30)],
+            utc_ticks(&ny(DstPolicy::RunOnceEarliest), after, 2)?
+        );
+        assert_eq!(
+            vec![at(5, 6, 30), at(6, 6, 30)],
+            utc_ticks(&ny(DstPolicy::RunOnceLatest), after, 2)?
+        );
+        assert_eq!(
+            vec![at(5, 5, 30), at(5, 6, 30), at(6, 6, 30)],
+            utc_ticks(&ny(DstPolicy::RunBoth), after, 3)?
+        );
+
+        // Resuming between the two occurrences still finds the second one.
+        let between = at(5, 6, 0);
+        assert_eq!(
+            vec![at(5, 6, 30)],
+            utc_ticks(&ny(DstPolicy::RunBoth), between, 1)?
+        );
+        assert_eq!(
+            vec![at(6, 6, 30)],
+            utc_ticks(&ny(DstPolicy::RunOnceEarliest), between, 1)?
+        );
+
+        // Ticks of both occurrences come out in order.
+        let every_20m = with_dst_policy(
+            "0 */20 1 * * *",
+            "America/New_York",
+            DstPolicy::RunBoth,
+        );
+        assert_eq!(
+            vec![
+                at(5, 5, 0),
+                at(5, 5, 20),
+                at(5, 5, 40),
+                at(5, 6, 0),
+                at(5, 6, 20),
+                at(5, 6, 40),
+            ],
+            utc_ticks(&every_20m, after, 6)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dst_policies_with_uneven_offsets() -> Result<(), TriggerError> {
+        let at =
+            |m, d, h, min| Utc.with_ymd_and_hms(2023, m, d, h, min, 0).unwrap();
+
+        // Lord Howe Island moves its clocks back by 30 minutes, from 2am to
+        // 1:30am on the 2nd of April 2023.
+        let lord_howe = with_dst_policy(
+            "0 45 1 * * *",
+            "Australia/Lord_Howe",
+            DstPolicy::RunBoth,
+        );
+        let after = at(4, 1, 0, 0);
+        assert_eq!(
+            vec![at(4, 1, 14, 45), at(4, 1, 15, 15), at(4, 2, 15, 15)],
+            utc_ticks(&lord_howe, after, 3)?
+        );
+
+        // Kolkata has no DST, so all policies behave the same.
+        for policy in [
+            DstPolicy::Skip,
+            DstPolicy::RunOnceEarliest,
+            DstPolicy::RunOnceLatest,
+            DstPolicy::RunBoth,
+        ] {
+            let kolkata =
+                with_dst_policy("0 30 2 * * *", "Asia/Kolkata", policy);
+            assert_eq!(
+                vec![at(3, 12, 21, 0), at(3, 13, 21, 0)],
+                utc_ticks(&kolkata, at(3, 12, 12, 0), 2)?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn estimated_future_runs_follow_dst_policy() {
+        let project = ProjectId::generate();
+        let trigger_id: TriggerId = TriggerId::generate(&project).into();
+        let after = Utc.with_ymd_and_hms(2023, 11, 4, 12, 0, 0).unwrap();
+        let at = |d, h| Utc.with_ymd_and_hms(2023, 11, d, h, 30, 0).unwrap();
+
+        let both = with_dst_policy(
+            "0 30 1 * * *",
+            "America/New_York",
+            DstPolicy::RunBoth,
+        );
+        assert_eq!(
+            vec![at(5, 5), at(5, 6)],
+            both.estimated_future_runs(&trigger_id, after, 2)
+        );
+        let skip = with_dst_policy(
+            "0 30 1 * * *",
+            "America/New_York",
+            DstPolicy::Skip,
+        );
+        assert_eq!(
+            vec![at(6, 6), at(7, 6)],
+            skip.estimated_future_runs(&trigger_id, after, 2)
+        );
+    }
+
+    #[test]
+    fn jittered_cron_ticks() -> Result<(), TriggerError> {
+        let schedule = Schedule::Recurring(Recurring {
+            cron: "0 5 * * * *".to_owned(), // fifth minute of every hour
+            timezone: "Etc/UTC".into(),
+            limit: None,
+            remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: None,
+            end_at: None,
+            jitter_s: Some(600),
+            exclude_calendars: vec![],
+            calendars: vec![],
+            dst_policy: None,
+        });
+        let project = ProjectId::generate();
+        let trigger_id: TriggerId = TriggerId::generate(&project).into();
+
+        // The jitter is bounded and stable for the same trigger.
+        let jitter = schedule.jitter(&trigger_id);
+        assert!(jitter >= Duration::zero());
+        assert!(jitter <= Duration::seconds(600));
+        assert_eq!(jitter, schedule.jitter(&trigger_id));
+
+        let after = Utc.with_ymd_and_hms(2021, 10, 27, 0, 5, 0).unwrap();
+        let mut result =
+            ScheduleIter::from_schedule(&schedule, after)?.with_jitter(jitter);
+        let expected = chrono_tz::UTC
+            .with_ymd_and_hms(2021, 10, 27, 1, 5, 0)
+            .unwrap();
+        assert_eq!(expected + jitter, result.peek().unwrap());
+        assert_eq!(expected + jitter, result.next().unwrap());
+        assert_eq!(
+            expected + Duration::hours(1) + jitter,
+            result.next().unwrap()
+        );
+
+        let runs = schedule.estimated_future_runs(&trigger_id, after, 2);
+        assert_eq!(expected + jitter, runs[0]);
+
+        Ok(())
+    }
+
     #[test]
     fn future_ticks_parsing_run_at() -> Result<(), TriggerError> {
         // generating some time points, one in the past, and three in the
@@ -315,12 +1402,14 @@ mod tests {
         let schedule = Schedule::RunAt(RunAt {
             timepoints,
             remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
         });
 
         // Egypt was UTC+2 in 2021-10-27, so we assume that now is the 26th of
         // October at 22:05
         let after = Utc.with_ymd_and_hms(2021, 10, 26, 22, 5, 0).unwrap();
-        let mut result = ScheduleIter::from_schedule(&schedule, Some(after))?;
+        let mut result = ScheduleIter::from_schedule(&schedule, after)?;
         assert!(matches!(result, ScheduleIter::RunAt { .. }));
         assert_eq!(2, result.remaining().unwrap());
         assert_eq!(after_2m, result.next().unwrap());
@@ -333,4 +1422,180 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn misfire_policies() -> Result<(), TriggerError> {
+        let cron_schedule = Schedule::Recurring(Recurring {
+            cron: "0 5 * * * *".to_owned(), // fifth minute of every hour
+            timezone: "Etc/UTC".into(),
+            limit: None,
+            remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: None,
+            end_at: None,
+            jitter_s: None,
+            exclude_calendars: vec![],
+            calendars: vec![],
+            dst_policy: None,
+        });
+        let last_ran_at = Utc.with_ymd_and_hms(2021, 10, 27, 0, 5, 0).unwrap();
+        // We missed 01:05, 02:05, 03:05, 04:05 and 05:05
+        let now = Utc.with_ymd_and_hms(2021, 10, 27, 5, 30, 0).unwrap();
+        let at = |h| chrono_tz::UTC.with_ymd_and_hms(2021, 10, 27, h, 5, 0);
+
+        let mut iter =
+            ScheduleIter::from_schedule(&cron_schedule, last_ran_at)?;
+        assert_eq!(
+            5,
+            iter.apply_misfire_policy(MisfirePolicy::Skip, None, now)
+        );
+        assert_eq!(at(6).unwrap(), iter.next().unwrap());
+
+        let mut iter =
+            ScheduleIter::from_schedule(&cron_schedule, last_ran_at)?;
+        assert_eq!(
+            4,
+            iter.apply_misfire_policy(MisfirePolicy::RunOnce, None, now)
+        );
+        assert_eq!(at(5).unwrap(), iter.peek().unwrap());
+        assert_eq!(at(5).unwrap(), iter.next().unwrap());
+        assert_eq!(at(6).unwrap(), iter.next().unwrap());
+
+        let mut iter =
+            ScheduleIter::from_schedule(&cron_schedule, last_ran_at)?;
+        assert_eq!(
+            3,
+            iter.apply_misfire_policy(
+                MisfirePolicy::RunAllMissed,
+                Some(2),
+                now
+            )
+        );
+        assert_eq!(at(4).unwrap(), iter.next().unwrap());
+        assert_eq!(at(5).unwrap(), iter.next().unwrap());
+        assert_eq!(at(6).unwrap(), iter.next().unwrap());
+
+        // Dropped timepoints are not coming back, so they are no longer
+        // counted as remaining.
+        let run_at_schedule = Schedule::RunAt(RunAt {
+            timepoints: (1..=6)
+                .map(|h| at(h).unwrap().fixed_offset())
+                .collect(),
+            remaining: None,
+            misfire_policy: Some(MisfirePolicy::RunOnce),
+            max_missed_runs: None,
+        });
+        assert_eq!(MisfirePolicy::RunOnce, run_at_schedule.misfire_policy());
+        let mut iter =
+            ScheduleIter::from_schedule(&run_at_schedule, last_ran_at)?;
+        assert_eq!(6, iter.remaining().unwrap());
+        assert_eq!(
+            4,
+            iter.apply_misfire_policy(MisfirePolicy::RunOnce, None, now)
+        );
+        assert_eq!(2, iter.remaining().unwrap());
+        assert_eq!(at(5).unwrap(), iter.next().unwrap());
+        assert_eq!(at(6).unwrap(), iter.next().unwrap());
+        assert!(iter.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn future_ticks_interval() -> Result<(), TriggerError> {
+        let at = |h, m| chrono_tz::UTC.with_ymd_and_hms(2021, 10, 27, h, m, 0);
+        let schedule = Schedule::Interval(Interval {
+            every_s: 90 * 60,
+            anchor: Some(Utc.with_ymd_and_hms(2021, 10, 27, 0, 0, 0).unwrap()),
+            limit: Some(3),
+            remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+        });
+        assert_eq!(3, schedule.limit().unwrap());
+
+        // Ticks are aligned to the anchor: 00:00, 01:30, 03:00, 04:30, ...
+        let after = Utc.with_ymd_and_hms(2021, 10, 27, 2, 0, 0).unwrap();
+        let mut result = ScheduleIter::from_schedule(&schedule, after)?;
+        assert!(matches!(result, ScheduleIter::Interval { .. }));
+        assert_eq!(3, result.remaining().unwrap());
+        assert_eq!(at(3, 0).unwrap(), result.peek().unwrap());
+        assert_eq!(at(3, 0).unwrap(), result.next().unwrap());
+        assert_eq!(at(4, 30).unwrap(), result.next().unwrap());
+        assert_eq!(at(6, 0).unwrap(), result.next().unwrap());
+        assert_eq!(0, result.remaining().unwrap());
+        assert!(result.next().is_none());
+
+        // An anchor in the future is the first tick.
+        let after = Utc.with_ymd_and_hms(2021, 10, 26, 0, 0, 0).unwrap();
+        let mut result = ScheduleIter::from_schedule(&schedule, after)?;
+        assert_eq!(at(0, 0).unwrap(), result.next().unwrap());
+
+        // We missed 03:00, 04:30 and 06:00
+        let after = Utc.with_ymd_and_hms(2021, 10, 27, 2, 0, 0).unwrap();
+        let now = Utc.with_ymd_and_hms(2021, 10, 27, 6, 10, 0).unwrap();
+        let mut result = ScheduleIter::from_schedule(&schedule, after)?;
+        assert_eq!(
+            2,
+            result.apply_misfire_policy(MisfirePolicy::RunOnce, None, now)
+        );
+        assert_eq!(3, result.remaining().unwrap());
+        assert_eq!(at(6, 0).unwrap(), result.next().unwrap());
+        assert_eq!(at(7, 30).unwrap(), result.next().unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn on_completion_of_schedule() {
+        let schedule = Schedule::OnCompletionOf(OnCompletionOf {
+            trigger: "export".to_owned(),
+            outcome: None,
+        });
+        // Dependent triggers have no ticks of their own.
+        assert!(schedule.limit().is_none());
+        assert!(matches!(
+            ScheduleIter::from_schedule(&schedule, Utc::now()),
+            Err(TriggerError::DependentSchedule(_))
+        ));
+        let project = ProjectId::generate();
+        let trigger_id: TriggerId = TriggerId::generate(&project).into();
+        assert!(schedule
+            .estimated_future_runs(&trigger_id, Utc::now(), 5)
+            .is_empty());
+
+        let on_completion_of = schedule.on_completion_of().unwrap();
+        assert!(on_completion_of.fires_on(RunStatus::Succeeded));
+        assert!(!on_completion_of.fires_on(RunStatus::Failed));
+
+        let on_completion_of = OnCompletionOf {
+            trigger: "export".to_owned(),
+            outcome: Some(CompletionOutcome::Any),
+        };
+        assert!(on_completion_of.fires_on(RunStatus::Succeeded));
+        assert!(on_completion_of.fires_on(RunStatus::Failed));
+        assert!(!on_completion_of.fires_on(RunStatus::Attempting));
+    }
+
+    #[test]
+    fn recurring_stored_before_calendars() {
+        // How recurring schedules were stored before calendars were added.
+        let json = r#"{
+            "cron": "0 5 * * * *",
+            "timezone": "Etc/UTC",
+            "limit": null,
+            "remaining": null,
+            "misfire_policy": null,
+            "max_missed_runs": null,
+            "start_at": null,
+            "end_at": null,
+            "jitter_s": null
+        }"#;
+        let recurring: Recurring = serde_json::from_str(json).unwrap();
+        assert_eq!("0 5 * * * *", recurring.cron);
+        assert!(recurring.exclude_calendars.is_empty());
+        assert!(recurring.calendars.is_empty());
+        assert_eq!(None, recurring.dst_policy);
+    }
 }
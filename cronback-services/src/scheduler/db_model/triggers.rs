@@ -1,15 +1,20 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
 use dto::{FromProto, IntoProto};
 use lib::prelude::*;
 use proto::events::TriggerMeta;
 use sea_orm::entity::prelude::*;
-use sea_orm::{DeriveActiveEnum, EnumIter};
+use sea_orm::{DeriveActiveEnum, EnumIter, FromJsonQueryResult};
+use serde::{Deserialize, Serialize};
 
 use super::schedule::Schedule;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[derive(
+    Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize,
+)]
 #[sea_orm(table_name = "triggers")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
@@ -26,6 +31,80 @@ pub struct Model {
     pub action: Action,
     pub status: Status,
     pub last_ran_at: Option<DateTime<Utc>>,
+    pub concurrency_policy: ConcurrencyPolicy,
+    // Set if the trigger runs on completion of another trigger.
+    pub upstream_trigger_id: Option<TriggerId>,
+    // Set if the trigger is paused and should resume on its own.
+    pub paused_until: Option<DateTime<Utc>>,
+    // Upcoming occurrences that won't run.
+    pub skipped_occurrences: SkippedOccurrences,
+    // Revisions recorded before labels existed don't have them.
+    #[serde(default)]
+    pub labels: Labels,
+    // When the trigger got cancelled or expired, retired triggers are deleted
+    // once their project's retention period has passed.
+    #[serde(default)]
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    FromJsonQueryResult,
+)]
+pub struct Labels(pub BTreeMap<String, String>);
+
+/// Matches the triggers whose label `key` is set to `value`, or, if
+/// `not_equal` is set, the ones whose label is missing or has a different
+/// value.
+#[derive(Debug, Clone, PartialEq, Eq, FromProto)]
+#[proto(target = "proto::scheduler_svc::LabelSelector")]
+pub struct LabelSelector {
+    pub key: String,
+    pub value: String,
+    pub not_equal: bool,
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    FromJsonQueryResult,
+)]
+pub struct SkippedOccurrences(pub Vec<DateTime<Utc>>);
+
+impl SkippedOccurrences {
+    pub fn contains(&self, at: &DateTime<Utc>) -> bool {
+        self.0.contains(at)
+    }
+
+    /// Adds the occurrences while keeping the list sorted and free of
+    /// duplicates.
+    pub fn extend(
+        &mut self,
+        occurrences: impl IntoIterator<Item = DateTime<Utc>>,
+    ) {
+        self.0.extend(occurrences);
+        self.0.sort();
+        self.0.dedup();
+    }
+
+    /// Forgets the occurrences at or before `at`, returns true if any was
+    /// dropped.
+    pub fn drop_until(&mut self, at: DateTime<Utc>) -> bool {
+        let len = self.0.len();
+        self.0.retain(|t| *t > at);
+        self.0.len() != len
+    }
 }
 
 impl Model {
@@ -54,6 +133,21 @@ impl Model {
         self.status.alive()
     }
 
+    /// When the trigger retired, triggers retired before `retired_at` was
+    /// recorded fall back to the last time they were touched.
+    pub fn retired_since(&self) -> Option<DateTime<Utc>> {
+        if !self.status.retired() {
+            return None;
+        }
+        self.retired_at.or_else(|| {
+            Some(
+                self.last_ran_at
+                    .max(self.updated_at)
+                    .unwrap_or(self.created_at),
+            )
+        })
+    }
+
     pub fn update(
         &mut self,
         new_name: String,
@@ -61,19 +155,24 @@ impl Model {
         new_payload: Option<Payload>,
         new_schedule: Option<Schedule>,
         new_action: Action,
+        now: DateTime<Utc>,
     ) {
-        self.updated_at = Some(Utc::now());
+        self.updated_at = Some(now);
 
         self.name = new_name;
         self.description = new_description;
         self.payload = new_payload;
         self.schedule = new_schedule;
         self.action = new_action;
-        self.status = if self.schedule.is_some() {
+        self.status = if matches!(
+            self.schedule,
+            Some(ref s) if s.on_completion_of().is_none()
+        ) {
             Status::Scheduled
         } else {
             Status::OnDemand
         };
+        self.retired_at = None;
         // NOTE: we leave last_ran_at as is.
     }
 
@@ -87,14 +186,57 @@ impl Model {
             return vec![];
         }
 
-        if let Some(ref schedule) = self.schedule {
-            let after = self.last_ran_at.unwrap_or(self.created_at);
-            // TODO make 5 configurable
-            schedule.estimated_future_runs(after, 5)
-        } else {
-            vec![]
+        // TODO make 5 configurable
+        self.upcoming_occurrences(self.resume_after(), 5)
+    }
+
+    /// The time after which the schedule picks up again. Runs are recorded
+    /// at their jittered time, so the jitter is taken out of `last_ran_at`,
+    /// otherwise a jitter longer than the schedule's period would skip the
+    /// ticks that fall before it. Triggers that never ran are considered from
+    /// the moment they were created.
+    pub fn resume_after(&self) -> DateTime<Utc> {
+        match (self.last_ran_at, &self.schedule) {
+            | (Some(last_ran_at), Some(schedule)) => {
+                last_ran_at - schedule.jitter(&self.id)
+            }
+            | (Some(last_ran_at), None) => last_ran_at,
+            | (None, _) => self.created_at,
         }
     }
+
+    /// The next `count` occurrences after `after`, the skipped ones are left
+    /// out.
+    pub fn upcoming_occurrences(
+        &self,
+        after: DateTime<Utc>,
+        count: usize,
+    ) -> Vec<DateTime<Utc>> {
+        let Some(ref schedule) = self.schedule else {
+            return vec![];
+        };
+        schedule
+            .estimated_future_runs(
+                &self.id,
+                after,
+                count + self.skipped_occurrences.0.len(),
+            )
+            .into_iter()
+            .filter(|t| !self.skipped_occurrences.contains(t))
+            .take(count)
+            .collect()
+    }
+
+    /// Whether the schedule has an occurrence exactly at `at`.
+    pub fn is_occurrence(&self, at: DateTime<Utc>) -> bool {
+        let Some(ref schedule) = self.schedule else {
+            return false;
+        };
+        // Occurrences are reported with the jitter applied.
+        let after =
+            at - schedule.jitter(&self.id) - chrono::Duration::seconds(1);
+        schedule.estimated_future_runs(&self.id, after, 1).first() == Some(&at)
+    }
 }
 
 #[derive(
@@ -108,6 +250,8 @@ impl Model {
     EnumIter,
     DeriveActiveEnum,
     DeriveDisplay,
+    Serialize,
+    Deserialize,
 )]
 #[sea_orm(rs_type = "String", db_type = "String(None)")]
 #[proto(target = "proto::triggers::TriggerStatus")]
@@ -133,6 +277,12 @@ impl Status {
         [Self::Scheduled, Self::Paused].contains(self)
     }
 
+    // Retired triggers won't run again, they are deleted after the retention
+    // period of their project.
+    pub fn retired(&self) -> bool {
+        [Self::Cancelled, Self::Expired].contains(self)
+    }
+
     pub fn cancelleable(&self) -> bool {
         [Self::Scheduled, Self::Paused, Self::OnDemand].contains(self)
     }
@@ -149,6 +299,33 @@ impl Status {
     }
 }
 
+#[derive(
+    Debug,
+    FromProto,
+    IntoProto,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    DeriveDisplay,
+    Serialize,
+    Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(None)")]
+#[proto(target = "proto::common::ConcurrencyPolicy")]
+pub enum ConcurrencyPolicy {
+    #[default]
+    #[sea_orm(string_value = "Allow")]
+    Allow,
+    #[sea_orm(string_value = "Forbid")]
+    Forbid,
+    #[sea_orm(string_value = "Replace")]
+    Replace,
+}
+
 impl ::std::convert::From<Model> for proto::triggers::Trigger {
     fn from(value: Model) -> Self {
         let estimated_future_runs = value
@@ -168,6 +345,15 @@ impl ::std::convert::From<Model> for proto::triggers::Trigger {
             status: Some(value.status.into()),
             last_ran_at: value.last_ran_at.map(Into::into),
             estimated_future_runs,
+            concurrency_policy: Some(value.concurrency_policy.into()),
+            paused_until: value.paused_until.map(Into::into),
+            skipped_occurrences: value
+                .skipped_occurrences
+                .0
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            labels: value.labels.0.into_iter().collect(),
         }
     }
 }
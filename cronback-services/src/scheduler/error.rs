@@ -1,5 +1,7 @@
+use chrono::{DateTime, Utc};
 use lib::prelude::*;
 use thiserror::Error;
+use tonic::metadata::MetadataValue;
 
 use super::db_model::triggers::Status;
 use super::spinner::dispatch::DispatchError;
@@ -15,10 +17,32 @@ pub enum TriggerError {
          IANA timezone?"
     )]
     InvalidTimezone(String),
+    #[error("Interval of {0} seconds is out of range")]
+    InvalidInterval(u64),
+    #[error("Trigger runs on completion of '{0}' and has no ticks of its own")]
+    DependentSchedule(String),
+    #[error("Upstream trigger '{0}' doesn't exist")]
+    UpstreamNotFound(String),
+    #[error("Trigger dependencies can't form a cycle: {0}")]
+    DependencyCycle(String),
+    #[error("Calendar '{0}' doesn't exist")]
+    CalendarNotFound(String),
+    #[error("Cannot fetch calendars from the metadata service: {0}")]
+    Metadata(String),
+    #[error("'{0}' is not an upcoming occurrence of this trigger")]
+    InvalidOccurrence(DateTime<Utc>),
+    #[error("Timepoint '{0}' is in the past")]
+    PastTimepoint(DateTime<Utc>),
+    #[error("Only triggers with a timepoints schedule have timepoints")]
+    NotRunAt,
+    #[error("A trigger can't have more than {0} upcoming timepoints")]
+    TooManyTimepoints(usize),
     #[error("Trigger '{0}' has no schedule!")]
     NotScheduled(TriggerId),
     #[error("Trigger '{0}' is unknown to this scheduler!")]
     NotFound(String),
+    #[error("Trigger '{0}' has no revision {1}")]
+    RevisionNotFound(/* name */ String, u64),
     #[error("Cannot {0} on a trigger with status {1}")]
     InvalidStatus(String, Status),
     //join error
@@ -32,6 +56,10 @@ pub enum TriggerError {
     AlreadyExists(/* name */ String),
     #[error("{0}")]
     PreconditionFailed(String),
+    #[error("This scheduler replica is on standby, the cell leader is at {0}")]
+    Standby(/* leader address */ String),
+    #[error("Shard {0} is served by another scheduler cell")]
+    ShardMoved(Shard),
 }
 
 // implement From<HandlerError> for Status
@@ -46,12 +74,27 @@ impl From<TriggerError> for tonic::Status {
                 tonic::Status::internal(format!("Internal error: {e}"))
             }
             | TriggerError::NotFound(e) => tonic::Status::not_found(e),
+            | e @ TriggerError::RevisionNotFound(..) => {
+                tonic::Status::not_found(e.to_string())
+            }
             | e @ TriggerError::AlreadyExists(_) => {
                 tonic::Status::already_exists(e.to_string())
             }
             | TriggerError::PreconditionFailed(e) => {
                 tonic::Status::failed_precondition(e)
             }
+            | e @ TriggerError::ShardMoved(_) => {
+                // Lets clients know that their shard routes are stale.
+                let mut status = tonic::Status::unavailable(e.to_string());
+                status.metadata_mut().insert(
+                    SHARD_MOVED_HEADER,
+                    MetadataValue::from_static("1"),
+                );
+                status
+            }
+            | e @ (TriggerError::Metadata(_) | TriggerError::Standby(_)) => {
+                tonic::Status::unavailable(e.to_string())
+            }
             | e => tonic::Status::invalid_argument(e.to_string()),
         }
     }
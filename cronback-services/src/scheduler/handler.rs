@@ -1,37 +1,75 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
+use lib::clients::ScopedSchedulerSvcClient;
 use lib::prelude::*;
 use lib::service::ServiceContext;
+use lib::GrpcClientType;
 use proto::scheduler_svc::scheduler_svc_server::SchedulerSvc;
 use proto::scheduler_svc::{
+    AdoptShardsRequest,
+    AdoptShardsResponse,
+    BulkAction,
+    BulkTriggerResult,
+    BulkUpdateTriggersRequest,
+    BulkUpdateTriggersResponse,
     CancelTriggerRequest,
     CancelTriggerResponse,
     DeleteProjectTriggersRequest,
     DeleteProjectTriggersResponse,
     DeleteTriggerRequest,
     DeleteTriggerResponse,
+    DrainShardsRequest,
+    DrainShardsResponse,
     GetTriggerIdRequest,
     GetTriggerIdResponse,
     GetTriggerRequest,
     GetTriggerResponse,
+    ListTriggerRevisionsRequest,
+    ListTriggerRevisionsResponse,
     ListTriggersFilter,
     ListTriggersRequest,
     ListTriggersResponse,
     PauseTriggerRequest,
     PauseTriggerResponse,
+    PreviewScheduleRequest,
+    PreviewScheduleResponse,
+    RefreshCalendarRequest,
+    RefreshCalendarResponse,
+    ReleaseShardsRequest,
+    ReleaseShardsResponse,
+    RestoreTriggerRevisionRequest,
+    RestoreTriggerRevisionResponse,
     ResumeTriggerRequest,
     ResumeTriggerResponse,
+    RunCompletedRequest,
+    RunCompletedResponse,
     RunTriggerRequest,
     RunTriggerResponse,
+    SkipTriggerRequest,
+    SkipTriggerResponse,
+    UpdateTimepointsRequest,
+    UpdateTimepointsResponse,
     UpsertTriggerRequest,
     UpsertTriggerResponse,
 };
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tonic::metadata::MetadataValue;
 use tonic::{Request, Response, Status};
 
 use super::db_model::triggers;
-use super::spinner::controller::SpinnerController;
+use super::error::TriggerError;
+use super::spinner::controller::{self, ShardData, SpinnerController};
 use super::SchedulerService;
 
+// Set on requests that a standby replica forwarded to the cell leader, they
+// are never forwarded again.
+const FORWARDED_HEADER: &str = "x-cronback-forwarded";
+
+/// Standby replicas of a cell serve reads from the database and reject
+/// requests that change triggers with `UNAVAILABLE`, pointing to the cell
+/// leader.
 pub(crate) struct SchedulerSvcHandler {
     #[allow(unused)]
     context: ServiceContext<SchedulerService>,
@@ -44,6 +82,34 @@ impl SchedulerSvcHandler {
     ) -> Self {
         Self { context, scheduler }
     }
+
+    async fn forward_run_completed(
+        &self,
+        ctx: RequestContext,
+        request: RunCompletedRequest,
+    ) -> Result<Response<RunCompletedResponse>, Status> {
+        let leader = match self.scheduler.cell_leader() {
+            | Some(leader) => leader,
+            | None => {
+                return Err(TriggerError::Standby("unknown".to_string()).into())
+            }
+        };
+        let channel = ScopedSchedulerSvcClient::create_channel(&leader)
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        let mut client = ScopedSchedulerSvcClient::create_raw_client(
+            channel,
+            GrpcRequestInterceptor {
+                project_id: Some(ctx.project_id),
+                request_id: Some(ctx.request_id),
+            },
+        );
+        let mut request = Request::new(request);
+        request
+            .metadata_mut()
+            .insert(FORWARDED_HEADER, MetadataValue::from_static("1"));
+        client.run_completed(request).await
+    }
 }
 
 #[tonic::async_trait]
@@ -53,6 +119,7 @@ impl SchedulerSvc for SchedulerSvcHandler {
         request: Request<UpsertTriggerRequest>,
     ) -> Result<Response<UpsertTriggerResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
         // Creating a new trigger from install_trigger
         let reply = self
             .scheduler
@@ -66,6 +133,7 @@ impl SchedulerSvc for SchedulerSvcHandler {
         request: Request<RunTriggerRequest>,
     ) -> Result<Response<RunTriggerResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
         // A trigger that exists can run regardless of its state.
         let request = request.into_inner();
         let mode = request.mode.into();
@@ -78,6 +146,7 @@ impl SchedulerSvc for SchedulerSvcHandler {
         request: Request<GetTriggerRequest>,
     ) -> Result<Response<GetTriggerResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_readable(&ctx.project_id)?;
         let request = request.into_inner();
         let trigger = self.scheduler.get_trigger(ctx, request.name).await?;
         let reply = GetTriggerResponse {
@@ -91,18 +160,111 @@ impl SchedulerSvc for SchedulerSvcHandler {
         request: Request<PauseTriggerRequest>,
     ) -> Result<Response<PauseTriggerResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
         let request = request.into_inner();
-        let trigger = self.scheduler.pause_trigger(ctx, request.name).await?;
+        let trigger = self
+            .scheduler
+            .pause_trigger(ctx, request.name, request.until.map(Into::into))
+            .await?;
         Ok(Response::new(PauseTriggerResponse {
             trigger: Some(trigger.into()),
         }))
     }
 
+    async fn skip_trigger(
+        &self,
+        request: Request<SkipTriggerRequest>,
+    ) -> Result<Response<SkipTriggerResponse>, Status> {
+        let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
+        let request = request.into_inner();
+        let trigger = self
+            .scheduler
+            .skip_trigger(
+                ctx,
+                request.name,
+                request.count,
+                request.occurrences.into_iter().map(Into::into).collect(),
+            )
+            .await?;
+        Ok(Response::new(SkipTriggerResponse {
+            trigger: Some(trigger.into()),
+        }))
+    }
+
+    async fn update_timepoints(
+        &self,
+        request: Request<UpdateTimepointsRequest>,
+    ) -> Result<Response<UpdateTimepointsResponse>, Status> {
+        let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
+        let request = request.into_inner();
+        let trigger = self
+            .scheduler
+            .update_timepoints(
+                ctx,
+                request.name,
+                request.add.into_iter().map(Into::into).collect(),
+                request.remove.into_iter().map(Into::into).collect(),
+            )
+            .await?;
+        Ok(Response::new(UpdateTimepointsResponse {
+            trigger: Some(trigger.into()),
+        }))
+    }
+
+    async fn list_trigger_revisions(
+        &self,
+        request: Request<ListTriggerRevisionsRequest>,
+    ) -> Result<Response<ListTriggerRevisionsResponse>, Status> {
+        let ctx = request.context()?;
+        self.scheduler.ensure_readable(&ctx.project_id)?;
+        let request = request.into_inner();
+        let paginated_result = self
+            .scheduler
+            .list_trigger_revisions(
+                ctx,
+                request.name,
+                request.pagination.unwrap_or_default(),
+            )
+            .await?;
+        Ok(Response::new(ListTriggerRevisionsResponse {
+            revisions: paginated_result
+                .data
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            pagination: Some(paginated_result.pagination),
+        }))
+    }
+
+    async fn restore_trigger_revision(
+        &self,
+        request: Request<RestoreTriggerRevisionRequest>,
+    ) -> Result<Response<RestoreTriggerRevisionResponse>, Status> {
+        let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
+        let request = request.into_inner();
+        let trigger = self
+            .scheduler
+            .restore_trigger_revision(
+                ctx,
+                request.name,
+                request.revision,
+                request.author,
+            )
+            .await?;
+        Ok(Response::new(RestoreTriggerRevisionResponse {
+            trigger: Some(trigger.into()),
+        }))
+    }
+
     async fn resume_trigger(
         &self,
         request: Request<ResumeTriggerRequest>,
     ) -> Result<Response<ResumeTriggerResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
         let request = request.into_inner();
         let name = request.name;
         let trigger = self.scheduler.resume_trigger(ctx, name).await?;
@@ -116,6 +278,7 @@ impl SchedulerSvc for SchedulerSvcHandler {
         request: Request<CancelTriggerRequest>,
     ) -> Result<Response<CancelTriggerResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
         let request = request.into_inner();
         let trigger = self.scheduler.cancel_trigger(ctx, request.name).await?;
         Ok(Response::new(CancelTriggerResponse {
@@ -128,6 +291,7 @@ impl SchedulerSvc for SchedulerSvcHandler {
         request: Request<DeleteTriggerRequest>,
     ) -> Result<Response<DeleteTriggerResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
         let request = request.into_inner();
         self.scheduler.delete_trigger(ctx, request.name).await?;
         Ok(Response::new(DeleteTriggerResponse {}))
@@ -138,14 +302,17 @@ impl SchedulerSvc for SchedulerSvcHandler {
         request: Request<ListTriggersRequest>,
     ) -> Result<Response<ListTriggersResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_readable(&ctx.project_id)?;
         let request = request.into_inner();
 
-        let statuses = list_filter_into_parts(request.filter);
+        let (statuses, label_selectors) =
+            list_filter_into_parts(request.filter);
         let paginated_result = self
             .scheduler
             .list_triggers(
                 ctx,
                 statuses,
+                label_selectors,
                 request.pagination.unwrap_or_default(),
             )
             .await?;
@@ -159,20 +326,129 @@ impl SchedulerSvc for SchedulerSvcHandler {
         }))
     }
 
+    async fn bulk_update_triggers(
+        &self,
+        request: Request<BulkUpdateTriggersRequest>,
+    ) -> Result<Response<BulkUpdateTriggersResponse>, Status> {
+        let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
+        let request = request.into_inner();
+        let action = match request.action() {
+            | BulkAction::Pause => controller::BulkAction::Pause,
+            | BulkAction::Resume => controller::BulkAction::Resume,
+            | BulkAction::Cancel => controller::BulkAction::Cancel,
+            | BulkAction::Delete => controller::BulkAction::Delete,
+            | BulkAction::Unknown => {
+                return Err(Status::invalid_argument(
+                    "A bulk action must be set",
+                ));
+            }
+        };
+        let statuses = request.statuses.into_iter().map(Into::into).collect();
+        let results = self
+            .scheduler
+            .bulk_update_triggers(
+                ctx,
+                action,
+                statuses,
+                request.name_prefix,
+                request.dry_run,
+            )
+            .await?;
+        Ok(Response::new(BulkUpdateTriggersResponse {
+            results: results
+                .into_iter()
+                .map(|(name, outcome)| {
+                    match outcome {
+                        | Ok(status) => {
+                            BulkTriggerResult {
+                                name,
+                                status: status.map(Into::into),
+                                error: None,
+                            }
+                        }
+                        | Err(e) => {
+                            BulkTriggerResult {
+                                name,
+                                status: None,
+                                error: Some(e.to_string()),
+                            }
+                        }
+                    }
+                })
+                .collect(),
+        }))
+    }
+
     async fn delete_project_triggers(
         &self,
         request: Request<DeleteProjectTriggersRequest>,
     ) -> Result<Response<DeleteProjectTriggersResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
         self.scheduler.delete_project_triggers(ctx).await?;
         Ok(Response::new(DeleteProjectTriggersResponse {}))
     }
 
+    async fn run_completed(
+        &self,
+        request: Request<RunCompletedRequest>,
+    ) -> Result<Response<RunCompletedResponse>, Status> {
+        let ctx = request.context()?;
+        // The dispatcher can reach any replica of the cell, the dependent
+        // triggers are run by the leader.
+        if !self.scheduler.is_leader()
+            && !request.metadata().contains_key(FORWARDED_HEADER)
+        {
+            return self.forward_run_completed(ctx, request.into_inner()).await;
+        }
+        self.scheduler.ensure_serving(&ctx.project_id)?;
+        let request = request.into_inner();
+        let status = request.status();
+        let trigger_id = request.trigger_id.ok_or_else(|| {
+            Status::invalid_argument("trigger_id is required")
+        })?;
+        self.scheduler
+            .run_dependent_triggers(ctx, trigger_id.into(), status)
+            .await?;
+        Ok(Response::new(RunCompletedResponse {}))
+    }
+
+    async fn preview_schedule(
+        &self,
+        request: Request<PreviewScheduleRequest>,
+    ) -> Result<Response<PreviewScheduleResponse>, Status> {
+        let ctx = request.context()?;
+        let request = request.into_inner();
+        let schedule = request
+            .schedule
+            .ok_or_else(|| Status::invalid_argument("schedule is required"))?;
+        let occurrences = self
+            .scheduler
+            .preview_schedule(ctx, schedule.into(), request.count as usize)
+            .await?;
+        Ok(Response::new(PreviewScheduleResponse {
+            occurrences: occurrences.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn refresh_calendar(
+        &self,
+        request: Request<RefreshCalendarRequest>,
+    ) -> Result<Response<RefreshCalendarResponse>, Status> {
+        let ctx = request.context()?;
+        self.scheduler.ensure_serving(&ctx.project_id)?;
+        let request = request.into_inner();
+        self.scheduler.refresh_calendar(ctx, request.name).await?;
+        Ok(Response::new(RefreshCalendarResponse {}))
+    }
+
     async fn get_trigger_id(
         &self,
         request: Request<GetTriggerIdRequest>,
     ) -> Result<Response<GetTriggerIdResponse>, Status> {
         let ctx = request.context()?;
+        self.scheduler.ensure_readable(&ctx.project_id)?;
         let request = request.into_inner();
 
         let trigger_id = self
@@ -183,17 +459,86 @@ impl SchedulerSvc for SchedulerSvcHandler {
             id: Some(trigger_id.into()),
         }))
     }
+
+    async fn drain_shards(
+        &self,
+        request: Request<DrainShardsRequest>,
+    ) -> Result<Response<DrainShardsResponse>, Status> {
+        let shards = parse_shards(request.into_inner().shards)?;
+        let data = self.scheduler.drain_shards(shards).await?;
+        Ok(Response::new(DrainShardsResponse {
+            triggers: encode_all(&data.triggers)?,
+            revisions: encode_all(&data.revisions)?,
+            outbox: encode_all(&data.outbox)?,
+        }))
+    }
+
+    async fn adopt_shards(
+        &self,
+        request: Request<AdoptShardsRequest>,
+    ) -> Result<Response<AdoptShardsResponse>, Status> {
+        let request = request.into_inner();
+        let shards = parse_shards(request.shards)?;
+        let data = ShardData {
+            triggers: decode_all(&request.triggers, "trigger")?,
+            revisions: decode_all(&request.revisions, "revision")?,
+            outbox: decode_all(&request.outbox, "dispatch")?,
+        };
+        self.scheduler.adopt_shards(shards, data).await?;
+        Ok(Response::new(AdoptShardsResponse {}))
+    }
+
+    async fn release_shards(
+        &self,
+        request: Request<ReleaseShardsRequest>,
+    ) -> Result<Response<ReleaseShardsResponse>, Status> {
+        let request = request.into_inner();
+        let shards = parse_shards(request.shards)?;
+        self.scheduler.release_shards(shards, request.moved).await?;
+        Ok(Response::new(ReleaseShardsResponse {}))
+    }
+}
+
+// Shard data is exchanged between cells in the scheduler's storage format.
+fn encode_all<T: Serialize>(items: &[T]) -> Result<Vec<Vec<u8>>, Status> {
+    items
+        .iter()
+        .map(serde_json::to_vec)
+        .collect::<Result<_, _>>()
+        .map_err(|e| Status::internal(e.to_string()))
+}
+
+fn decode_all<T: DeserializeOwned>(
+    raw: &[Vec<u8>],
+    what: &str,
+) -> Result<Vec<T>, Status> {
+    raw.iter()
+        .map(|raw| serde_json::from_slice(raw))
+        .collect::<Result<_, _>>()
+        .map_err(|e| Status::invalid_argument(format!("Malformed {what}: {e}")))
+}
+
+fn parse_shards(shards: Vec<u64>) -> Result<HashSet<Shard>, Status> {
+    if let Some(shard) = shards.iter().find(|s| **s >= SHARD_COUNT) {
+        return Err(Status::invalid_argument(format!(
+            "Shard {shard} is out of range"
+        )));
+    }
+    Ok(shards.into_iter().map(Shard).collect())
 }
 
 fn list_filter_into_parts(
     filter: Option<ListTriggersFilter>,
-) -> Option<Vec<triggers::Status>> {
+) -> (Option<Vec<triggers::Status>>, Vec<triggers::LabelSelector>) {
     let Some(filter) = filter else {
-        return None;
+        return (None, vec![]);
     };
 
-    let ListTriggersFilter { statuses } = filter;
-    if !statuses.is_empty() {
+    let ListTriggersFilter {
+        statuses,
+        label_selectors,
+    } = filter;
+    let statuses = if !statuses.is_empty() {
         Some(
             statuses
                 .into_iter()
@@ -202,5 +547,9 @@ fn list_filter_into_parts(
         )
     } else {
         None
-    }
+    };
+    (
+        statuses,
+        label_selectors.into_iter().map(Into::into).collect(),
+    )
 }
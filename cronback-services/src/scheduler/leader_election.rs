@@ -0,0 +1,180 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use lib::service::ServiceContext;
+use tracing::{error, info, warn};
+use ulid::Ulid;
+
+use super::lease_store::{LeaseStore, LeaseStoreError};
+use super::spinner::controller::SpinnerController;
+use super::SchedulerService;
+
+/// Runs the replicas of a scheduler cell in active/standby mode. Replicas
+/// contend for the cell lease, the holder starts the spinner and keeps
+/// renewing the lease while standby replicas keep trying to take it over once
+/// it stops being renewed.
+pub(crate) struct LeaderElection {
+    context: ServiceContext<SchedulerService>,
+    store: LeaseStore,
+    controller: Arc<SpinnerController>,
+    holder: String,
+    address: String,
+}
+
+impl LeaderElection {
+    pub fn new(
+        context: ServiceContext<SchedulerService>,
+        store: LeaseStore,
+        controller: Arc<SpinnerController>,
+    ) -> Self {
+        let config = context.service_config();
+        let address = config.advertised_address.clone().unwrap_or_else(|| {
+            format!("http://{}:{}", config.address, config.port)
+        });
+        Self {
+            context,
+            store,
+            controller,
+            holder: Ulid::new().to_string(),
+            address,
+        }
+    }
+
+    /// Acquires and renews the lease until the service shuts down.
+    pub async fn run(&self) {
+        let mut context = self.context.clone();
+        let config = context.service_config();
+        let ttl = Duration::from_secs(config.lease_ttl_s);
+        // Renewing three times per ttl tolerates a couple of failed renewals.
+        let renew_every = ttl / 3;
+        // The version of the lease we hold, it's ours until `valid_until`
+        // unless we renew it.
+        let mut held: Option<(i64, Instant)> = None;
+        // The version of someone else's lease and when we first saw it. The
+        // lease is expired once its version doesn't change for a whole ttl,
+        // as measured by our own clock.
+        let mut observed: Option<(i64, Instant)> = None;
+
+        info!(
+            holder = self.holder,
+            cell_id = config.cell_id,
+            "Contending for the scheduler cell lease"
+        );
+        loop {
+            let attempted_at = Instant::now();
+            let res = match held {
+                | Some((version, _)) => self.try_acquire(Some(version)).await,
+                | None => self.try_take_over(&mut observed, ttl).await,
+            };
+
+            match res {
+                | Ok(Some(version)) => {
+                    held = Some((version, attempted_at + ttl));
+                    observed = None;
+                    if !self.controller.is_leader() {
+                        info!(
+                            holder = self.holder,
+                            "Acquired the cell lease, starting the spinner"
+                        );
+                        self.controller.set_cell_leader(None);
+                        if let Err(e) = self.controller.start().await {
+                            error!("Failed to start the spinner: {e}");
+                            self.step_down().await;
+                            held = None;
+                        }
+                    }
+                }
+                | Ok(None) => {
+                    if held.take().is_some() {
+                        warn!(
+                            holder = self.holder,
+                            "Lost the cell lease, stopping the spinner"
+                        );
+                        self.controller.step_down();
+                    }
+                }
+                | Err(e) => {
+                    error!("Failed to acquire the cell lease: {e}");
+                    // We can't tell if another replica has taken over, so we
+                    // stop before our lease expires to avoid running the same
+                    // triggers twice.
+                    if held
+                        .map(|(_, until)| Instant::now() + renew_every >= until)
+                        .unwrap_or(false)
+                    {
+                        warn!(
+                            holder = self.holder,
+                            "Cell lease is about to expire, stopping the \
+                             spinner"
+                        );
+                        self.controller.step_down();
+                        held = None;
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(renew_every) => {},
+                _ = context.recv_shutdown_signal() => {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Releases the lease if we hold it. Meant to be called after the
+    /// controller has checkpointed the triggers on shutdown.
+    pub async fn release(&self) {
+        let cell_id = self.context.service_config().cell_id;
+        if let Err(e) = self.store.release(cell_id, &self.holder).await {
+            error!("Failed to release the cell lease: {e}");
+        }
+    }
+
+    async fn step_down(&self) {
+        self.controller.step_down();
+        self.release().await;
+    }
+
+    async fn try_acquire(
+        &self,
+        expected_version: Option<i64>,
+    ) -> Result<Option<i64>, LeaseStoreError> {
+        let cell_id = self.context.service_config().cell_id;
+        self.store
+            .try_acquire(cell_id, &self.holder, &self.address, expected_version)
+            .await
+    }
+
+    /// Acquires the lease if it's free or if its holder stopped renewing it.
+    async fn try_take_over(
+        &self,
+        observed: &mut Option<(i64, Instant)>,
+        ttl: Duration,
+    ) -> Result<Option<i64>, LeaseStoreError> {
+        let cell_id = self.context.service_config().cell_id;
+        let Some(lease) = self.store.get_lease(cell_id).await? else {
+            return self.try_acquire(None).await;
+        };
+        self.controller
+            .set_cell_leader(Some(lease.holder_address.clone()));
+
+        match *observed {
+            | Some((version, seen_at)) if version == lease.version => {
+                if seen_at.elapsed() < ttl {
+                    return Ok(None);
+                }
+                info!(
+                    holder = self.holder,
+                    expired_holder = lease.holder,
+                    "Cell lease expired, taking it over"
+                );
+                self.try_acquire(Some(version)).await
+            }
+            | _ => {
+                *observed = Some((lease.version, Instant::now()));
+                Ok(None)
+            }
+        }
+    }
+}
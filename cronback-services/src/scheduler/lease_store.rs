@@ -0,0 +1,140 @@
+use lib::prelude::*;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, Set};
+
+use super::db_model::leases::{self, Column};
+use super::db_model::{Lease, Leases};
+
+pub type LeaseStoreError = DatabaseError;
+
+/// Replicas of the same scheduler cell contend for a single lease row, the
+/// replica that keeps renewing the lease is the leader of the cell.
+#[derive(Clone)]
+pub struct LeaseStore {
+    db: Database,
+}
+
+impl LeaseStore {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Acquires the lease of the cell if it's free, or takes it over if it's
+    /// still at `expected_version`. The holder renews its lease the same way
+    /// with the version it got from the last call. Returns the new version of
+    /// the lease, or `None` if someone else changed it in the meantime.
+    ///
+    /// Versions act as fencing tokens, replicas don't compare timestamps
+    /// since their clocks might not agree.
+    pub async fn try_acquire(
+        &self,
+        cell_id: u32,
+        holder: &str,
+        holder_address: &str,
+        expected_version: Option<i64>,
+    ) -> Result<Option<i64>, LeaseStoreError> {
+        let Some(expected_version) = expected_version else {
+            let lease = leases::ActiveModel {
+                cell_id: Set(cell_id.into()),
+                holder: Set(holder.to_string()),
+                holder_address: Set(holder_address.to_string()),
+                version: Set(1),
+            };
+            let inserted = Leases::insert(lease)
+                .on_conflict(
+                    OnConflict::column(Column::CellId).do_nothing().to_owned(),
+                )
+                .exec_without_returning(&self.db.orm)
+                .await?;
+            return Ok((inserted > 0).then_some(1));
+        };
+
+        let version = expected_version + 1;
+        let res = Leases::update_many()
+            .col_expr(Column::Holder, Expr::value(holder))
+            .col_expr(Column::HolderAddress, Expr::value(holder_address))
+            .col_expr(Column::Version, Expr::value(version))
+            .filter(Column::CellId.eq(i64::from(cell_id)))
+            .filter(Column::Version.eq(expected_version))
+            .exec(&self.db.orm)
+            .await?;
+        Ok((res.rows_affected > 0).then_some(version))
+    }
+
+    /// Gives up the lease so that a standby replica can take over without
+    /// waiting for it to expire.
+    pub async fn release(
+        &self,
+        cell_id: u32,
+        holder: &str,
+    ) -> Result<(), LeaseStoreError> {
+        Leases::delete_many()
+            .filter(Column::CellId.eq(i64::from(cell_id)))
+            .filter(Column::Holder.eq(holder))
+            .exec(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_lease(
+        &self,
+        cell_id: u32,
+    ) -> Result<Option<Lease>, LeaseStoreError> {
+        let res = Leases::find_by_id(i64::from(cell_id))
+            .one(&self.db.orm)
+            .await?;
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lib::service::CronbackService;
+
+    use super::LeaseStore;
+    use crate::scheduler::SchedulerService;
+
+    #[tokio::test]
+    async fn test_lease_store() -> anyhow::Result<()> {
+        let db = SchedulerService::in_memory_database().await?;
+        let store = LeaseStore::new(db);
+
+        // First replica to ask becomes the leader.
+        assert_eq!(store.try_acquire(0, "a", "http://a", None).await?, Some(1));
+        // Other replicas can't take a lease that exists.
+        assert_eq!(store.try_acquire(0, "b", "http://b", None).await?, None);
+        // Leases are per cell.
+        assert_eq!(store.try_acquire(1, "b", "http://b", None).await?, Some(1));
+
+        // The holder renews its lease, bumping its version.
+        assert_eq!(
+            store.try_acquire(0, "a", "http://a", Some(1)).await?,
+            Some(2)
+        );
+        // A replica that saw an older version can't take the lease over.
+        assert_eq!(store.try_acquire(0, "b", "http://b", Some(1)).await?, None);
+        let lease = store.get_lease(0).await?.unwrap();
+        assert_eq!(lease.holder, "a");
+        assert_eq!(lease.version, 2);
+
+        // A lease that stopped changing is taken over.
+        assert_eq!(
+            store.try_acquire(0, "b", "http://b", Some(2)).await?,
+            Some(3)
+        );
+        // And the old holder can't renew it anymore.
+        assert_eq!(store.try_acquire(0, "a", "http://a", Some(2)).await?, None);
+        let lease = store.get_lease(0).await?.unwrap();
+        assert_eq!(lease.holder, "b");
+        assert_eq!(lease.holder_address, "http://b");
+
+        // Releasing only works for the holder.
+        store.release(0, "a").await?;
+        assert!(store.get_lease(0).await?.is_some());
+        store.release(0, "b").await?;
+        assert_eq!(store.get_lease(0).await?, None);
+        assert_eq!(store.try_acquire(0, "a", "http://a", None).await?, Some(1));
+
+        Ok(())
+    }
+}
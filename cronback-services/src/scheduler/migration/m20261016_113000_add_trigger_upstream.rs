@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Triggers::Table)
+                    .add_column(
+                        ColumnDef::new(Triggers::UpstreamTriggerId).string(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("IX_triggers_project_upstream_trigger")
+                    .table(Triggers::Table)
+                    .col(Triggers::ProjectId)
+                    .col(Triggers::UpstreamTriggerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("IX_triggers_project_upstream_trigger")
+                    .table(Triggers::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Triggers::Table)
+                    .drop_column(Triggers::UpstreamTriggerId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Triggers {
+    Table,
+    ProjectId,
+    UpstreamTriggerId,
+}
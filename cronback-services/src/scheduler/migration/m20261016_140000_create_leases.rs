@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Leases::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Leases::CellId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Leases::Holder).string().not_null())
+                    .col(
+                        ColumnDef::new(Leases::HolderAddress)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Leases::Version)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Leases::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Leases {
+    Table,
+    CellId,
+    Holder,
+    HolderAddress,
+    Version,
+}
@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DispatchOutbox::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DispatchOutbox::RunId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(DispatchOutbox::ProjectId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DispatchOutbox::TriggerId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DispatchOutbox::ScheduledAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DispatchOutbox::Request)
+                            .binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DispatchOutbox::Attempts)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(DispatchOutbox::NextAttemptAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DispatchOutbox::LastError).string())
+                    .col(
+                        ColumnDef::new(DispatchOutbox::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("IX_dispatch_outbox_next_attempt_at")
+                    .table(DispatchOutbox::Table)
+                    .col(DispatchOutbox::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(DispatchOutbox::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum DispatchOutbox {
+    Table,
+    RunId,
+    ProjectId,
+    TriggerId,
+    ScheduledAt,
+    Request,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    CreatedAt,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TriggerRevisions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TriggerRevisions::TriggerId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TriggerRevisions::Revision)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TriggerRevisions::ProjectId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TriggerRevisions::Author).string())
+                    .col(
+                        ColumnDef::new(TriggerRevisions::Trigger)
+                            .json()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TriggerRevisions::CreatedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(TriggerRevisions::TriggerId)
+                            .col(TriggerRevisions::Revision),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TriggerRevisions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum TriggerRevisions {
+    Table,
+    TriggerId,
+    Revision,
+    ProjectId,
+    Author,
+    Trigger,
+    CreatedAt,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("UX_trigger_revisions_project_trigger_revision")
+                    .table(TriggerRevisions::Table)
+                    .col(TriggerRevisions::ProjectId)
+                    .col(TriggerRevisions::TriggerId)
+                    .col(TriggerRevisions::Revision)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("UX_trigger_revisions_project_trigger_revision")
+                    .table(TriggerRevisions::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum TriggerRevisions {
+    Table,
+    TriggerId,
+    Revision,
+    ProjectId,
+}
@@ -1,12 +1,40 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20230521_233041_create_triggers;
+mod m20261016_101500_add_trigger_concurrency_policy;
+mod m20261016_113000_add_trigger_upstream;
+mod m20261016_130000_add_trigger_paused_until;
+mod m20261016_133000_add_trigger_skipped_occurrences;
+mod m20261016_140000_create_leases;
+mod m20261016_150000_create_dispatch_outbox;
+mod m20261016_160000_create_trigger_revisions;
+mod m20261016_165000_add_trigger_revisions_unique_index;
+mod m20261016_170000_add_trigger_labels;
+mod m20261016_180000_add_trigger_retired_at;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20230521_233041_create_triggers::Migration)]
+        vec![
+            Box::new(m20230521_233041_create_triggers::Migration),
+            Box::new(
+                m20261016_101500_add_trigger_concurrency_policy::Migration,
+            ),
+            Box::new(m20261016_113000_add_trigger_upstream::Migration),
+            Box::new(m20261016_130000_add_trigger_paused_until::Migration),
+            Box::new(
+                m20261016_133000_add_trigger_skipped_occurrences::Migration,
+            ),
+            Box::new(m20261016_140000_create_leases::Migration),
+            Box::new(m20261016_150000_create_dispatch_outbox::Migration),
+            Box::new(m20261016_160000_create_trigger_revisions::Migration),
+            Box::new(
+                m20261016_165000_add_trigger_revisions_unique_index::Migration,
+            ),
+            Box::new(m20261016_170000_add_trigger_labels::Migration),
+            Box::new(m20261016_180000_add_trigger_retired_at::Migration),
+        ]
     }
 }
@@ -2,7 +2,10 @@ mod config;
 pub(crate) mod db_model;
 pub(crate) mod error;
 pub(crate) mod handler;
+mod leader_election;
+pub(crate) mod lease_store;
 mod migration;
+pub(crate) mod outbox_store;
 pub(crate) mod spinner;
 pub(crate) mod trigger_store;
 
@@ -11,10 +14,13 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use handler::SchedulerSvcHandler;
+use leader_election::LeaderElection;
+use lease_store::LeaseStore;
 use lib::prelude::*;
 use lib::service::{CronbackService, ServiceContext};
 use lib::{netutils, service, GrpcClientProvider};
 use metrics::{describe_gauge, describe_histogram, Unit};
+use outbox_store::OutboxStore;
 use proto::scheduler_svc::scheduler_svc_server::SchedulerSvcServer;
 use spinner::controller::SpinnerController;
 use trigger_store::TriggerStore;
@@ -49,6 +55,16 @@ impl CronbackService for SchedulerService {
             Unit::Count,
             "How many active triggers are loaded into the spinner"
         );
+        describe_gauge!(
+            "scheduler.dispatch_outbox_depth",
+            Unit::Count,
+            "How many dispatches are waiting in the outbox to be retried"
+        );
+        describe_gauge!(
+            "scheduler.dispatch_outbox_age_seconds",
+            Unit::Seconds,
+            "How long the oldest dispatch in the outbox has been waiting"
+        );
     }
 
     #[tracing::instrument(skip_all, fields(service = context.service_name()))]
@@ -58,19 +74,38 @@ impl CronbackService for SchedulerService {
     ) -> anyhow::Result<()> {
         let config = context.service_config();
 
-        let trigger_store = TriggerStore::new(db);
+        let trigger_store = TriggerStore::new(db.clone());
+        let outbox_store = OutboxStore::new(db.clone());
 
         let dispatcher_clients =
             Arc::new(GrpcClientProvider::new(context.config().clone()));
+        let metadata_clients =
+            Arc::new(GrpcClientProvider::new(context.config().clone()));
 
         let controller = Arc::new(SpinnerController::new(
             context.clone(),
             trigger_store,
+            outbox_store,
             dispatcher_clients,
+            metadata_clients,
         ));
 
         let addr = netutils::parse_addr(&config.address, config.port).unwrap();
-        controller.start().await?;
+        // With leader election, the spinner only starts once this replica
+        // acquires the cell lease.
+        let election = if config.leader_election {
+            let election = Arc::new(LeaderElection::new(
+                context.clone(),
+                LeaseStore::new(db),
+                controller.clone(),
+            ));
+            let cloned_election = election.clone();
+            tokio::spawn(async move { cloned_election.run().await });
+            Some(election)
+        } else {
+            controller.start().await?;
+            None
+        };
 
         let async_es = controller.clone();
         let db_flush_s = config.db_flush_s;
@@ -82,6 +117,34 @@ impl CronbackService for SchedulerService {
             }
         });
 
+        let relay = controller.clone();
+        let outbox_poll_s = config.outbox_poll_s;
+        let mut relay_context = context.clone();
+        tokio::spawn(async move {
+            let sleep = Duration::from_secs(outbox_poll_s);
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(sleep) => {},
+                    // Dispatches that are still due stay in the outbox for
+                    // the next leader.
+                    _ = relay_context.recv_shutdown_signal() => {
+                        break;
+                    }
+                }
+                relay.relay_outbox().await;
+            }
+        });
+
+        let collector = controller.clone();
+        let retention_gc_s = config.retention_gc_s;
+        tokio::spawn(async move {
+            let sleep = Duration::from_secs(retention_gc_s);
+            loop {
+                tokio::time::sleep(sleep).await;
+                collector.collect_retired_triggers().await;
+            }
+        });
+
         let handler =
             SchedulerSvcHandler::new(context.clone(), controller.clone());
         let svc = SchedulerSvcServer::new(handler);
@@ -96,6 +159,9 @@ impl CronbackService for SchedulerService {
         .await;
 
         controller.shutdown().await;
+        if let Some(election) = election {
+            election.release().await;
+        }
         Ok(())
     }
 }
@@ -124,14 +190,19 @@ pub mod test_helpers {
 
         let dispatcher_client_provider =
             Arc::new(GrpcClientProvider::new(context.config().clone()));
+        let metadata_client_provider =
+            Arc::new(GrpcClientProvider::new(context.config().clone()));
 
         let db = SchedulerService::in_memory_database().await.unwrap();
 
-        let trigger_store = TriggerStore::new(db);
+        let trigger_store = TriggerStore::new(db.clone());
+        let outbox_store = OutboxStore::new(db);
         let controller = Arc::new(SpinnerController::new(
             context.clone(),
             trigger_store,
+            outbox_store,
             dispatcher_client_provider,
+            metadata_client_provider,
         ));
         controller.start().await.unwrap();
 
@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use lib::prelude::*;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+
+use super::db_model::outbox::{self, Column};
+use super::db_model::{DispatchOutbox, OutboxEntry};
+
+pub type OutboxStoreError = DatabaseError;
+
+/// Dispatches that failed to reach the dispatcher are kept here until a retry
+/// succeeds.
+#[derive(Clone)]
+pub struct OutboxStore {
+    db: Database,
+}
+
+impl OutboxStore {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Adds a dispatch to the outbox, a dispatch that's already there is left
+    /// untouched.
+    pub async fn enqueue(
+        &self,
+        entry: OutboxEntry,
+    ) -> Result<(), OutboxStoreError> {
+        let active_model: outbox::ActiveModel = entry.into();
+        DispatchOutbox::insert(active_model)
+            .on_conflict(
+                OnConflict::column(Column::RunId).do_nothing().to_owned(),
+            )
+            .exec_without_returning(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    /// The dispatches that are due for a retry, oldest first.
+    pub async fn get_due(
+        &self,
+        now: DateTime<Utc>,
+        limit: u64,
+    ) -> Result<Vec<OutboxEntry>, OutboxStoreError> {
+        let res = DispatchOutbox::find()
+            .filter(Column::NextAttemptAt.lte(now))
+            .order_by_asc(Column::NextAttemptAt)
+            .limit(limit)
+            .all(&self.db.orm)
+            .await?;
+        Ok(res)
+    }
+
+    pub async fn reschedule(
+        &self,
+        run_id: &RunId,
+        next_attempt_at: DateTime<Utc>,
+        error: String,
+    ) -> Result<(), OutboxStoreError> {
+        DispatchOutbox::update_many()
+            .col_expr(Column::Attempts, Expr::col(Column::Attempts).add(1))
+            .col_expr(Column::NextAttemptAt, Expr::value(next_attempt_at))
+            .col_expr(Column::LastError, Expr::value(error))
+            .filter(Column::RunId.eq(run_id.clone()))
+            .exec(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, run_id: &RunId) -> Result<(), OutboxStoreError> {
+        DispatchOutbox::delete_by_id(run_id.clone())
+            .exec(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    /// The dispatches of projects that live in the given shards.
+    pub async fn get_by_shards(
+        &self,
+        shards: &HashSet<Shard>,
+    ) -> Result<Vec<OutboxEntry>, OutboxStoreError> {
+        let res = DispatchOutbox::find()
+            .all(&self.db.orm)
+            .await?
+            .into_iter()
+            .filter(|e| shards.contains(&e.project_id.shard()))
+            .collect();
+        Ok(res)
+    }
+
+    /// Deletes the dispatches of projects that live in the given shards. Only
+    /// meant for shards that were handed over to another cell.
+    pub async fn remove_by_shards(
+        &self,
+        shards: &HashSet<Shard>,
+    ) -> Result<(), OutboxStoreError> {
+        let run_ids: Vec<_> = self
+            .get_by_shards(shards)
+            .await?
+            .into_iter()
+            .map(|e| e.run_id)
+            .collect();
+        DispatchOutbox::delete_many()
+            .filter(Column::RunId.is_in(run_ids))
+            .exec(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    /// How many dispatches are waiting in the outbox, and since when the
+    /// oldest one is waiting.
+    pub async fn stats(
+        &self,
+    ) -> Result<(u64, Option<DateTime<Utc>>), OutboxStoreError> {
+        let res: Option<(i64, Option<DateTime<Utc>>)> = DispatchOutbox::find()
+            .select_only()
+            .column_as(Column::RunId.count(), "depth")
+            .column_as(Column::CreatedAt.min(), "oldest")
+            .into_tuple()
+            .one(&self.db.orm)
+            .await?;
+        Ok(res
+            .map(|(depth, oldest)| (depth as u64, oldest))
+            .unwrap_or_default())
+    }
+}
+
+impl OutboxEntry {
+    pub fn new(
+        run_id: RunId,
+        project_id: ValidShardedId<ProjectId>,
+        trigger_id: TriggerId,
+        scheduled_at: DateTime<Utc>,
+        request: Vec<u8>,
+        error: String,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            run_id,
+            project_id,
+            trigger_id,
+            scheduled_at,
+            request,
+            attempts: 1,
+            next_attempt_at: now,
+            last_error: Some(error),
+            created_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Timelike, Utc};
+    use lib::service::CronbackService;
+
+    use super::*;
+    use crate::scheduler::SchedulerService;
+
+    fn build_entry(
+        project: &ValidShardedId<ProjectId>,
+        next_attempt_at: DateTime<Utc>,
+    ) -> OutboxEntry {
+        let trigger_id = TriggerId::generate(project);
+        OutboxEntry::new(
+            RunId::generate(project).into(),
+            project.clone(),
+            trigger_id.into(),
+            next_attempt_at,
+            vec![1, 2, 3],
+            "dispatcher is unreachable".to_owned(),
+            next_attempt_at,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_outbox_store() -> anyhow::Result<()> {
+        let db = SchedulerService::in_memory_database().await?;
+        let store = OutboxStore::new(db);
+
+        assert_eq!(store.stats().await?, (0, None));
+
+        let project = ProjectId::generate();
+        let now = Utc::now().with_nanosecond(0).unwrap();
+        let e1 = build_entry(&project, now - Duration::seconds(10));
+        let e2 = build_entry(&project, now - Duration::seconds(5));
+        let e3 = build_entry(&project, now + Duration::seconds(60));
+
+        store.enqueue(e2.clone()).await?;
+        store.enqueue(e1.clone()).await?;
+        store.enqueue(e3.clone()).await?;
+        // Enqueuing the same run again is a no-op.
+        store.enqueue(e1.clone()).await?;
+
+        assert_eq!(store.get_due(now, 10).await?, vec![e1.clone(), e2.clone()]);
+        assert_eq!(store.get_due(now, 1).await?, vec![e1.clone()]);
+        assert_eq!(store.stats().await?, (3, Some(e1.created_at)));
+
+        // A failed retry pushes the entry back.
+        store
+            .reschedule(&e1.run_id, now + Duration::seconds(30), "boom".into())
+            .await?;
+        let due = store.get_due(now + Duration::seconds(30), 10).await?;
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0], e2);
+        assert_eq!(due[1].run_id, e1.run_id);
+        assert_eq!(due[1].attempts, 2);
+        assert_eq!(due[1].last_error.as_deref(), Some("boom"));
+
+        store.remove(&e1.run_id).await?;
+        store.remove(&e2.run_id).await?;
+        assert_eq!(store.stats().await?, (1, Some(e3.created_at)));
+
+        Ok(())
+    }
+}
@@ -1,12 +1,13 @@
 use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
 use chrono_tz::Tz;
 use lib::prelude::*;
 use tracing::{info, trace};
 
-use crate::scheduler::db_model::schedule::ScheduleIter;
+use crate::scheduler::db_model::schedule::{Schedule, ScheduleIter};
 use crate::scheduler::db_model::triggers::Status;
 use crate::scheduler::db_model::Trigger;
 use crate::scheduler::error::TriggerError;
@@ -18,15 +19,33 @@ use crate::scheduler::error::TriggerError;
 /// dirty_triggers holds the set of triggers that has been updated since the
 /// last time the spinner has looked at it. The spinner resets the set after
 /// reloading.
-#[derive(Default)]
 pub(crate) struct ActiveTriggerMap {
     state: HashMap<TriggerId, ActiveTrigger>,
     /// The set of trigger Ids that has been updated
     dirty: bool,
     awaiting_db_flush: HashSet<TriggerId>,
+    /// Paused triggers that will resume on their own.
+    snoozed: HashSet<TriggerId>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for ActiveTriggerMap {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock))
+    }
 }
 
 impl ActiveTriggerMap {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            state: HashMap::default(),
+            dirty: false,
+            awaiting_db_flush: HashSet::default(),
+            snoozed: HashSet::default(),
+            clock,
+        }
+    }
+
     /// Inserts or updates a trigger if exists
     pub fn add_or_update(
         &mut self,
@@ -37,12 +56,27 @@ impl ActiveTriggerMap {
         // get the updated status
         let cloned_trigger = trigger.clone();
         let trigger_id = trigger.id.clone();
-        let active_trigger = ActiveTrigger::try_from(trigger, fast_forward)?;
+        if trigger.status == Status::Paused && trigger.paused_until.is_some() {
+            self.snoozed.insert(trigger_id.clone());
+        }
+        let active_trigger =
+            ActiveTrigger::try_from(trigger, fast_forward, self.clock.now())?;
         self.state.insert(trigger_id, active_trigger);
         self.mark_dirty();
         Ok(cloned_trigger)
     }
 
+    /// Fails the same way `add_or_update` would for this trigger, without
+    /// adding it.
+    pub fn check(
+        &self,
+        trigger: &Trigger,
+        fast_forward: bool,
+    ) -> Result<(), TriggerError> {
+        ActiveTrigger::try_from(trigger.clone(), fast_forward, self.clock.now())
+            .map(|_| ())
+    }
+
     /// Indicates whether the trigger map has been updated since the last time
     /// `reset_dirty` was called.
     pub fn is_dirty(&self) -> bool {
@@ -65,9 +99,7 @@ impl ActiveTriggerMap {
     pub fn is_trigger_retired(&self, trigger_id: &TriggerId) -> bool {
         self.state
             .get(trigger_id)
-            .map(|t| {
-                [Status::Cancelled, Status::Expired].contains(&t.get().status)
-            })
+            .map(|t| t.get().status.retired())
             .unwrap_or(false)
     }
 
@@ -85,7 +117,8 @@ impl ActiveTriggerMap {
         res
     }
 
-    // Should be used to only push dead/retired triggers.
+    // Should be used to only push dead/retired triggers, or to put back the
+    // ones that were popped.
     pub fn push_trigger(
         &mut self,
         trigger_id: TriggerId,
@@ -99,6 +132,8 @@ impl ActiveTriggerMap {
 
     pub fn clear(&mut self) {
         self.state.clear();
+        self.awaiting_db_flush.clear();
+        self.snoozed.clear();
         self.reset_dirty();
     }
 
@@ -141,6 +176,14 @@ impl ActiveTriggerMap {
         self.state.get(id).map(|t| t.get())
     }
 
+    pub fn get_by_project(&self, project_id: &ProjectId) -> Vec<&Trigger> {
+        self.state
+            .values()
+            .filter(|t| t.project() == project_id)
+            .map(|t| t.get())
+            .collect()
+    }
+
     /*
      * Advance ensures that the next tick is not the same as current tick.
      * I hear you asking, why do we need that?
@@ -166,7 +209,10 @@ impl ActiveTriggerMap {
             return None;
         }
 
-        let next_tick = trigger.advance();
+        let (next_tick, dropped_skipped) = trigger.advance();
+        if dropped_skipped {
+            self.add_to_awaiting_db_flush(trigger_id.clone());
+        }
 
         if next_tick.is_some() {
             return next_tick;
@@ -178,15 +224,90 @@ impl ActiveTriggerMap {
         None
     }
 
+    /// Pauses the trigger, until the given time if set, or until it gets
+    /// resumed otherwise.
     pub fn pause(
         &mut self,
         trigger_id: &TriggerId,
+        until: Option<DateTime<Utc>>,
     ) -> Result<(), TriggerError> {
         self.update_status(
             trigger_id,
             Status::Paused,
             &[Status::Cancelled, Status::Expired],
-        )
+        )?;
+        let trigger = self.state.get_mut(trigger_id).unwrap();
+        if trigger.inner.paused_until != until {
+            trigger.inner.paused_until = until;
+            self.add_to_awaiting_db_flush(trigger_id.clone());
+        }
+        if until.is_some() {
+            self.snoozed.insert(trigger_id.clone());
+        }
+        Ok(())
+    }
+
+    /// Marks upcoming occurrences of the trigger to be skipped.
+    pub fn skip(
+        &mut self,
+        trigger_id: &TriggerId,
+        occurrences: Vec<DateTime<Utc>>,
+    ) -> Result<(), TriggerError> {
+        let Some(trigger) = self.state.get_mut(trigger_id) else {
+            return Err(TriggerError::NotFound(trigger_id.to_string()));
+        };
+        trigger.inner.skipped_occurrences.extend(occurrences);
+        self.add_to_awaiting_db_flush(trigger_id.clone());
+        Ok(())
+    }
+
+    /// Adds timepoints to, and removes timepoints from, the upcoming ones of a
+    /// `RunAt` trigger, without reinstalling it.
+    pub fn update_timepoints(
+        &mut self,
+        trigger_id: &TriggerId,
+        add: &[DateTime<FixedOffset>],
+        remove: &[DateTime<FixedOffset>],
+    ) -> Result<(), TriggerError> {
+        let Some(trigger) = self.state.get_mut(trigger_id) else {
+            return Err(TriggerError::NotFound(trigger_id.to_string()));
+        };
+        trigger.update_timepoints(add, remove)?;
+        self.add_to_awaiting_db_flush(trigger_id.clone());
+        // The next tick might have changed.
+        self.mark_dirty();
+        Ok(())
+    }
+
+    /// Resumes the paused triggers whose `paused_until` is at or before
+    /// `now`.
+    pub fn resume_snoozed(&mut self, now: DateTime<Utc>) {
+        if self.snoozed.is_empty() {
+            return;
+        }
+        let mut due = Vec::new();
+        self.snoozed.retain(|trigger_id| {
+            let Some(trigger) = self.state.get(trigger_id) else {
+                return false;
+            };
+            let trigger = trigger.get();
+            match trigger.paused_until {
+                | Some(until) if trigger.status == Status::Paused => {
+                    if until <= now {
+                        due.push(trigger_id.clone());
+                        return false;
+                    }
+                    true
+                }
+                // Resumed or cancelled in the meantime.
+                | _ => false,
+            }
+        });
+        for trigger_id in due {
+            info!(%trigger_id, "Resuming trigger, its pause has elapsed");
+            // Only fails if the trigger is no longer in the map.
+            let _ = self.resume(&trigger_id);
+        }
     }
 
     pub fn resume(
@@ -213,6 +334,7 @@ impl ActiveTriggerMap {
         new_status: Status,
         reject_statuses: &[Status],
     ) -> Result<(), TriggerError> {
+        let now = self.clock.now();
         let Some(trigger) = self.state.get_mut(trigger_id) else {
             return Err(TriggerError::NotFound(trigger_id.to_string()));
         };
@@ -227,7 +349,7 @@ impl ActiveTriggerMap {
             ));
         }
 
-        if trigger.update_status(new_status.clone()) {
+        if trigger.update_status(new_status.clone(), now) {
             e!(
                 project_id = project_id,
                 TriggerStatusUpdated {
@@ -293,6 +415,34 @@ impl ActiveTriggerMap {
         self.mark_dirty();
     }
 
+    /// Removes the triggers of projects that live in the given shards and
+    /// returns them, including their progress that's awaiting a db flush.
+    pub fn remove_by_shards(
+        &mut self,
+        shards: &HashSet<Shard>,
+    ) -> Vec<Trigger> {
+        let trigger_ids: Vec<TriggerId> = self
+            .state
+            .iter()
+            .filter(|(_, t)| shards.contains(&t.get().project_id.shard()))
+            .map(|(id, _)| id.clone())
+            .collect();
+        let mut removed = Vec::with_capacity(trigger_ids.len());
+        for trigger_id in trigger_ids {
+            let trigger = self.state.remove(&trigger_id).unwrap();
+            self.awaiting_db_flush.remove(&trigger_id);
+            self.snoozed.remove(&trigger_id);
+            removed.push(trigger.get().clone());
+        }
+        info!(
+            "Removed {} triggers of shards {:?} from active trigger map",
+            removed.len(),
+            shards,
+        );
+        self.mark_dirty();
+        removed
+    }
+
     //// PRIVATE
     fn mark_dirty(&mut self) {
         self.dirty = true;
@@ -347,19 +497,36 @@ impl ActiveTrigger {
     fn try_from(
         mut trigger: Trigger,
         fast_forward: bool,
+        now: DateTime<Utc>,
     ) -> Result<Self, TriggerError> {
+        // On fast forward, we ignore the last run time and only look at the
+        // ticks after `now`.
+        let after = if fast_forward {
+            now
+        } else {
+            trigger.resume_after()
+        };
         // Do we have a cron pattern or a set of time points?
         let k = trigger
             .schedule
             .as_mut()
             .ok_or_else(|| TriggerError::NotScheduled(trigger.id.clone()))?;
-        // On fast forward, we ignore the last run time.
-        let last_ran_at = if fast_forward {
-            None
-        } else {
-            trigger.last_ran_at
-        };
-        let ticks = ScheduleIter::from_schedule(k, last_ran_at)?;
+        let mut ticks = ScheduleIter::from_schedule(k, after)?
+            .with_jitter(k.jitter(&trigger.id));
+        if !fast_forward {
+            let policy = k.misfire_policy();
+            let dropped =
+                ticks.apply_misfire_policy(policy, k.max_missed_runs(), now);
+            if dropped > 0 {
+                info!(
+                    trigger_id = %trigger.id,
+                    "Dropped {} missed tick(s) according to the misfire \
+                     policy {:?}",
+                    dropped,
+                    policy,
+                );
+            }
+        }
 
         // RunAt triggers in particular might have expired timepoints that will
         // be skipped. Update the underlying trigger so that the
@@ -392,22 +559,63 @@ impl ActiveTrigger {
         self.inner.alive()
     }
 
-    pub fn advance(&mut self) -> Option<DateTime<Tz>> {
-        // Advances the iterator and peeks the following item
-        let _ = self.ticks.next();
+    /// Advances the iterator and peeks the following item. Also returns
+    /// whether skipped occurrences were consumed along the way.
+    pub fn advance(&mut self) -> (Option<DateTime<Tz>>, bool) {
+        let current = self.ticks.next();
+        // Skipped occurrences don't count against the run limit.
+        let skipped = current.map_or(false, |tick| {
+            self.inner
+                .skipped_occurrences
+                .contains(&tick.with_timezone(&Utc))
+        });
+        if skipped {
+            self.ticks.restore_run();
+        }
+        // Skipped occurrences are not needed once their time has passed.
+        let dropped_skipped = current
+            .map(|tick| {
+                self.inner
+                    .skipped_occurrences
+                    .drop_until(tick.with_timezone(&Utc))
+            })
+            .unwrap_or(false);
         let res = self.ticks.peek();
         let schedule = self.inner.schedule.as_mut().unwrap();
         schedule.set_remaining(self.ticks.remaining());
-        res
+        (res, dropped_skipped)
     }
 
     pub fn last_ran_at(&self) -> Option<DateTime<Utc>> {
         self.inner.last_ran_at
     }
 
+    fn update_timepoints(
+        &mut self,
+        add: &[DateTime<FixedOffset>],
+        remove: &[DateTime<FixedOffset>],
+    ) -> Result<(), TriggerError> {
+        let upcoming = self.ticks.update_timepoints(add, remove)?;
+        if let Some(Schedule::RunAt(run_at)) = self.inner.schedule.as_mut() {
+            // The timepoints that passed already are dropped, the trigger
+            // won't run them again anyway.
+            run_at.timepoints = upcoming;
+            run_at.remaining = self.ticks.remaining();
+        }
+        Ok(())
+    }
+
     // Returns true if state has changed.
-    fn update_status(&mut self, new_status: Status) -> bool {
+    fn update_status(
+        &mut self,
+        new_status: Status,
+        now: DateTime<Utc>,
+    ) -> bool {
         if self.inner.status != new_status {
+            if new_status != Status::Paused {
+                self.inner.paused_until = None;
+            }
+            self.inner.retired_at = new_status.retired().then_some(now);
             self.inner.status = new_status;
             true
         } else {
@@ -421,7 +629,7 @@ mod tests {
     use std::time::Duration;
 
     use super::*;
-    use crate::scheduler::db_model::schedule::{Recurring, Schedule};
+    use crate::scheduler::db_model::schedule::{Recurring, RunAt, Schedule};
     use crate::scheduler::db_model::Trigger;
 
     fn create_cron_schedule(
@@ -433,6 +641,14 @@ mod tests {
             timezone: "Europe/London".into(),
             limit: cron_events_limit,
             remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+            start_at: None,
+            end_at: None,
+            jitter_s: None,
+            exclude_calendars: vec![],
+            calendars: vec![],
+            dst_policy: None,
         })
     }
 
@@ -451,11 +667,18 @@ mod tests {
                 http_method: HttpMethod::Get,
                 timeout_s: Duration::from_secs(30),
                 retry: None,
+                success_criteria: None,
             }),
             payload: None,
             status: Status::Scheduled,
             schedule: Some(sched),
             last_ran_at: None,
+            concurrency_policy: Default::default(),
+            upstream_trigger_id: None,
+            paused_until: None,
+            skipped_occurrences: Default::default(),
+            labels: Default::default(),
+            retired_at: None,
         }
     }
 
@@ -487,4 +710,247 @@ mod tests {
         assert_eq!(tick2, tick2_again);
         Ok(())
     }
+
+    #[test]
+    fn skipped_occurrences_are_consumed() -> Result<(), TriggerError> {
+        let cron = create_cron_schedule("0 * * * * *", None);
+        let trigger = create_trigger(cron);
+        let trigger_id = trigger.id.clone();
+        let upcoming = trigger.upcoming_occurrences(trigger.created_at, 3);
+        let mut map = ActiveTriggerMap::default();
+        map.add_or_update(trigger, false)?;
+        map.clear_db_flush();
+
+        map.skip(&trigger_id, vec![upcoming[0]])?;
+        assert!(map.awaiting_db_flush().contains(&trigger_id));
+        let trigger = map.get(&trigger_id).unwrap();
+        assert_eq!(vec![upcoming[0]], trigger.skipped_occurrences.0);
+        // Skipped occurrences are not estimated to run.
+        assert_eq!(&upcoming[1..], &trigger.estimated_future_runs()[..2]);
+
+        // The occurrence is forgotten once the trigger moves past it.
+        let mut temporal_states = map.build_temporal_state();
+        let tick = temporal_states.pop().unwrap().0.next_tick;
+        assert_eq!(upcoming[0], tick);
+        map.clear_db_flush();
+        map.advance(&trigger_id);
+        assert!(map.awaiting_db_flush().contains(&trigger_id));
+        assert!(map
+            .get(&trigger_id)
+            .unwrap()
+            .skipped_occurrences
+            .0
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn skipped_occurrences_keep_remaining_runs() -> Result<(), TriggerError> {
+        let cron = create_cron_schedule("0 * * * * *", Some(3));
+        let trigger = create_trigger(cron);
+        let trigger_id = trigger.id.clone();
+        let upcoming = trigger.upcoming_occurrences(trigger.created_at, 3);
+        let mut map = ActiveTriggerMap::default();
+        map.add_or_update(trigger, false)?;
+        map.skip(&trigger_id, vec![upcoming[0]])?;
+
+        let remaining = |map: &ActiveTriggerMap| {
+            match map.get(&trigger_id).unwrap().schedule {
+                | Some(Schedule::Recurring(ref recurring)) => {
+                    recurring.remaining
+                }
+                | _ => None,
+            }
+        };
+        let mut temporal_states = map.build_temporal_state();
+        assert_eq!(upcoming[0], temporal_states.pop().unwrap().0.next_tick);
+        // Moving past the skipped occurrence doesn't consume a run.
+        assert_eq!(
+            Some(upcoming[1]),
+            map.advance(&trigger_id).map(|t| t.with_timezone(&Utc))
+        );
+        assert_eq!(Some(3), remaining(&map));
+        // But moving past an occurrence that ran does.
+        assert_eq!(
+            Some(upcoming[2]),
+            map.advance(&trigger_id).map(|t| t.with_timezone(&Utc))
+        );
+        assert_eq!(Some(2), remaining(&map));
+        Ok(())
+    }
+
+    #[test]
+    fn snoozed_trigger_resumes() -> Result<(), TriggerError> {
+        let cron = create_cron_schedule("0 * * * * *", None);
+        let trigger = create_trigger(cron);
+        let trigger_id = trigger.id.clone();
+        let mut map = ActiveTriggerMap::default();
+        map.add_or_update(trigger, false)?;
+        map.clear_db_flush();
+
+        let now = Utc::now();
+        let until = now + chrono::Duration::minutes(10);
+        map.pause(&trigger_id, Some(until))?;
+        assert_eq!(Status::Paused, map.get(&trigger_id).unwrap().status);
+        assert_eq!(Some(until), map.get(&trigger_id).unwrap().paused_until);
+        assert!(map.awaiting_db_flush().contains(&trigger_id));
+
+        // Not due yet.
+        map.resume_snoozed(now);
+        assert_eq!(Status::Paused, map.get(&trigger_id).unwrap().status);
+
+        map.resume_snoozed(until);
+        let trigger = map.get(&trigger_id).unwrap();
+        assert_eq!(Status::Scheduled, trigger.status);
+        assert_eq!(None, trigger.paused_until);
+
+        // A manual resume also clears the snooze.
+        map.pause(&trigger_id, Some(until))?;
+        map.resume(&trigger_id)?;
+        assert_eq!(None, map.get(&trigger_id).unwrap().paused_until);
+        map.pause(&trigger_id, None)?;
+        map.resume_snoozed(until);
+        assert_eq!(Status::Paused, map.get(&trigger_id).unwrap().status);
+        Ok(())
+    }
+
+    #[test]
+    fn timepoints_are_updated_in_place() -> Result<(), TriggerError> {
+        let now = Utc::now();
+        let at = |h| (now + chrono::Duration::hours(h)).fixed_offset();
+        let trigger = create_trigger(Schedule::RunAt(RunAt {
+            timepoints: vec![at(1), at(3)],
+            remaining: None,
+            misfire_policy: None,
+            max_missed_runs: None,
+        }));
+        let trigger_id = trigger.id.clone();
+        let mut map = ActiveTriggerMap::default();
+        map.add_or_update(trigger, false)?;
+        let mut temporal_states = map.build_temporal_state();
+        assert_eq!(at(1), temporal_states.pop().unwrap().0.next_tick);
+        map.clear_db_flush();
+
+        // Existing timepoints aren't added twice, and unknown ones are
+        // ignored on removal.
+        map.update_timepoints(&trigger_id, &[at(2), at(3)], &[at(1), at(5)])?;
+        assert!(map.is_dirty());
+        assert!(map.awaiting_db_flush().contains(&trigger_id));
+        let trigger = map.get(&trigger_id).unwrap();
+        let Some(Schedule::RunAt(run_at)) = trigger.schedule.clone() else {
+            panic!("Expected a RunAt schedule");
+        };
+        assert_eq!(vec![at(2), at(3)], run_at.timepoints);
+        assert_eq!(Some(2), run_at.remaining);
+        assert_eq!(
+            vec![at(2).with_timezone(&Utc), at(3).with_timezone(&Utc)],
+            trigger.estimated_future_runs()
+        );
+        let mut temporal_states = map.build_temporal_state();
+        assert_eq!(at(2), temporal_states.pop().unwrap().0.next_tick);
+
+        // Going over the limit leaves the trigger untouched.
+        let too_many: Vec<_> = (10..5010).map(at).collect();
+        assert!(matches!(
+            map.update_timepoints(&trigger_id, &too_many, &[]),
+            Err(TriggerError::TooManyTimepoints(_))
+        ));
+        assert_eq!(
+            Some(Schedule::RunAt(run_at)),
+            map.get(&trigger_id).unwrap().schedule
+        );
+
+        let cron = create_trigger(create_cron_schedule("0 * * * * *", None));
+        let cron_id = cron.id.clone();
+        map.add_or_update(cron, false)?;
+        assert!(matches!(
+            map.update_timepoints(&cron_id, &[at(2)], &[]),
+            Err(TriggerError::NotRunAt)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn jitter_longer_than_the_period() -> Result<(), TriggerError> {
+        use chrono::TimeZone;
+
+        let start = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 30).unwrap();
+        let mut map = ActiveTriggerMap::new(Arc::new(ManualClock::new(start)));
+
+        // Every minute, delayed by up to ten minutes. Pick a trigger whose
+        // jitter spans more than one period.
+        let mut cron = create_cron_schedule("0 * * * * *", None);
+        if let Schedule::Recurring(ref mut recurring) = cron {
+            recurring.jitter_s = Some(600);
+        }
+        let mut trigger = loop {
+            let trigger = create_trigger(cron.clone());
+            if cron.jitter(&trigger.id) > chrono::Duration::minutes(1) {
+                break trigger;
+            }
+        };
+        let jitter = cron.jitter(&trigger.id);
+        let tick = Utc.with_ymd_and_hms(2023, 1, 1, 1, 0, 0).unwrap();
+        trigger.created_at = start;
+        trigger.last_ran_at = Some(tick + jitter);
+        let trigger_id = trigger.id.clone();
+
+        // The next run is the tick right after the last one, not the first
+        // tick after the jittered run time.
+        let expected = tick + chrono::Duration::minutes(1) + jitter;
+        assert_eq!(expected, trigger.estimated_future_runs()[0]);
+        map.add_or_update(trigger, false)?;
+        let next_tick = map.build_temporal_state().pop().unwrap().0.next_tick;
+        assert_eq!(expected, next_tick);
+        assert_eq!(
+            expected + chrono::Duration::minutes(1),
+            map.advance(&trigger_id).unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ticks_follow_the_clock() -> Result<(), TriggerError> {
+        use chrono::TimeZone;
+
+        let london = chrono_tz::Europe::London;
+        // Clocks in London go forward at 1am on the 26th of March 2023.
+        let start = Utc.with_ymd_and_hms(2023, 3, 26, 0, 30, 0).unwrap();
+        let clock = Arc::new(ManualClock::new(start));
+        let mut map = ActiveTriggerMap::new(clock.clone());
+
+        // 9am every day, the trigger missed the last three days.
+        let mut trigger =
+            create_trigger(create_cron_schedule("0 0 9 * * *", None));
+        trigger.created_at = start - chrono::Duration::days(3);
+        let trigger_id = trigger.id.clone();
+
+        // Fast forwarding ignores the missed ticks and only considers the
+        // ticks after the clock's time, across the DST transition.
+        map.add_or_update(trigger.clone(), true)?;
+        let tick = map.build_temporal_state().pop().unwrap().0.next_tick;
+        assert_eq!(
+            london.with_ymd_and_hms(2023, 3, 26, 9, 0, 0).unwrap(),
+            tick
+        );
+        assert_eq!(Utc.with_ymd_and_hms(2023, 3, 26, 8, 0, 0).unwrap(), tick);
+        assert_eq!(
+            london.with_ymd_and_hms(2023, 3, 27, 9, 0, 0).unwrap(),
+            map.advance(&trigger_id).unwrap()
+        );
+
+        // The wall clock jumps backwards by two days.
+        clock.advance(chrono::Duration::days(-2));
+        map.add_or_update(trigger, true)?;
+        let tick = map.build_temporal_state().pop().unwrap().0.next_tick;
+        assert_eq!(
+            london.with_ymd_and_hms(2023, 3, 24, 9, 0, 0).unwrap(),
+            tick
+        );
+
+        // Retiring a trigger is timestamped by the clock as well.
+        map.cancel(&trigger_id)?;
+        assert_eq!(Some(clock.now()), map.get(&trigger_id).unwrap().retired_at);
+        Ok(())
+    }
 }
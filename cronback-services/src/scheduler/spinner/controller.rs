@@ -1,30 +1,91 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 
 use async_recursion::async_recursion;
-use chrono::Utc;
-use lib::clients::ScopedDispatcherSvcClient;
+use chrono::{DateTime, FixedOffset, Utc};
+use lib::clients::{ScopedDispatcherSvcClient, ScopedMetadataSvcClient};
 use lib::prelude::*;
 use lib::service::ServiceContext;
-use lib::GrpcClientProvider;
+use lib::{GrpcClientFactory, GrpcClientProvider};
+use metrics::gauge;
 use proto::common::request_precondition::PreconditionType;
 use proto::common::{PaginationIn, UpsertEffect};
 use proto::events::TriggerMeta;
-use proto::runs::Run;
+use proto::metadata_svc::{
+    GetCalendarsRequest,
+    GetShardAssignmentsRequest,
+    GetTriggerRetentionRequest,
+};
+use proto::runs::{Run, RunStatus};
 use proto::scheduler_svc::{UpsertTriggerRequest, UpsertTriggerResponse};
 use tracing::{debug, error, info, trace, warn};
 
 use super::active_triggers::{ActiveTrigger, ActiveTriggerMap};
-use super::dispatch::{dispatch, DispatchMode};
+use super::dispatch::{dispatch, DispatchJob, DispatchMode};
 use super::name_cache::NameCache;
 use super::spinner::{Spinner, SpinnerHandle};
-use crate::scheduler::db_model::triggers::Status;
-use crate::scheduler::db_model::Trigger;
+use crate::scheduler::db_model::schedule::{
+    Calendar,
+    Occurrence,
+    Schedule,
+    MAX_TIMEPOINTS,
+};
+use crate::scheduler::db_model::triggers::{
+    ConcurrencyPolicy,
+    LabelSelector,
+    Labels,
+    Status,
+};
+use crate::scheduler::db_model::{OutboxEntry, Trigger, TriggerRevision};
 use crate::scheduler::error::TriggerError;
+use crate::scheduler::outbox_store::OutboxStore;
 use crate::scheduler::trigger_store::{TriggerStore, TriggerStoreError};
 use crate::scheduler::SchedulerService;
 
+// How many outbox dispatches are relayed per round.
+const OUTBOX_RELAY_BATCH: u64 = 100;
+// How many retired triggers are looked at per round of garbage collection.
+const RETIRED_TRIGGERS_BATCH: u64 = 500;
+// How many triggers a bulk update handles per hold of the active triggers
+// write lock, so that the spinner isn't starved.
+const BULK_UPDATE_BATCH: usize = 100;
+
+/// An action applied to all the triggers matching a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkAction {
+    Pause,
+    Resume,
+    Cancel,
+    Delete,
+}
+
+impl BulkAction {
+    /// The status of the triggers after the action, `None` if they are
+    /// deleted.
+    fn target_status(&self) -> Option<Status> {
+        match self {
+            | BulkAction::Pause => Some(Status::Paused),
+            | BulkAction::Resume => Some(Status::Scheduled),
+            | BulkAction::Cancel => Some(Status::Cancelled),
+            | BulkAction::Delete => None,
+        }
+    }
+}
+
+/// The outcome of a bulk action on a single trigger, its new status on
+/// success (`None` if it was deleted).
+pub type BulkTriggerResult = (String, Result<Option<Status>, TriggerError>);
+
+/// Everything a cell keeps about the projects of a set of shards, handed
+/// over to another cell when the shards move.
+#[derive(Debug, Default)]
+pub struct ShardData {
+    pub triggers: Vec<Trigger>,
+    pub revisions: Vec<TriggerRevision>,
+    pub outbox: Vec<OutboxEntry>,
+}
+
 ///  SpinnerController is the gateway to the scheduling and dispatch thread
 ///  (spinner) It's designed to be easily shared with inner mutability and
 ///  minimal locking to reduce contention.
@@ -51,20 +112,30 @@ use crate::scheduler::SchedulerService;
 ///  Needs to take care of:
 ///  - Compaction/eviction: remove expired triggers.
 ///  - Flushes active triggers map into the database periodically.
+///  - Deletes retired triggers after the retention period of their project.
 pub(crate) struct SpinnerController {
     context: ServiceContext<SchedulerService>,
     triggers: Arc<RwLock<ActiveTriggerMap>>,
     spinner: Mutex<Option<SpinnerHandle>>,
     store: TriggerStore,
+    outbox: OutboxStore,
     name_cache: Arc<NameCache<TriggerStoreError>>,
     dispatcher_clients: Arc<GrpcClientProvider<ScopedDispatcherSvcClient>>,
+    metadata_clients: Arc<GrpcClientProvider<ScopedMetadataSvcClient>>,
+    // Where the leader of the cell is, as last seen by a standby replica.
+    cell_leader: Mutex<Option<String>>,
+    // Shards that are being moved, or have been moved, to another cell. Moves
+    // are recorded by the metadata service and loaded on start.
+    moved_shards: RwLock<HashSet<Shard>>,
 }
 
 impl SpinnerController {
     pub fn new(
         context: ServiceContext<SchedulerService>,
         store: TriggerStore,
+        outbox: OutboxStore,
         dispatcher_clients: Arc<GrpcClientProvider<ScopedDispatcherSvcClient>>,
+        metadata_clients: Arc<GrpcClientProvider<ScopedMetadataSvcClient>>,
     ) -> Self {
         let name_cacher_fetcher = {
             let store = store.clone();
@@ -78,17 +149,26 @@ impl SpinnerController {
             })
         };
 
+        let triggers =
+            Arc::new(RwLock::new(ActiveTriggerMap::new(context.clock())));
         Self {
             context,
-            triggers: Arc::default(),
+            triggers,
             spinner: Mutex::default(),
             name_cache: Arc::new(NameCache::new(name_cacher_fetcher)),
             store,
+            outbox,
             dispatcher_clients,
+            metadata_clients,
+            cell_leader: Mutex::default(),
+            moved_shards: RwLock::default(),
         }
     }
 
     pub async fn start(&self) -> Result<(), TriggerError> {
+        // The triggers of shards that moved away might still be in the
+        // database if we stopped in the middle of a move.
+        self.load_moved_shards().await?;
         {
             let mut spinner = self.spinner.lock().unwrap();
             if spinner.is_some() {
@@ -100,6 +180,7 @@ impl SpinnerController {
                     self.context.clone(),
                     self.triggers.clone(),
                     self.dispatcher_clients.clone(),
+                    self.outbox.clone(),
                 )
                 .start(),
             );
@@ -108,6 +189,71 @@ impl SpinnerController {
         self.load_triggers_from_database().await
     }
 
+    /// Whether this replica is running the spinner, a standby replica must
+    /// not change triggers since the leader would overwrite them.
+    pub fn is_leader(&self) -> bool {
+        self.spinner.lock().unwrap().is_some()
+    }
+
+    pub fn ensure_leader(&self) -> Result<(), TriggerError> {
+        if self.is_leader() {
+            return Ok(());
+        }
+        let leader = self.cell_leader.lock().unwrap().clone();
+        Err(TriggerError::Standby(
+            leader.unwrap_or_else(|| "unknown".to_string()),
+        ))
+    }
+
+    /// Whether this replica can serve requests of a project, the project's
+    /// shard might have been moved to another cell.
+    pub fn ensure_serving(
+        &self,
+        project_id: &ValidShardedId<ProjectId>,
+    ) -> Result<(), TriggerError> {
+        self.ensure_leader()?;
+        self.ensure_readable(project_id)
+    }
+
+    /// Whether this replica can serve reads of a project. Standby replicas
+    /// read the triggers from the database they share with the leader, which
+    /// can lag behind the leader until its next checkpoint.
+    pub fn ensure_readable(
+        &self,
+        project_id: &ValidShardedId<ProjectId>,
+    ) -> Result<(), TriggerError> {
+        let shard = project_id.shard();
+        if self.moved_shards.read().unwrap().contains(&shard) {
+            return Err(TriggerError::ShardMoved(shard));
+        }
+        Ok(())
+    }
+
+    pub fn cell_leader(&self) -> Option<String> {
+        self.cell_leader.lock().unwrap().clone()
+    }
+
+    pub fn set_cell_leader(&self, address: Option<String>) {
+        *self.cell_leader.lock().unwrap() = address;
+    }
+
+    /// Stops the spinner after losing the cell lease. Unlike `shutdown`, this
+    /// doesn't checkpoint since the new leader might have already loaded
+    /// the triggers. The new leader dispatches the ticks since our last
+    /// checkpoint again, which doesn't create more runs since the run ids of
+    /// ticks are derived from the ticks (see `DispatchJob::for_tick`).
+    pub fn step_down(&self) {
+        {
+            let mut spinner = self.spinner.lock().unwrap();
+            let Some(spinner) = spinner.take() else {
+                return;
+            };
+            spinner.shutdown();
+        }
+        let mut triggers = self.triggers.write().unwrap();
+        triggers.clear();
+    }
+
     /// Checkpointing flushes the dirty active triggers to the database
     pub async fn perform_checkpoint(&self) {
         trace!("Attempting to checkpoint triggers");
@@ -185,6 +331,315 @@ impl SpinnerController {
         }
     }
 
+    /// Retries the dispatches that are due in the outbox. Dispatches that keep
+    /// failing are retried with an exponential backoff until they succeed or
+    /// fail with a non-retryable error.
+    pub async fn relay_outbox(&self) {
+        // Standby replicas share the database with the leader, only the
+        // leader relays to avoid dispatching the same run concurrently.
+        if !self.is_leader() {
+            return;
+        }
+        let config = self.context.service_config();
+        let now = self.context.clock().now();
+        let due = match self.outbox.get_due(now, OUTBOX_RELAY_BATCH).await {
+            | Ok(due) => due,
+            | Err(e) => {
+                error!("Failed to fetch the due outbox dispatches: {}", e);
+                return;
+            }
+        };
+        if !due.is_empty() {
+            info!("Relaying {} dispatches from the outbox", due.len());
+        }
+
+        for entry in due {
+            // The dispatches of drained shards are relayed by the cell that
+            // adopts them.
+            if self
+                .moved_shards
+                .read()
+                .unwrap()
+                .contains(&entry.project_id.shard())
+            {
+                continue;
+            }
+            let context =
+                RequestContext::new(RequestId::new(), entry.project_id.clone());
+            let res = match DispatchJob::from_outbox(
+                context,
+                &entry,
+                self.dispatcher_clients.clone(),
+            ) {
+                | Ok(mut job) => job.run().await,
+                | Err(e) => {
+                    error!(
+                        run_id = %entry.run_id,
+                        "Dropping undecodable dispatch from the outbox: {}", e
+                    );
+                    self.remove_from_outbox(&entry.run_id).await;
+                    continue;
+                }
+            };
+
+            match res {
+                | Ok(_) => {
+                    info!(
+                        run_id = %entry.run_id,
+                        trigger_id = %entry.trigger_id,
+                        "Dispatched run from the outbox after {} attempts",
+                        entry.attempts
+                    );
+                    self.remove_from_outbox(&entry.run_id).await;
+                }
+                | Err(e) if e.is_retryable() => {
+                    let delay = outbox_retry_delay(
+                        entry.attempts,
+                        config.outbox_retry_delay_s,
+                        config.outbox_retry_max_delay_s,
+                    );
+                    warn!(
+                        run_id = %entry.run_id,
+                        trigger_id = %entry.trigger_id,
+                        "Failed to dispatch run from the outbox, retrying in \
+                         {}s: {}",
+                        delay.num_seconds(),
+                        e
+                    );
+                    if let Err(e) = self
+                        .outbox
+                        .reschedule(&entry.run_id, now + delay, e.to_string())
+                        .await
+                    {
+                        error!(
+                            run_id = %entry.run_id,
+                            "Failed to reschedule outbox dispatch: {}", e
+                        );
+                    }
+                }
+                | Err(e) => {
+                    error!(
+                        run_id = %entry.run_id,
+                        trigger_id = %entry.trigger_id,
+                        "Dropping dispatch from the outbox after a \
+                         non-retryable error: {}",
+                        e
+                    );
+                    self.remove_from_outbox(&entry.run_id).await;
+                }
+            }
+        }
+
+        match self.outbox.stats().await {
+            | Ok((depth, oldest)) => {
+                let age = oldest
+                    .map(|oldest| (now - oldest).num_seconds().max(0))
+                    .unwrap_or_default();
+                gauge!("scheduler.dispatch_outbox_depth", depth as f64);
+                gauge!("scheduler.dispatch_outbox_age_seconds", age as f64);
+            }
+            | Err(e) => {
+                error!("Failed to compute the outbox stats: {}", e);
+            }
+        }
+    }
+
+    /// Deletes the cancelled and expired triggers that have been retired for
+    /// longer than the retention period of their project.
+    pub async fn collect_retired_triggers(&self) {
+        // Standby replicas share the database with the leader, only the
+        // leader deletes triggers.
+        if !self.is_leader() {
+            return;
+        }
+        let now = self.context.clock().now();
+        // The retention of each project is only fetched once per collection,
+        // `None` if it couldn't be fetched.
+        let mut retentions: HashMap<ValidShardedId<ProjectId>, Option<u64>> =
+            HashMap::new();
+        let mut after: Option<TriggerId> = None;
+        loop {
+            let retired = match self
+                .store
+                .get_retired_triggers(after.as_ref(), RETIRED_TRIGGERS_BATCH)
+                .await
+            {
+                | Ok(retired) => retired,
+                | Err(e) => {
+                    error!("Failed to fetch the retired triggers: {}", e);
+                    return;
+                }
+            };
+            let Some(last) = retired.last() else {
+                return;
+            };
+            after = Some(last.id.clone());
+            let is_last_batch = (retired.len() as u64) < RETIRED_TRIGGERS_BATCH;
+
+            let mut by_project: HashMap<
+                ValidShardedId<ProjectId>,
+                Vec<Trigger>,
+            > = HashMap::new();
+            for trigger in retired {
+                by_project
+                    .entry(trigger.project_id.clone())
+                    .or_default()
+                    .push(trigger);
+            }
+            for (project_id, retired) in by_project {
+                // Triggers of moved shards belong to another cell now.
+                if self.ensure_serving(&project_id).is_err() {
+                    continue;
+                }
+                let retention_s = match retentions.get(&project_id) {
+                    | Some(retention_s) => *retention_s,
+                    | None => {
+                        let retention_s =
+                            self.fetch_trigger_retention(&project_id).await;
+                        retentions.insert(project_id.clone(), retention_s);
+                        retention_s
+                    }
+                };
+                if let Some(retention_s) = retention_s {
+                    self.delete_retired_triggers(
+                        &project_id,
+                        retired,
+                        retention_s,
+                        now,
+                    )
+                    .await;
+                }
+            }
+
+            if is_last_batch {
+                return;
+            }
+        }
+    }
+
+    /// The retention period of the project's retired triggers, `None` if it
+    /// couldn't be fetched.
+    async fn fetch_trigger_retention(
+        &self,
+        project_id: &ValidShardedId<ProjectId>,
+    ) -> Option<u64> {
+        match self.get_trigger_retention(project_id).await {
+            | Ok(retention_s) => {
+                Some(retention_s.unwrap_or(
+                    self.context.service_config().trigger_retention_s,
+                ))
+            }
+            | Err(e) => {
+                warn!(
+                    project_id = %project_id,
+                    "Not collecting retired triggers, failed to fetch the \
+                     project's retention: {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    async fn delete_retired_triggers(
+        &self,
+        project_id: &ValidShardedId<ProjectId>,
+        retired: Vec<Trigger>,
+        retention_s: u64,
+        now: DateTime<Utc>,
+    ) {
+        let cutoff = now - chrono::Duration::seconds(retention_s as i64);
+        let to_delete: Vec<Trigger> = retired
+            .into_iter()
+            .filter(
+                |t| matches!(t.retired_since(), Some(since) if since <= cutoff),
+            )
+            .collect();
+        if to_delete.is_empty() {
+            return;
+        }
+
+        let triggers = self.triggers.clone();
+        let to_delete = tokio::task::spawn_blocking(move || {
+            let mut w = triggers.write().unwrap();
+            // The trigger might have been updated back to life since we
+            // loaded it from the database.
+            let to_delete: Vec<Trigger> = to_delete
+                .into_iter()
+                .filter(|t| {
+                    match w.get(&t.id) {
+                        | Some(active) => active.status.retired(),
+                        | None => true,
+                    }
+                })
+                .collect();
+            for trigger in &to_delete {
+                w.remove(&trigger.id);
+            }
+            to_delete
+        })
+        .await;
+        let to_delete = match to_delete {
+            | Ok(to_delete) => to_delete,
+            | Err(e) => {
+                error!("Failed to evict retired triggers: {}", e);
+                return;
+            }
+        };
+
+        info!(
+            project_id = %project_id,
+            "Deleting {} triggers retired for more than {}s",
+            to_delete.len(),
+            retention_s
+        );
+        for trigger in to_delete {
+            if let Err(e) =
+                self.store.delete_trigger(project_id, &trigger.id).await
+            {
+                error!(
+                    trigger_id = %trigger.id,
+                    "Failed to delete retired trigger: {}", e
+                );
+                continue;
+            }
+            self.name_cache.remove(project_id, &trigger.name);
+            e!(
+                project_id = project_id.clone(),
+                TriggerDeleted {
+                    meta: Some(trigger.meta()),
+                }
+            );
+        }
+    }
+
+    /// The retention period of the project's retired triggers, `None` if the
+    /// project uses the default.
+    async fn get_trigger_retention(
+        &self,
+        project_id: &ValidShardedId<ProjectId>,
+    ) -> Result<Option<u64>, TriggerError> {
+        let mut metadata = self
+            .metadata_clients
+            .get_client(&RequestId::new(), project_id)
+            .await
+            .map_err(|e| TriggerError::Metadata(e.to_string()))?;
+        let resp = metadata
+            .get_trigger_retention(GetTriggerRetentionRequest {
+                id: Some(project_id.clone().into()),
+            })
+            .await
+            .map_err(|e| TriggerError::Metadata(e.to_string()))?
+            .into_inner();
+        Ok(resp.retention_s)
+    }
+
+    async fn remove_from_outbox(&self, run_id: &RunId) {
+        if let Err(e) = self.outbox.remove(run_id).await {
+            error!(run_id = %run_id, "Failed to remove outbox dispatch: {}", e);
+        }
+    }
+
     pub async fn get_trigger_id(
         &self,
         project_id: &ProjectId,
@@ -194,88 +649,153 @@ impl SpinnerController {
         maybe_id.ok_or_else(|| TriggerError::NotFound(name.to_string()))
     }
 
-    #[tracing::instrument(skip_all)]
-    pub async fn update_trigger(
+    #[tracing::instrument(skip_all, fields(trigger_name = %name, project_id = %context.project_id))]
+    pub async fn list_trigger_revisions(
         &self,
-        _existing_trigger: Trigger,
-        _upsert_request: UpsertTriggerRequest,
-    ) -> Result<UpsertTriggerResponse, TriggerError> {
-        todo!()
-        // TODO: Take snapshots of previous triggers and store for auditing.
-        // if upsert_request.fail_if_exists {
-        //     return Err(TriggerError::UpdateNotAllowed(existing_trigger.id));
-        // }
-        //
-        // // Is the updated trigger a "Scheduled" trigger?
-        // // Do we have the existing trigger in memory?
-        // let trigger_id = existing_trigger.id.clone();
-        // let triggers_map = self.triggers.clone();
-        // // Why clone? because we might need it if the trigger was not in the
-        // // active map.
-        // let install_cloned = upsert_request.clone();
-        // let mut updated_trigger = tokio::task::spawn_blocking(move || {
-        //     let mut w = triggers_map.write().unwrap();
-        //     let Some(alive_trigger) = w.get(&trigger_id) else {
-        //         return None;
-        //     };
-        //     // are we attempting to install a `scheduled` replacement, or is
-        //     // the replacement an on_demand one?
-        //     let mut updated_trigger = alive_trigger.clone();
-        //     updated_trigger.update(
-        //         install_cloned.name,
-        //         install_cloned.description,
-        //         install_cloned.reference,
-        //         install_cloned.payload.map(Into::into),
-        //         install_cloned.schedule.map(Into::into),
-        //         install_cloned.action.unwrap().into(),
-        //     );
-        //     if updated_trigger.schedule.is_some() {
-        //         Some(w.add_or_update(
-        //             updated_trigger,
-        //             // We fast forward on update to avoid triggering old
-        //             // events if the new trigger
-        //             // has any old timestamps.
-        //             /* fast_forward = */
-        //             true,
-        //         ))
-        //     } else {
-        //         // If the old is scheduled and the new is not,
-        //         // we are in trouble, so we remove it from
-        //         // the map.
-        //         w.pop_trigger(&trigger_id);
-        //         Some(Ok(updated_trigger))
-        //     }
-        // })
-        // .await?
-        // .transpose()?;
-        //
-        // // if we don't have an updated_trigger, it means that we can merge
-        // with // the `existing_trigger` to get the updated one.
-        // if updated_trigger.is_none() {
-        //     existing_trigger.update(
-        //         install_trigger.name,
-        //         install_trigger.description,
-        //         install_trigger.reference,
-        //         install_trigger.payload.map(|p| p.into()),
-        //         install_trigger.schedule.map(|s| s.into()),
-        //         install_trigger.action.unwrap().into(),
-        //     );
-        //     updated_trigger = Some(existing_trigger);
-        // }
-        //
-        // // Guaranteed to be set at this point.
-        // let updated_trigger = updated_trigger.unwrap();
-        //
-        // // We have the updated trigger, let's save it to the database.
-        // self.store.update_trigger(updated_trigger.clone()).await?;
-        //
-        // //
-        // // RESPOND
-        // let reply = UpsertTriggerResponse {
-        //     trigger: Some(updated_trigger.into()),
-        //     already_existed: true,
-        // };
-        // Ok(reply)
+        context: RequestContext,
+        name: String,
+        pagination: PaginationIn,
+    ) -> Result<PaginatedResponse<TriggerRevision>, TriggerError> {
+        let trigger_id =
+            self.get_trigger_id(&context.project_id, &name).await?;
+        let revisions = self
+            .store
+            .get_revisions(&context.project_id, &trigger_id, &pagination)
+            .await?;
+        Ok(revisions)
+    }
+
+    /// Updates the trigger to the definition it had at `revision`, which is
+    /// recorded as a new revision.
+    #[tracing::instrument(skip_all, fields(trigger_name = %name, project_id = %context.project_id))]
+    pub async fn restore_trigger_revision(
+        &self,
+        context: RequestContext,
+        name: String,
+        revision: u64,
+        author: Option<String>,
+    ) -> Result<Trigger, TriggerError> {
+        let existing_trigger = self
+            .store
+            .get_trigger_by_name(&context.project_id, &name)
+            .await?
+            .ok_or_else(|| TriggerError::NotFound(name.clone()))?;
+        let snapshot = self
+            .store
+            .get_revision(
+                &context.project_id,
+                &existing_trigger.id,
+                revision as i64,
+            )
+            .await?
+            .ok_or_else(|| TriggerError::RevisionNotFound(name, revision))?;
+
+        let mut trigger: proto::triggers::Trigger = snapshot.trigger.0.into();
+        reset_remaining(&mut trigger, self.context.clock().now());
+        self.update_trigger(&context, existing_trigger, trigger, author)
+            .await
+    }
+
+    /// Updates the definition of an existing trigger and records it as a new
+    /// revision. The trigger keeps its runtime state (e.g. `last_ran_at`), and
+    /// remains paused if it was.
+    #[tracing::instrument(skip_all, fields(trigger_id = %existing_trigger.id))]
+    async fn update_trigger(
+        &self,
+        context: &RequestContext,
+        existing_trigger: Trigger,
+        trigger: proto::triggers::Trigger,
+        author: Option<String>,
+    ) -> Result<Trigger, TriggerError> {
+        let project_id = &context.project_id;
+        let trigger_id = existing_trigger.id.clone();
+        let name = existing_trigger.name.clone();
+
+        let mut schedule: Option<Schedule> = trigger.schedule.map(Into::into);
+        if let Some(schedule) = schedule.as_mut() {
+            self.resolve_calendars(context, schedule).await?;
+        }
+        let upstream_trigger_id =
+            match schedule.as_ref().and_then(Schedule::on_completion_of) {
+                | Some(on_completion_of) => {
+                    Some(
+                        self.resolve_upstream_trigger(
+                            project_id,
+                            &trigger_id,
+                            &name,
+                            &on_completion_of.trigger,
+                        )
+                        .await?,
+                    )
+                }
+                | None => None,
+            };
+        let description = trigger.description;
+        let payload = trigger.payload.map(Into::into);
+        let action = trigger.action.unwrap().into();
+        let concurrency_policy = trigger
+            .concurrency_policy
+            .map(ConcurrencyPolicy::from)
+            .unwrap_or_default();
+        let labels = Labels(trigger.labels.into_iter().collect());
+
+        let previous_trigger = existing_trigger.clone();
+        let now = self.context.clock().now();
+        let triggers = self.triggers.clone();
+        let cloned_trigger_id = trigger_id.clone();
+        let updated_trigger = tokio::task::spawn_blocking(move || {
+            let r = triggers.read().unwrap();
+            // The active map has the most recent runtime state of the trigger.
+            let mut updated = r
+                .get(&cloned_trigger_id)
+                .cloned()
+                .unwrap_or(existing_trigger);
+            let was_paused = updated.status == Status::Paused;
+            updated.update(name, description, payload, schedule, action, now);
+            updated.concurrency_policy = concurrency_policy;
+            updated.labels = labels;
+            updated.upstream_trigger_id = upstream_trigger_id;
+            if was_paused && updated.status == Status::Scheduled {
+                updated.status = Status::Paused;
+            } else {
+                updated.paused_until = None;
+            }
+            if is_spinning(&updated) {
+                // A schedule the spinner can't run must fail the update
+                // before it gets persisted.
+                r.check(&updated, /* fast_forward = */ true)?;
+            }
+            Ok::<_, TriggerError>(updated)
+        })
+        .await??;
+
+        self.store
+            .update_trigger_with_revision(
+                updated_trigger.clone(),
+                &previous_trigger,
+                author,
+            )
+            .await?;
+
+        // The spinner only picks up the update once it's persisted.
+        let triggers = self.triggers.clone();
+        let cloned_trigger = updated_trigger.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut w = triggers.write().unwrap();
+            if is_spinning(&cloned_trigger) {
+                // We fast forward on update to avoid triggering old events if
+                // the new schedule has any past timestamps.
+                w.add_or_update(
+                    cloned_trigger,
+                    /* fast_forward = */ true,
+                )?;
+            } else {
+                w.pop_trigger(&trigger_id);
+            }
+            Ok::<_, TriggerError>(())
+        })
+        .await??;
+        Ok(updated_trigger)
     }
 
     #[async_recursion]
@@ -287,29 +807,11 @@ impl SpinnerController {
         // We assume that trigger will always be set.
         let project_id = &context.project_id;
         let mut trigger = upsert_request.trigger.clone().unwrap();
-
-        // Reset remaining if it was set.
-        // TODO: When updating, allow the user to express their intent to
-        // whether they want to reset the remaining or not.
-        if let Some(schedule) = trigger.schedule.as_mut() {
-            // the inner .schedule must be set at this point.
-            // The spinner will update `remaining` to the accurate value as soon
-            // as it runs.
-            let schedule = schedule.schedule.as_mut().unwrap();
-            match schedule {
-                | proto::triggers::schedule::Schedule::Recurring(r)
-                    if r.limit.is_some() =>
-                {
-                    r.remaining = r.limit;
-                }
-                | proto::triggers::schedule::Schedule::Recurring(r) => {
-                    r.remaining = None;
-                }
-                | proto::triggers::schedule::Schedule::RunAt(r) => {
-                    r.remaining = Some(r.timepoints.len() as u64);
-                }
-            };
+        if trigger.name.is_empty() {
+            trigger.name =
+                upsert_request.trigger_name.clone().unwrap_or_default();
         }
+        reset_remaining(&mut trigger, self.context.clock().now());
 
         let request_precondition = upsert_request.precondition.get_or_default();
         // ** Are we installing new or updating an existing trigger? **
@@ -330,36 +832,84 @@ impl SpinnerController {
             return Err(TriggerError::AlreadyExists(trigger_name.to_string()));
         } else if let Some(existing_trigger) = existing_trigger {
             // Update
-            return self.update_trigger(existing_trigger, upsert_request).await;
+            let trigger = self
+                .update_trigger(
+                    &context,
+                    existing_trigger,
+                    trigger,
+                    upsert_request.author,
+                )
+                .await?;
+            return Ok(UpsertTriggerResponse {
+                trigger: Some(trigger.into()),
+                effect: UpsertEffect::Modified.into(),
+            });
         }
         // It doesn't exist and we are not updating, so we are installing.
         //
         assert!(existing_trigger.is_none());
 
-        let id = TriggerId::generate(project_id);
+        let id: TriggerId = TriggerId::generate(project_id).into();
+
+        let mut schedule: Option<Schedule> = trigger.schedule.map(Into::into);
+        if let Some(schedule) = schedule.as_mut() {
+            self.resolve_calendars(&context, schedule).await?;
+        }
+        // Dependent triggers don't have ticks of their own, they run when
+        // their upstream trigger completes.
+        let upstream_trigger_id =
+            match schedule.as_ref().and_then(Schedule::on_completion_of) {
+                | Some(on_completion_of) => {
+                    Some(
+                        self.resolve_upstream_trigger(
+                            project_id,
+                            &id,
+                            trigger_name,
+                            &on_completion_of.trigger,
+                        )
+                        .await?,
+                    )
+                }
+                | None => None,
+            };
 
         // We want to keep a copy in case we have to call update later.
         //let copied_request = install_trigger.clone();
-        let is_scheduled = trigger.schedule.is_some();
+        let is_scheduled = schedule.is_some() && upstream_trigger_id.is_none();
         let trigger = Trigger {
-            id: id.into(),
+            id,
             project_id: project_id.clone(),
             name: trigger_name.to_string(),
             description: trigger.description,
-            created_at: Utc::now(),
+            created_at: self.context.clock().now(),
             updated_at: None,
             action: trigger.action.unwrap().into(),
             payload: trigger.payload.map(|p| p.into()),
-            schedule: trigger.schedule.map(|s| s.into()),
+            schedule,
             status: if is_scheduled {
                 Status::Scheduled
             } else {
                 Status::OnDemand
             },
             last_ran_at: None,
+            concurrency_policy: trigger
+                .concurrency_policy
+                .map(ConcurrencyPolicy::from)
+                .unwrap_or_default(),
+            upstream_trigger_id,
+            paused_until: None,
+            skipped_occurrences: Default::default(),
+            labels: Labels(trigger.labels.into_iter().collect()),
+            retired_at: None,
         };
 
-        let store_result = self.store.install_trigger(trigger.clone()).await;
+        let store_result = self
+            .store
+            .install_trigger_with_revision(
+                trigger.clone(),
+                upsert_request.author.clone(),
+            )
+            .await;
 
         match store_result {
             | Ok(_) => { /* success */ }
@@ -459,13 +1009,19 @@ impl SpinnerController {
         &self,
         context: RequestContext,
         statuses: Option<Vec<Status>>,
+        label_selectors: Vec<LabelSelector>,
         pagination: PaginationIn,
     ) -> Result<PaginatedResponse<Trigger>, TriggerError> {
         // Hopefully in the future we will be able to get the compact version
         // directly from database instead of fetching the entire trigger.
         let paginated_triggers = self
             .store
-            .get_triggers_by_project(&context.project_id, pagination, statuses)
+            .get_triggers_by_project(
+                &context.project_id,
+                pagination,
+                statuses,
+                label_selectors,
+            )
             .await?;
 
         let PaginatedResponse { data, pagination } = paginated_triggers;
@@ -519,11 +1075,130 @@ impl SpinnerController {
         Ok(run)
     }
 
+    /// Runs the triggers that depend on `upstream_trigger_id` after one of its
+    /// runs has finished with `status`.
+    #[tracing::instrument(skip_all, fields(trigger_id = %upstream_trigger_id, project_id = %context.project_id))]
+    pub async fn run_dependent_triggers(
+        &self,
+        context: RequestContext,
+        upstream_trigger_id: TriggerId,
+        status: RunStatus,
+    ) -> Result<(), TriggerError> {
+        let dependent_triggers = self
+            .store
+            .get_dependent_triggers(&context.project_id, &upstream_trigger_id)
+            .await?;
+
+        for trigger in dependent_triggers {
+            let fires = matches!(
+                trigger.schedule.as_ref().and_then(Schedule::on_completion_of),
+                Some(on_completion_of) if on_completion_of.fires_on(status)
+            );
+            // Cancelled triggers never run.
+            if !fires || trigger.status != Status::OnDemand {
+                continue;
+            }
+
+            let trigger_id = trigger.id.clone();
+            info!(
+                trigger_id = %trigger_id,
+                "Running trigger on completion of its upstream trigger"
+            );
+            // A failure to dispatch one trigger shouldn't prevent the others
+            // from running.
+            if let Err(e) = dispatch(
+                context.clone(),
+                trigger,
+                self.dispatcher_clients.clone(),
+                DispatchMode::Async,
+            )
+            .await
+            {
+                error!(
+                    trigger_id = %trigger_id,
+                    "Failed to dispatch dependent trigger: {}", e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates the next `count` occurrences of a schedule without
+    /// installing it.
+    #[tracing::instrument(skip_all, fields(project_id = %context.project_id))]
+    pub async fn preview_schedule(
+        &self,
+        context: RequestContext,
+        schedule: Schedule,
+        count: usize,
+    ) -> Result<Vec<Occurrence>, TriggerError> {
+        let mut schedule = schedule;
+        self.resolve_calendars(&context, &mut schedule).await?;
+        schedule.preview(self.context.clock().now(), count)
+    }
+
+    /// Updates the snapshot of the calendar `name` in the active triggers
+    /// that exclude it. A calendar that no longer exists excludes nothing.
+    #[tracing::instrument(skip_all, fields(calendar = %name, project_id = %context.project_id))]
+    pub async fn refresh_calendar(
+        &self,
+        context: RequestContext,
+        name: String,
+    ) -> Result<(), TriggerError> {
+        let calendar = self
+            .fetch_calendars(&context, vec![name.clone()])
+            .await?
+            .pop()
+            .unwrap_or_else(|| {
+                Calendar {
+                    name: name.clone(),
+                    timezone: "Etc/UTC".to_owned(),
+                    dates: vec![],
+                }
+            });
+
+        let triggers = self.triggers.clone();
+        let project_id = context.project_id.clone();
+        let refreshed = tokio::task::spawn_blocking(move || {
+            let mut w = triggers.write().unwrap();
+            let affected: Vec<Trigger> = w
+                .get_by_project(&project_id)
+                .into_iter()
+                .filter(|t| {
+                    matches!(
+                        t.schedule,
+                        Some(Schedule::Recurring(ref r))
+                            if r.exclude_calendars.contains(&name)
+                    )
+                })
+                .cloned()
+                .collect();
+            let refreshed = affected.len();
+            for mut trigger in affected {
+                if let Some(Schedule::Recurring(r)) = trigger.schedule.as_mut()
+                {
+                    r.calendars.retain(|c| c.name != name);
+                    r.calendars.push(calendar.clone());
+                }
+                let trigger_id = trigger.id.clone();
+                // Only the upcoming ticks change, the ticks before now were
+                // already handled and must not be treated as missed.
+                w.add_or_update(trigger, /* fast_forward = */ true)?;
+                w.add_to_awaiting_db_flush(trigger_id);
+            }
+            Ok::<_, TriggerError>(refreshed)
+        })
+        .await??;
+        info!("Refreshed the calendar in {refreshed} trigger(s)");
+        Ok(())
+    }
+
     #[tracing::instrument(skip_all, fields(trigger_name = %name, project_id = %context.project_id))]
     pub async fn pause_trigger(
         &self,
         context: RequestContext,
         name: String,
+        until: Option<DateTime<Utc>>,
     ) -> Result<Trigger, TriggerError> {
         let triggers = self.triggers.clone();
         let trigger_id =
@@ -540,13 +1215,148 @@ impl SpinnerController {
         }
         tokio::task::spawn_blocking(move || {
             let mut w = triggers.write().unwrap();
-            w.pause(&trigger_id)
+            w.pause(&trigger_id, until)
                 .map(|_| w.get(&trigger_id).unwrap())
                 .cloned()
         })
         .await?
     }
 
+    /// Skips the next `count` occurrences that aren't skipped already, or
+    /// exactly the given `occurrences`, without otherwise affecting the
+    /// trigger.
+    #[tracing::instrument(skip_all, fields(trigger_name = %name, project_id = %context.project_id))]
+    pub async fn skip_trigger(
+        &self,
+        context: RequestContext,
+        name: String,
+        count: Option<u64>,
+        occurrences: Vec<DateTime<Utc>>,
+    ) -> Result<Trigger, TriggerError> {
+        let triggers = self.triggers.clone();
+        let trigger_id =
+            self.get_trigger_id(&context.project_id, &name).await?;
+        let status =
+            self.get_trigger_status(&context.project_id, &name).await?;
+        // Only triggers with ticks of their own have occurrences to skip.
+        if !status.alive() {
+            return Err(TriggerError::InvalidStatus("skip".to_owned(), status));
+        }
+        let now = self.context.clock().now();
+        tokio::task::spawn_blocking(move || {
+            let mut w = triggers.write().unwrap();
+            let trigger = w
+                .get(&trigger_id)
+                .ok_or_else(|| TriggerError::NotFound(name.clone()))?;
+            let occurrences = match count {
+                | Some(count) => {
+                    let after = trigger.last_ran_at.unwrap_or(now).max(now);
+                    trigger.upcoming_occurrences(after, count as usize)
+                }
+                | None => {
+                    if let Some(invalid) = occurrences
+                        .iter()
+                        .find(|at| **at <= now || !trigger.is_occurrence(**at))
+                    {
+                        return Err(TriggerError::InvalidOccurrence(*invalid));
+                    }
+                    occurrences
+                }
+            };
+            w.skip(&trigger_id, occurrences)?;
+            Ok(w.get(&trigger_id).unwrap().clone())
+        })
+        .await?
+    }
+
+    /// Adds timepoints to, and removes timepoints from, a trigger with a
+    /// `RunAt` schedule without replacing it. Adding a timepoint that's
+    /// already upcoming, or removing one that isn't, has no effect. An expired
+    /// trigger ran all its timepoints, adding new ones schedules it again.
+    #[tracing::instrument(skip_all, fields(trigger_name = %name, project_id = %context.project_id))]
+    pub async fn update_timepoints(
+        &self,
+        context: RequestContext,
+        name: String,
+        add: Vec<DateTime<FixedOffset>>,
+        remove: Vec<DateTime<FixedOffset>>,
+    ) -> Result<Trigger, TriggerError> {
+        let triggers = self.triggers.clone();
+        let trigger_id =
+            self.get_trigger_id(&context.project_id, &name).await?;
+        let now = self.context.clock().now();
+        if let Some(past) = add.iter().find(|at| at.with_timezone(&Utc) <= now)
+        {
+            return Err(TriggerError::PastTimepoint(past.with_timezone(&Utc)));
+        }
+        // Expired triggers are evicted from the active map, we need the
+        // stored version to bring them back.
+        let stored = self
+            .store
+            .get_trigger_by_id(&context.project_id, &trigger_id)
+            .await?
+            .ok_or_else(|| TriggerError::NotFound(name.clone()))?;
+        let (trigger, revived) = tokio::task::spawn_blocking(move || {
+            let mut w = triggers.write().unwrap();
+            // The active map has the most recent status of the trigger.
+            let mut trigger = w.get(&trigger_id).cloned().unwrap_or(stored);
+            match trigger.status {
+                | Status::Expired if !add.is_empty() => {
+                    // All the timepoints of an expired trigger have passed,
+                    // only the added ones are left. We validate them before
+                    // reviving the trigger so that a rejected update leaves
+                    // it expired.
+                    let Some(Schedule::RunAt(run_at)) =
+                        trigger.schedule.as_mut()
+                    else {
+                        return Err(TriggerError::NotRunAt);
+                    };
+                    let mut timepoints: Vec<_> = add
+                        .iter()
+                        .filter(|t| !remove.contains(t))
+                        .copied()
+                        .collect();
+                    timepoints.sort();
+                    timepoints.dedup();
+                    if timepoints.len() > MAX_TIMEPOINTS {
+                        return Err(TriggerError::TooManyTimepoints(
+                            MAX_TIMEPOINTS,
+                        ));
+                    }
+                    run_at.remaining = Some(timepoints.len() as u64);
+                    run_at.timepoints = timepoints;
+                    trigger.status = Status::Scheduled;
+                    trigger.retired_at = None;
+                    w.add_or_update(trigger, /* fast_forward = */ false)?;
+                    w.add_to_awaiting_db_flush(trigger_id.clone());
+                    Ok((w.get(&trigger_id).unwrap().clone(), true))
+                }
+                | ref status if !status.alive() => {
+                    Err(TriggerError::InvalidStatus(
+                        "update timepoints".to_owned(),
+                        status.clone(),
+                    ))
+                }
+                | _ => {
+                    w.update_timepoints(&trigger_id, &add, &remove)?;
+                    Ok((w.get(&trigger_id).unwrap().clone(), false))
+                }
+            }
+        })
+        .await??;
+        if revived {
+            e!(
+                context = context,
+                TriggerStatusUpdated {
+                    meta: trigger.meta().into(),
+                    old_status: Status::Expired.into(),
+                    new_status: trigger.status.clone().into(),
+                }
+            );
+        }
+        Ok(trigger)
+    }
+
     #[tracing::instrument(skip_all, fields(trigger_name = %name, project_id = %context.project_id))]
     pub async fn resume_trigger(
         &self,
@@ -597,6 +1407,7 @@ impl SpinnerController {
             let mut trigger =
                 self.get_trigger(context.clone(), name.to_string()).await?;
             trigger.status = Status::Cancelled;
+            trigger.retired_at = Some(self.context.clock().now());
             self.store.update_trigger(trigger.clone()).await?;
             e!(
                 context = context,
@@ -682,6 +1493,180 @@ impl SpinnerController {
         Ok(())
     }
 
+    /// Applies `action` to the triggers of the project with one of the
+    /// `statuses` (any if empty) whose name starts with `name_prefix`. The
+    /// result of each trigger is reported separately, nothing is changed on
+    /// `dry_run`.
+    #[tracing::instrument(skip_all, fields(project_id = %context.project_id, ?action))]
+    pub async fn bulk_update_triggers(
+        &self,
+        context: RequestContext,
+        action: BulkAction,
+        statuses: Vec<Status>,
+        name_prefix: Option<String>,
+        dry_run: bool,
+    ) -> Result<Vec<BulkTriggerResult>, TriggerError> {
+        let found = self
+            .store
+            .find_triggers(&context.project_id, name_prefix.as_deref())
+            .await?;
+        // The active map has the most recent status of the live triggers,
+        // the database might lag behind until the next checkpoint.
+        let triggers = self.triggers.clone();
+        let matching: Vec<Trigger> = tokio::task::spawn_blocking(move || {
+            let r = triggers.read().unwrap();
+            found
+                .into_iter()
+                .map(|t| r.get(&t.id).cloned().unwrap_or(t))
+                .filter(|t| statuses.is_empty() || statuses.contains(&t.status))
+                .collect()
+        })
+        .await?;
+
+        let mut results = Vec::with_capacity(matching.len());
+        for batch in matching.chunks(BULK_UPDATE_BATCH) {
+            let batch = batch.to_vec();
+            if action == BulkAction::Delete {
+                results.extend(
+                    self.bulk_delete_triggers(&context, batch, dry_run).await?,
+                );
+                continue;
+            }
+
+            let triggers = self.triggers.clone();
+            let ids: Vec<TriggerId> =
+                batch.iter().map(|t| t.id.clone()).collect();
+            // Triggers that aren't in the active map are neither scheduled
+            // nor paused, `None` means we have to handle them from the
+            // database.
+            let outcomes = tokio::task::spawn_blocking(move || {
+                let mut w = triggers.write().unwrap();
+                ids.iter()
+                    .map(|id| apply_bulk_action(&mut w, id, action, dry_run))
+                    .collect::<Vec<_>>()
+            })
+            .await?;
+
+            for (mut trigger, outcome) in batch.into_iter().zip(outcomes) {
+                let outcome = match outcome {
+                    | Some(outcome) => outcome.map(Some),
+                    | None if action == BulkAction::Cancel
+                        && trigger.status.cancelleable() =>
+                    {
+                        if dry_run {
+                            Ok(Some(Status::Cancelled))
+                        } else {
+                            self.cancel_stored_trigger(&context, &mut trigger)
+                                .await
+                                .map(|_| Some(Status::Cancelled))
+                        }
+                    }
+                    | None => {
+                        Err(TriggerError::InvalidStatus(
+                            action.target_status().unwrap().as_operation(),
+                            trigger.status.clone(),
+                        ))
+                    }
+                };
+                results.push((trigger.name, outcome));
+            }
+        }
+        info!(
+            "Bulk {action:?} matched {} trigger(s){}",
+            results.len(),
+            if dry_run { " (dry run)" } else { "" }
+        );
+        Ok(results)
+    }
+
+    async fn bulk_delete_triggers(
+        &self,
+        context: &RequestContext,
+        batch: Vec<Trigger>,
+        dry_run: bool,
+    ) -> Result<Vec<BulkTriggerResult>, TriggerError> {
+        if dry_run {
+            return Ok(batch.into_iter().map(|t| (t.name, Ok(None))).collect());
+        }
+        let triggers = self.triggers.clone();
+        let ids: Vec<TriggerId> = batch.iter().map(|t| t.id.clone()).collect();
+        let mut removed: HashMap<TriggerId, ActiveTrigger> =
+            tokio::task::spawn_blocking(move || {
+                let mut w = triggers.write().unwrap();
+                ids.into_iter()
+                    .filter_map(|id| {
+                        let active = w.pop_trigger(&id)?;
+                        Some((id, active))
+                    })
+                    .collect()
+            })
+            .await?;
+
+        // Like `delete_trigger`, we delete from the database after removing
+        // the triggers from the active map. A failure doesn't stop the batch,
+        // the trigger goes back to the active map and the error is reported
+        // as its result.
+        let mut failed = Vec::new();
+        let mut results = Vec::with_capacity(batch.len());
+        for trigger in batch {
+            if let Err(e) = self
+                .store
+                .delete_trigger(&context.project_id, &trigger.id)
+                .await
+            {
+                if let Some(active) = removed.remove(&trigger.id) {
+                    failed.push((trigger.id, active));
+                }
+                results.push((trigger.name, Err(e.into())));
+                continue;
+            }
+            self.name_cache.remove(&context.project_id, &trigger.name);
+            e!(
+                context = context,
+                TriggerDeleted {
+                    meta: Some(trigger.meta()),
+                }
+            );
+            results.push((trigger.name, Ok(None)));
+        }
+
+        if !failed.is_empty() {
+            let triggers = self.triggers.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut w = triggers.write().unwrap();
+                for (id, active) in failed {
+                    w.push_trigger(id.clone(), active);
+                    // Its pending changes might have been dropped by a
+                    // checkpoint while it was out of the map.
+                    w.add_to_awaiting_db_flush(id);
+                }
+            })
+            .await?;
+        }
+        Ok(results)
+    }
+
+    /// Cancels a trigger that isn't in the active map.
+    async fn cancel_stored_trigger(
+        &self,
+        context: &RequestContext,
+        trigger: &mut Trigger,
+    ) -> Result<(), TriggerError> {
+        let old_status = trigger.status.clone();
+        trigger.status = Status::Cancelled;
+        trigger.retired_at = Some(self.context.clock().now());
+        self.store.update_trigger(trigger.clone()).await?;
+        e!(
+            context = context,
+            TriggerStatusUpdated {
+                meta: trigger.meta().into(),
+                old_status: old_status.into(),
+                new_status: Status::Cancelled.into(),
+            }
+        );
+        Ok(())
+    }
+
     pub async fn shutdown(&self) {
         {
             let mut spinner = self.spinner.lock().unwrap();
@@ -697,6 +1682,192 @@ impl SpinnerController {
         triggers.clear();
     }
 
+    /// Stops serving the shards and returns their triggers, revisions and
+    /// pending dispatches. The shards remain in the database until they are
+    /// released.
+    pub async fn drain_shards(
+        &self,
+        shards: HashSet<Shard>,
+    ) -> Result<ShardData, TriggerError> {
+        self.ensure_leader()?;
+        self.moved_shards
+            .write()
+            .unwrap()
+            .extend(shards.iter().copied());
+
+        let triggers_map = self.triggers.clone();
+        let cloned_shards = shards.clone();
+        let drained = tokio::task::spawn_blocking(move || {
+            let mut w = triggers_map.write().unwrap();
+            w.remove_by_shards(&cloned_shards)
+        })
+        .await?;
+        // The drained triggers might have progress that wasn't checkpointed
+        // yet.
+        for trigger in drained {
+            self.store.update_trigger(trigger).await?;
+        }
+
+        let data = ShardData {
+            triggers: self.store.get_triggers_by_shards(&shards).await?,
+            revisions: self.store.get_revisions_by_shards(&shards).await?,
+            outbox: self.outbox.get_by_shards(&shards).await?,
+        };
+        info!(
+            "Drained {} triggers, {} revisions and {} pending dispatches of \
+             shards {:?}",
+            data.triggers.len(),
+            data.revisions.len(),
+            data.outbox.len(),
+            shards
+        );
+        Ok(data)
+    }
+
+    /// Installs the data drained from another cell and starts serving their
+    /// shards.
+    pub async fn adopt_shards(
+        &self,
+        shards: HashSet<Shard>,
+        data: ShardData,
+    ) -> Result<(), TriggerError> {
+        self.ensure_leader()?;
+        let foreign = data
+            .triggers
+            .iter()
+            .map(|t| &t.project_id)
+            .chain(data.revisions.iter().map(|r| &r.project_id))
+            .chain(data.outbox.iter().map(|e| &e.project_id))
+            .find(|project_id| !shards.contains(&project_id.shard()));
+        if let Some(project_id) = foreign {
+            return Err(TriggerError::PreconditionFailed(format!(
+                "Project '{project_id}' doesn't belong to the adopted shards",
+            )));
+        }
+
+        let ShardData {
+            triggers,
+            revisions,
+            outbox,
+        } = data;
+        for trigger in &triggers {
+            self.store.import_trigger(trigger.clone()).await?;
+        }
+        for revision in revisions {
+            self.store.import_revision(revision).await?;
+        }
+        for entry in outbox {
+            self.outbox.enqueue(entry).await?;
+        }
+        let fast_forward = self.context.service_config().dangerous_fast_forward;
+        let triggers_map = self.triggers.clone();
+        let adopted = triggers.len();
+        tokio::task::spawn_blocking(move || {
+            let mut w = triggers_map.write().unwrap();
+            for trigger in triggers.into_iter().filter(|t| t.alive()) {
+                w.add_or_update(trigger, fast_forward)?;
+            }
+            Ok::<_, TriggerError>(())
+        })
+        .await??;
+
+        self.moved_shards
+            .write()
+            .unwrap()
+            .retain(|shard| !shards.contains(shard));
+        info!("Adopted {adopted} triggers of shards {:?}", shards);
+        Ok(())
+    }
+
+    /// Forgets the data of drained shards once another cell adopted them, the
+    /// other cell owns the triggers, their revisions and pending dispatches
+    /// from now on. Serves the shards again if the move was aborted.
+    pub async fn release_shards(
+        &self,
+        shards: HashSet<Shard>,
+        moved: bool,
+    ) -> Result<(), TriggerError> {
+        self.ensure_leader()?;
+        if moved {
+            // Requests that raced with draining might have installed new
+            // triggers in the meantime.
+            let triggers_map = self.triggers.clone();
+            let cloned_shards = shards.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut w = triggers_map.write().unwrap();
+                w.remove_by_shards(&cloned_shards);
+            })
+            .await?;
+            let triggers = self.store.get_triggers_by_shards(&shards).await?;
+            self.store.delete_shards(&shards).await?;
+            self.outbox.remove_by_shards(&shards).await?;
+            for trigger in triggers {
+                self.name_cache.remove_project(&trigger.project_id);
+            }
+            // In case the shards were adopted by this cell before the move
+            // was aborted.
+            self.moved_shards
+                .write()
+                .unwrap()
+                .extend(shards.iter().copied());
+            info!("Released shards {:?} that moved to another cell", shards);
+            return Ok(());
+        }
+
+        let triggers = self.store.get_triggers_by_shards(&shards).await?;
+        let fast_forward = self.context.service_config().dangerous_fast_forward;
+        let triggers_map = self.triggers.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut w = triggers_map.write().unwrap();
+            for trigger in triggers.into_iter().filter(|t| t.alive()) {
+                w.add_or_update(trigger, fast_forward)?;
+            }
+            Ok::<_, TriggerError>(())
+        })
+        .await??;
+        self.moved_shards
+            .write()
+            .unwrap()
+            .retain(|shard| !shards.contains(shard));
+        info!("Resumed serving shards {:?}", shards);
+        Ok(())
+    }
+
+    /// Loads the shards that the metadata service assigns to other cells.
+    async fn load_moved_shards(&self) -> Result<(), TriggerError> {
+        // With a single cell, shards have nowhere to move to.
+        if self.context.config().get_main().scheduler_cell_map.len() <= 1 {
+            return Ok(());
+        }
+        let cell_id = u64::from(self.context.service_config().cell_id);
+        // This is a system-generated request.
+        let mut client = self
+            .metadata_clients
+            .get_cell_client(&RequestId::new(), 0)
+            .await
+            .map_err(|e| TriggerError::Metadata(e.to_string()))?;
+        let assignments = client
+            .get_shard_assignments(GetShardAssignmentsRequest {})
+            .await
+            .map_err(|e| TriggerError::Metadata(e.to_string()))?
+            .into_inner()
+            .assignments;
+        let moved: HashSet<Shard> = assignments
+            .into_iter()
+            .filter(|a| a.cell_id != cell_id)
+            .map(|a| Shard(a.shard))
+            .collect();
+        if !moved.is_empty() {
+            info!("Shards {:?} are served by other cells", moved);
+            // A move that failed to release its source cell leaves a copy of
+            // the shards behind, the cell they moved to owns them now.
+            self.store.delete_shards(&moved).await?;
+            self.outbox.remove_by_shards(&moved).await?;
+        }
+        *self.moved_shards.write().unwrap() = moved;
+        Ok(())
+    }
+
     async fn load_triggers_from_database(&self) -> Result<(), TriggerError> {
         let triggers = self.store.get_all_active_triggers().await?;
 
@@ -709,13 +1880,113 @@ impl SpinnerController {
                  execute future events only"
             );
         }
+        let moved_shards = self.moved_shards.read().unwrap().clone();
         let mut map = self.triggers.write().unwrap();
-        for trigger in triggers {
+        for trigger in triggers
+            .into_iter()
+            .filter(|t| !moved_shards.contains(&t.project_id.shard()))
+        {
             map.add_or_update(trigger, config.dangerous_fast_forward)?;
         }
         Ok(())
     }
 
+    /// Takes a snapshot of the calendars that a recurring schedule excludes so
+    /// that the spinner never has to reach out to the metadata service.
+    async fn resolve_calendars(
+        &self,
+        context: &RequestContext,
+        schedule: &mut Schedule,
+    ) -> Result<(), TriggerError> {
+        let Schedule::Recurring(recurring) = schedule else {
+            return Ok(());
+        };
+        if recurring.exclude_calendars.is_empty() {
+            recurring.calendars.clear();
+            return Ok(());
+        }
+        let calendars = self
+            .fetch_calendars(context, recurring.exclude_calendars.clone())
+            .await?;
+        if let Some(missing) = recurring
+            .exclude_calendars
+            .iter()
+            .find(|name| !calendars.iter().any(|c| &c.name == *name))
+        {
+            return Err(TriggerError::CalendarNotFound(missing.clone()));
+        }
+        recurring.calendars = calendars;
+        Ok(())
+    }
+
+    async fn fetch_calendars(
+        &self,
+        context: &RequestContext,
+        names: Vec<String>,
+    ) -> Result<Vec<Calendar>, TriggerError> {
+        let mut metadata = self
+            .metadata_clients
+            .get_client(&context.request_id, &context.project_id)
+            .await
+            .map_err(|e| TriggerError::Metadata(e.to_string()))?;
+        let calendars = metadata
+            .get_calendars(GetCalendarsRequest {
+                id: Some(context.project_id.clone().into()),
+                names,
+            })
+            .await
+            .map_err(|e| TriggerError::Metadata(e.to_string()))?
+            .into_inner()
+            .calendars;
+        Ok(calendars.into_iter().map(Into::into).collect())
+    }
+
+    /// Finds the trigger that `trigger_name` runs on completion of, and makes
+    /// sure that following the chain of upstream triggers doesn't lead back to
+    /// `trigger_id`.
+    async fn resolve_upstream_trigger(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+        trigger_name: &str,
+        upstream_name: &str,
+    ) -> Result<TriggerId, TriggerError> {
+        let mut chain = vec![trigger_name.to_owned()];
+        if upstream_name == trigger_name {
+            chain.push(upstream_name.to_owned());
+            return Err(TriggerError::DependencyCycle(chain.join(" -> ")));
+        }
+
+        let upstream = self
+            .store
+            .get_trigger_by_name(project, upstream_name)
+            .await?
+            .ok_or_else(|| {
+                TriggerError::UpstreamNotFound(upstream_name.to_owned())
+            })?;
+        let upstream_trigger_id = upstream.id.clone();
+
+        let mut visited = HashSet::new();
+        let mut next = Some(upstream);
+        while let Some(current) = next {
+            chain.push(current.name.clone());
+            if current.id == *trigger_id {
+                return Err(TriggerError::DependencyCycle(chain.join(" -> ")));
+            }
+            if !visited.insert(current.id.clone()) {
+                // A cycle that we are not part of, nothing to follow.
+                break;
+            }
+            next = match current.upstream_trigger_id {
+                | Some(id) => {
+                    self.store.get_trigger_by_id(project, &id).await?
+                }
+                | None => None,
+            };
+        }
+        Ok(upstream_trigger_id)
+    }
+
     async fn get_trigger_status(
         &self,
         project: &ValidShardedId<ProjectId>,
@@ -726,3 +1997,231 @@ impl SpinnerController {
         status.ok_or_else(|| TriggerError::NotFound(name.to_owned()))
     }
 }
+
+/// Applies a status changing bulk action to a trigger in the active map,
+/// returns `None` if the trigger isn't in the map.
+fn apply_bulk_action(
+    w: &mut ActiveTriggerMap,
+    trigger_id: &TriggerId,
+    action: BulkAction,
+    dry_run: bool,
+) -> Option<Result<Status, TriggerError>> {
+    let status = w.get(trigger_id)?.status.clone();
+    let target = action.target_status()?;
+    let retired = status.retired();
+    let result = match action {
+        | BulkAction::Pause | BulkAction::Resume if retired => {
+            Err(TriggerError::InvalidStatus(target.as_operation(), status))
+        }
+        | _ if dry_run => Ok(()),
+        | BulkAction::Pause => w.pause(trigger_id, None),
+        | BulkAction::Resume => w.resume(trigger_id),
+        | BulkAction::Cancel => w.cancel(trigger_id),
+        | BulkAction::Delete => unreachable!(),
+    };
+    Some(result.map(|_| target))
+}
+
+/// Exponential backoff between attempts of an outbox dispatch.
+fn outbox_retry_delay(
+    attempts: i32,
+    base_delay_s: u64,
+    max_delay_s: u64,
+) -> chrono::Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    let delay_s = base_delay_s.saturating_mul(2u64.pow(exponent));
+    chrono::Duration::seconds(delay_s.min(max_delay_s) as i64)
+}
+
+/// Whether the trigger has ticks of its own for the spinner to run.
+fn is_spinning(trigger: &Trigger) -> bool {
+    trigger.schedule.is_some() && trigger.upstream_trigger_id.is_none()
+}
+
+/// Resets the remaining runs of the schedule.
+// TODO: When updating, allow the user to express their intent to whether they
+// want to reset the remaining or not.
+fn reset_remaining(trigger: &mut proto::triggers::Trigger, now: DateTime<Utc>) {
+    let Some(schedule) = trigger.schedule.as_mut() else {
+        return;
+    };
+    // the inner .schedule must be set at this point.
+    // The spinner will update `remaining` to the accurate value as soon as it
+    // runs.
+    let schedule = schedule.schedule.as_mut().unwrap();
+    match schedule {
+        | proto::triggers::schedule::Schedule::Recurring(r)
+            if r.limit.is_some() =>
+        {
+            r.remaining = r.limit;
+        }
+        | proto::triggers::schedule::Schedule::Recurring(r) => {
+            r.remaining = None;
+        }
+        | proto::triggers::schedule::Schedule::RunAt(r) => {
+            r.remaining = Some(r.timepoints.len() as u64);
+        }
+        | proto::triggers::schedule::Schedule::Interval(r) => {
+            r.remaining = r.limit;
+            // Unless told otherwise, intervals are counted from the moment
+            // the trigger gets installed.
+            if r.anchor.is_none() {
+                r.anchor = Some(now.into());
+            }
+        }
+        | proto::triggers::schedule::Schedule::OnCompletionOf(_) => {}
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+    use lib::service::CronbackService;
+    use lib::{ConfigBuilder, Shutdown};
+    use proto::common::{action, Action, HttpMethod, Webhook};
+    use proto::triggers::{schedule, Recurring};
+
+    use super::*;
+
+    fn make_replica(
+        context: ServiceContext<SchedulerService>,
+        db: Database,
+    ) -> SpinnerController {
+        let config = context.config().clone();
+        SpinnerController::new(
+            context,
+            TriggerStore::new(db.clone()),
+            OutboxStore::new(db),
+            Arc::new(GrpcClientProvider::new(config.clone())),
+            Arc::new(GrpcClientProvider::new(config)),
+        )
+    }
+
+    fn make_upsert(name: &str) -> UpsertTriggerRequest {
+        UpsertTriggerRequest {
+            trigger: Some(proto::triggers::Trigger {
+                name: name.to_owned(),
+                action: Some(Action {
+                    action: Some(action::Action::Webhook(Webhook {
+                        url: "http://localhost:3000".to_owned(),
+                        http_method: HttpMethod::Get.into(),
+                        timeout_s: 30.0,
+                        ..Default::default()
+                    })),
+                }),
+                schedule: Some(proto::triggers::Schedule {
+                    schedule: Some(schedule::Schedule::Recurring(Recurring {
+                        cron: "0 * * * * *".to_owned(),
+                        timezone: "Etc/UTC".to_owned(),
+                        ..Default::default()
+                    })),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn standby_takes_over_after_step_down() -> anyhow::Result<()> {
+        let config = ConfigBuilder::default()
+            .register_service::<SchedulerService>()
+            .build_once()?;
+        let context =
+            SchedulerService::make_context(config, Shutdown::default());
+        // Replicas of a cell share the same database.
+        let db = SchedulerService::in_memory_database().await?;
+        let leader = make_replica(context.clone(), db.clone());
+        let standby = make_replica(context, db);
+
+        let project = ProjectId::generate();
+        let ctx = RequestContext::new(RequestId::new(), project.clone());
+
+        leader.start().await?;
+        standby.set_cell_leader(Some("http://leader".to_owned()));
+        leader
+            .upsert_trigger(ctx.clone(), make_upsert("t1"))
+            .await?;
+
+        // The standby rejects changes but serves reads.
+        assert!(leader.ensure_serving(&project).is_ok());
+        assert!(matches!(
+            standby.ensure_serving(&project),
+            Err(TriggerError::Standby(leader)) if leader == "http://leader"
+        ));
+        assert!(standby.ensure_readable(&project).is_ok());
+        let trigger = standby.get_trigger(ctx.clone(), "t1".to_owned()).await?;
+        assert_eq!(trigger.status, Status::Scheduled);
+
+        // Failover, the standby picks up the triggers of the old leader.
+        leader.step_down();
+        standby.start().await?;
+        standby.set_cell_leader(None);
+        assert!(!leader.is_leader());
+        assert!(standby.is_leader());
+        assert!(matches!(
+            leader.ensure_serving(&project),
+            Err(TriggerError::Standby(_))
+        ));
+        let trigger = standby.get_trigger(ctx.clone(), "t1".to_owned()).await?;
+        assert_eq!(trigger.status, Status::Scheduled);
+        let trigger_id = standby.get_trigger_id(&project, "t1").await?;
+        assert!(standby.triggers.read().unwrap().get(&trigger_id).is_some());
+
+        // And serves the changes from now on.
+        standby.upsert_trigger(ctx, make_upsert("t2")).await?;
+        standby.shutdown().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn outbox_is_relayed_by_the_leader() -> anyhow::Result<()> {
+        // Nothing listens on the dispatcher address of the default config.
+        let config = ConfigBuilder::default()
+            .register_service::<SchedulerService>()
+            .build_once()?;
+        let now = Utc::now().with_nanosecond(0).unwrap();
+        let clock = Arc::new(ManualClock::new(now));
+        let context =
+            SchedulerService::make_context(config, Shutdown::default())
+                .with_clock(clock.clone());
+        let db = SchedulerService::in_memory_database().await?;
+        let leader = make_replica(context.clone(), db.clone());
+        let standby = make_replica(context, db);
+        leader.start().await?;
+
+        let project = ProjectId::generate();
+        let request = proto::dispatcher_svc::DispatchRequest {
+            trigger_id: Some(TriggerId::generate(&project).into()),
+            run_id: Some(RunId::generate(&project).into()),
+            ..Default::default()
+        };
+        let entry = OutboxEntry::new(
+            request.run_id.clone().unwrap().into(),
+            project.clone(),
+            request.trigger_id.clone().unwrap().into(),
+            now,
+            prost::Message::encode_to_vec(&request),
+            "dispatcher is unreachable".to_owned(),
+            now,
+        );
+        leader.outbox.enqueue(entry.clone()).await?;
+
+        // Only the leader relays.
+        standby.relay_outbox().await;
+        assert_eq!(leader.outbox.get_due(now, 10).await?, vec![entry.clone()]);
+
+        // The dispatcher is still down, the dispatch is retried later.
+        leader.relay_outbox().await;
+        assert!(leader.outbox.get_due(now, 10).await?.is_empty());
+        let later = now + chrono::Duration::days(1);
+        let due = leader.outbox.get_due(later, 10).await?;
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].run_id, entry.run_id);
+        assert_eq!(due[0].attempts, 2);
+        assert!(due[0].next_attempt_at > now);
+
+        leader.shutdown().await;
+        Ok(())
+    }
+}
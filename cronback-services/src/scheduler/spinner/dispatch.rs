@@ -1,15 +1,21 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use dto::{FromProto, IntoProto};
 use lib::clients::ScopedDispatcherSvcClient;
 use lib::prelude::*;
 use lib::{GrpcClientError, GrpcClientFactory, GrpcClientProvider};
+use prost::Message;
 use proto::dispatcher_svc::{self, DispatchRequest};
 use proto::runs::Run;
+use sha2::{Digest, Sha256};
 use thiserror::Error;
-use tracing::info;
+use tonic::Code;
+use tracing::{error, info, warn};
+use ulid::Ulid;
 
-use crate::scheduler::db_model::Trigger;
+use crate::scheduler::db_model::{OutboxEntry, Trigger};
+use crate::scheduler::outbox_store::OutboxStore;
 
 #[tracing::instrument(skip_all, fields(trigger_id = %trigger.id))]
 pub(crate) async fn dispatch(
@@ -24,6 +30,73 @@ pub(crate) async fn dispatch(
     job.run().await
 }
 
+/// Dispatches a scheduled tick of a trigger. If the dispatcher can't be
+/// reached, the dispatch is persisted in the outbox to be retried later with
+/// the same run id and scheduled time, so the tick is not lost.
+#[tracing::instrument(skip_all, fields(trigger_id = %trigger.id))]
+pub(crate) async fn dispatch_tick(
+    context: RequestContext,
+    trigger: Trigger,
+    dispatch_clients: Arc<GrpcClientProvider<ScopedDispatcherSvcClient>>,
+    outbox: OutboxStore,
+    scheduled_at: DateTime<Utc>,
+    clock: Arc<dyn Clock>,
+) -> Result<(), DispatchError> {
+    let trigger_id = trigger.id.clone();
+    let mut job = DispatchJob::from_trigger(
+        context,
+        trigger,
+        dispatch_clients,
+        DispatchMode::Async,
+    )
+    .for_tick(scheduled_at);
+    info!(trigger = job.trigger_id(), "async-dispatch");
+    let err = match job.run().await {
+        | Ok(_) => return Ok(()),
+        | Err(e) if e.is_retryable() => e,
+        | Err(e) => return Err(e),
+    };
+
+    warn!(
+        "Failed to dispatch trigger {}, will retry from the outbox: {}",
+        trigger_id, err
+    );
+    let entry = OutboxEntry::new(
+        job.run_id(),
+        job.context.project_id.clone(),
+        trigger_id,
+        scheduled_at,
+        job.dispatch_request.encode_to_vec(),
+        err.to_string(),
+        clock.now(),
+    );
+    if let Err(e) = outbox.enqueue(entry).await {
+        error!(
+            "Failed to add the dispatch of trigger {} to the outbox: {}",
+            job.trigger_id(),
+            e
+        );
+        return Err(err);
+    }
+    Ok(())
+}
+
+fn tick_run_id(
+    project_id: &ValidShardedId<ProjectId>,
+    trigger_id: &str,
+    scheduled_at: DateTime<Utc>,
+) -> ValidShardedId<RunId> {
+    // Like generated run ids, the ulid is ordered by time.
+    let digest = Sha256::digest(trigger_id.as_bytes());
+    let random = u128::from_be_bytes(digest[..16].try_into().unwrap());
+    let ulid = Ulid::from_parts(scheduled_at.timestamp_millis() as u64, random);
+    ValidShardedId::from_string_unsafe(format!(
+        "run_{}{}",
+        project_id.shard().encoded(),
+        ulid
+    ))
+}
+
 #[derive(Error, Debug)]
 pub enum DispatchError {
     #[error("Failed while attempting to communicate with dispatcher")]
@@ -34,6 +107,28 @@ pub enum DispatchError {
     GrpcClient(#[from] GrpcClientError),
 }
 
+impl DispatchError {
+    /// Whether the dispatch might succeed if attempted again later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            | DispatchError::Transport(_) | DispatchError::GrpcClient(_) => {
+                true
+            }
+            | DispatchError::Logical(status) => {
+                matches!(
+                    status.code(),
+                    Code::Unavailable
+                        | Code::DeadlineExceeded
+                        | Code::Unknown
+                        | Code::ResourceExhausted
+                        | Code::Aborted
+                        | Code::Cancelled
+                )
+            }
+        }
+    }
+}
+
 #[derive(Debug, FromProto, IntoProto)]
 #[proto(target = "proto::dispatcher_svc::DispatchMode")]
 pub enum DispatchMode {
@@ -61,11 +156,50 @@ impl DispatchJob {
                 action: Some(trigger.action.into()),
                 payload: trigger.payload.map(|p| p.into()),
                 mode: dispatcher_svc::DispatchMode::from(mode).into(),
+                concurrency_policy: trigger.concurrency_policy.into(),
+                // The run id is picked here so that retries of this dispatch
+                // don't end up creating more than one run.
+                run_id: Some(RunId::generate(&trigger.project_id).into()),
+                scheduled_at: None,
             },
             dispatcher_clients,
         }
     }
 
+    /// Resumes a dispatch that was persisted in the outbox.
+    pub fn from_outbox(
+        context: RequestContext,
+        entry: &OutboxEntry,
+        dispatcher_clients: Arc<GrpcClientProvider<ScopedDispatcherSvcClient>>,
+    ) -> Result<Self, prost::DecodeError> {
+        Ok(Self {
+            context,
+            dispatch_request: DispatchRequest::decode(
+                entry.request.as_slice(),
+            )?,
+            dispatcher_clients,
+        })
+    }
+
+    /// Dispatches the tick of the trigger at `scheduled_at`. The run id of a
+    /// tick is derived from the trigger and the time of the tick, a replica
+    /// that takes over the cell dispatches the ticks since the last
+    /// checkpoint again and the dispatcher already has their runs.
+    pub fn for_tick(mut self, scheduled_at: DateTime<Utc>) -> Self {
+        let run_id = tick_run_id(
+            &self.context.project_id,
+            self.trigger_id(),
+            scheduled_at,
+        );
+        self.dispatch_request.run_id = Some(run_id.into());
+        self.dispatch_request.scheduled_at = Some(scheduled_at.into());
+        self
+    }
+
+    pub fn run_id(&self) -> RunId {
+        self.dispatch_request.run_id.clone().unwrap().into()
+    }
+
     pub fn trigger_id(&self) -> &str {
         &self.dispatch_request.trigger_id.unwrap_ref().value
     }
@@ -81,3 +215,108 @@ impl DispatchJob {
         Ok(run)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::TimeZone;
+    use lib::service::CronbackService;
+    use lib::ConfigBuilder;
+
+    use super::*;
+    use crate::scheduler::db_model::triggers::Status;
+    use crate::scheduler::SchedulerService;
+
+    fn build_trigger(project: &ValidShardedId<ProjectId>) -> Trigger {
+        Trigger {
+            id: TriggerId::generate(project).into(),
+            project_id: project.clone(),
+            name: "tick".to_string(),
+            description: None,
+            created_at: Utc::now(),
+            updated_at: None,
+            payload: None,
+            schedule: None,
+            action: Action::Webhook(Webhook {
+                url: "http://test".to_string(),
+                http_method: HttpMethod::Get,
+                timeout_s: Duration::from_secs(5),
+                retry: None,
+                success_criteria: None,
+            }),
+            status: Status::Scheduled,
+            last_ran_at: None,
+            concurrency_policy: Default::default(),
+            upstream_trigger_id: None,
+            paused_until: None,
+            skipped_occurrences: Default::default(),
+            labels: Default::default(),
+            retired_at: None,
+        }
+    }
+
+    #[test]
+    fn tick_run_ids() {
+        let project = ProjectId::generate();
+        let trigger1 = TriggerId::generate(&project);
+        let trigger2 = TriggerId::generate(&project);
+        let tick = Utc.with_ymd_and_hms(2023, 6, 1, 10, 0, 0).unwrap();
+        let next_tick = tick + chrono::Duration::minutes(1);
+
+        let run_id = tick_run_id(&project, trigger1.value(), tick);
+        assert!(run_id.has_valid_prefix());
+        assert_eq!(run_id.shard(), project.shard());
+        // The same tick always gets the same run.
+        assert_eq!(run_id, tick_run_id(&project, trigger1.value(), tick));
+        assert_ne!(run_id, tick_run_id(&project, trigger2.value(), tick));
+        assert_ne!(run_id, tick_run_id(&project, trigger1.value(), next_tick));
+    }
+
+    #[tokio::test]
+    async fn undispatched_ticks_go_to_the_outbox() -> anyhow::Result<()> {
+        // Nothing listens on the dispatcher address of the default config.
+        let config = ConfigBuilder::default()
+            .register_service::<SchedulerService>()
+            .build_once()?;
+        let dispatcher_clients = Arc::new(GrpcClientProvider::new(config));
+        let outbox =
+            OutboxStore::new(SchedulerService::in_memory_database().await?);
+
+        let project = ProjectId::generate();
+        let trigger = build_trigger(&project);
+        let tick = Utc.with_ymd_and_hms(2023, 6, 1, 10, 0, 0).unwrap();
+        let now = tick + chrono::Duration::seconds(1);
+        let clock: Arc<dyn Clock> = Arc::new(ManualClock::new(now));
+        let context = RequestContext::new(RequestId::new(), project.clone());
+
+        for _ in 0..2 {
+            dispatch_tick(
+                context.clone(),
+                trigger.clone(),
+                dispatcher_clients.clone(),
+                outbox.clone(),
+                tick,
+                clock.clone(),
+            )
+            .await?;
+        }
+
+        // Both attempts are the same run, it's only queued once.
+        let due = outbox.get_due(now, 10).await?;
+        assert_eq!(due.len(), 1);
+        let entry = &due[0];
+        let run_id: RunId =
+            tick_run_id(&project, trigger.id.value(), tick).into();
+        assert_eq!(entry.run_id, run_id);
+        assert_eq!(entry.trigger_id, trigger.id);
+        assert_eq!(entry.scheduled_at, tick);
+        assert_eq!(entry.created_at, now);
+
+        // The outbox retries the same request.
+        let job = DispatchJob::from_outbox(context, entry, dispatcher_clients)?;
+        assert_eq!(job.run_id(), run_id);
+        assert_eq!(job.trigger_id(), trigger.id.value());
+        Ok(())
+    }
+}
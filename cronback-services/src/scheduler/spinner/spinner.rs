@@ -6,6 +6,7 @@ use std::time::{Duration, Instant};
 use std::vec;
 
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use lib::clients::ScopedDispatcherSvcClient;
 use lib::prelude::*;
 use lib::service::ServiceContext;
@@ -15,8 +16,10 @@ use tokio::runtime::Handle;
 use tracing::{debug, info, trace, warn, Instrument};
 
 use super::active_triggers::{ActiveTriggerMap, TriggerTemporalState};
-use super::dispatch::{self, DispatchError, DispatchMode};
+use super::dispatch::{self, DispatchError};
+use crate::scheduler::config::SchedulerSvcConfig;
 use crate::scheduler::db_model::triggers::Status;
+use crate::scheduler::outbox_store::OutboxStore;
 use crate::scheduler::SchedulerService;
 
 pub(crate) struct Spinner {
@@ -25,6 +28,7 @@ pub(crate) struct Spinner {
     shutdown: Arc<RwLock<bool>>,
     context: ServiceContext<SchedulerService>,
     dispatcher_clients: Arc<GrpcClientProvider<ScopedDispatcherSvcClient>>,
+    outbox: OutboxStore,
 }
 
 pub(crate) struct SpinnerHandle {
@@ -32,6 +36,27 @@ pub(crate) struct SpinnerHandle {
     shutdown_signal: Arc<RwLock<bool>>,
 }
 
+/// What the spinner carries over from one tick to the next.
+struct SpinnerState {
+    config: SchedulerSvcConfig,
+    // All the time checks go through the clock of the context, tests drive
+    // the spinner by moving a manual clock.
+    clock: Arc<dyn Clock>,
+    temporal_states: BinaryHeap<Reverse<TriggerTemporalState>>,
+    inflight_dispatches: Vec<InflightDispatch>,
+}
+
+impl SpinnerState {
+    fn new(context: &ServiceContext<SchedulerService>) -> Self {
+        Self {
+            config: context.service_config(),
+            clock: context.clock(),
+            temporal_states: Default::default(),
+            inflight_dispatches: Vec::new(),
+        }
+    }
+}
+
 struct InflightDispatch {
     pub trigger_id: TriggerId,
     pub ran_at: DateTime<Utc>,
@@ -52,8 +77,9 @@ impl SpinnerHandle {
 }
 
 ///  **Design thoughts:**
-///   - [Spinner] is not responsible for dispatch retries. The async dispatch
-///     logic will need to take care of this if the RPC call failed.
+///   - [Spinner] is not responsible for dispatch retries. Dispatches that fail
+///     to reach the dispatcher are persisted in the outbox and retried by the
+///     [SpinnerController].
 ///   - The spinner will only consider triggers already installed in
 ///     [ActiveTriggerMap].
 ///   - Short sweet locking of [ActiveTriggerMap] to reduce contention.
@@ -100,6 +126,7 @@ impl Spinner {
         context: ServiceContext<SchedulerService>,
         triggers: Arc<RwLock<ActiveTriggerMap>>,
         dispatcher_clients: Arc<GrpcClientProvider<ScopedDispatcherSvcClient>>,
+        outbox: OutboxStore,
     ) -> Self {
         Self {
             tokio_handle: Handle::current(),
@@ -107,6 +134,7 @@ impl Spinner {
             context,
             triggers,
             dispatcher_clients,
+            outbox,
         }
     }
 
@@ -127,13 +155,9 @@ impl Spinner {
 
     #[tracing::instrument(skip_all)]
     fn run_forever(self) {
-        let mut temporal_states: BinaryHeap<Reverse<TriggerTemporalState>> =
-            Default::default();
-        let mut inflight_dispatches: Vec<InflightDispatch> = Vec::new();
-        let config = self.context.service_config();
+        let mut state = SpinnerState::new(&self.context);
         let yield_max_duration =
-            Duration::from_millis(config.spinner_yield_max_ms);
-        let max_triggers_per_tick = config.max_triggers_per_tick;
+            Duration::from_millis(state.config.spinner_yield_max_ms);
         'tick_loop: loop {
             {
                 let shutdown = self.shutdown.read().unwrap();
@@ -142,154 +166,9 @@ impl Spinner {
                 }
             }
 
-            // Successful dispatches should update the last_ran_at in
-            // ActiveTriggerMap and compacted.
-            {
-                // Scoped to drop memory asap.
-                let mut pending_dispatches =
-                    Vec::with_capacity(inflight_dispatches.len());
-                let mut success_dispatches =
-                    Vec::with_capacity(inflight_dispatches.len());
-                for inflight in inflight_dispatches.drain(..) {
-                    if inflight.handle.is_finished() {
-                        // Success? This is quick since it's already finished.
-                        if self
-                            .tokio_handle
-                            .block_on(inflight.handle)
-                            .unwrap()
-                            .is_ok()
-                        {
-                            success_dispatches
-                                .push((inflight.trigger_id, inflight.ran_at));
-                        }
-                    } else {
-                        // keep it around, we are still waiting for them.
-                        pending_dispatches.push(inflight);
-                    }
-                }
-                // continue tracking those who didn't finish yet.
-                inflight_dispatches = pending_dispatches;
-                {
-                    // Those who succeeded should be updated in the active map
-                    let mut w = self.triggers.write().unwrap();
-                    for (trigger_id, ran_at) in success_dispatches {
-                        w.update_last_ran_at(&trigger_id, ran_at);
-                    }
-                }
-            }
-            counter!(
-                "inflight_dispatches_total",
-                inflight_dispatches.len() as u64
-            );
-
-            /*
-             * 1. Go over all installed triggers that have next_tick <= now()
-             * Those are removed from the min-heap. Keep the list of removed
-             * triggers until we finish up the loop.
-             *
-             */
-            let mut dispatch_queue = vec![];
-            for _ in 0..max_triggers_per_tick {
-                let Some(temporal_state) = temporal_states.peek() else {
-                    break;
-                };
-                if temporal_state.0.next_tick <= Utc::now() {
-                    let temporal_state = temporal_states.pop().unwrap().0;
-                    trace!(
-                        "Adding trigger {} to the dispatch queue",
-                        temporal_state.trigger_id,
-                    );
-                    dispatch_queue.push(temporal_state);
-                } else {
-                    // The rest is in the future.
-                    break;
-                }
-            }
-            if dispatch_queue.len() == max_triggers_per_tick as usize {
-                warn!(
-                    "Reached max dispatches per tick ({}), some triggers will \
-                     be deferred",
-                    max_triggers_per_tick
-                );
-            }
-
-            /*
-             * 2. Dispatch a new event (async) for each of those triggers.
-             */
             let instant = Instant::now();
-            trace!(
-                "[TICK] temporal_state {} and dispatch_queue {}",
-                temporal_states.len(),
-                dispatch_queue.len(),
-            );
-            for trigger in dispatch_queue.iter() {
-                let id = trigger.trigger_id.clone();
-                let scheduled_time = trigger.next_tick;
-                let lag = Utc::now()
-                    .signed_duration_since(scheduled_time)
-                    .num_milliseconds() as f64;
-                histogram!("spinner.dispatch_lag_seconds", lag / 1000.0);
-                if lag > 10_000.0 {
-                    warn!(
-                        "Spinner lag has exceeded 10s, you might need to \
-                         increase `max_triggers_per_tick` (current \
-                         '{max_triggers_per_tick}') or reduce \
-                         `spinner_yield_max_ms` (current '{}')",
-                        config.spinner_yield_max_ms
-                    );
-                }
-
-                if let Some(handle) = self.dispatch(&id) {
-                    inflight_dispatches.push(InflightDispatch {
-                        trigger_id: id.clone(),
-                        ran_at: Utc::now(),
-                        handle,
-                    });
-                }
-            }
-            /*
-             * 3. Write-Lock ActiveTriggerMap
-             * 4. Calculate temporal state for the removed triggers and
-             *    re-insert.
-             */
-            if !dispatch_queue.is_empty() {
-                // Maybe individual triggers need to be advanced.
-                let mut w = self.triggers.write().unwrap();
-                for mut trigger in dispatch_queue {
-                    // Those states that yield None in advance will be dropped.
-                    if let Some(next_tick) = w.advance(&trigger.trigger_id) {
-                        trace!(
-                            "Trigger {} next trigger time is {}",
-                            trigger.trigger_id,
-                            next_tick
-                        );
-                        // _can_ be avoided if the trigger map is dirty, but
-                        // will keep it for simplicity.
-                        trigger.next_tick = next_tick;
-                        temporal_states.push(Reverse(trigger));
-                    } else {
-                        // This trigger is no longer active. We should flush and
-                        // compact.
-                        w.add_to_awaiting_db_flush(trigger.trigger_id);
-                    }
-                }
-            }
-            /*
-             * 5. Check if we have dirty triggers, fetch their contents.
-             * 6. Rebuild temporal state if needed.
-             */
-            // Is the state dirty?
-            if self.triggers.read().unwrap().is_dirty() {
-                trace!("Triggers updated, reloading...");
-                // TODO only reload the triggers that has been updated. Or
-                // re-construct the entire temporal state.
-                let mut w = self.triggers.write().unwrap();
-                temporal_states = w.build_temporal_state();
-                gauge!(
-                    "spinner.active_triggers_total",
-                    temporal_states.len() as f64
-                );
-            }
+            // We are on the spinner's own thread, outside of the runtime.
+            self.tokio_handle.block_on(self.tick(&mut state));
 
             // This indicates how busy the machine is. The closer to zero
             // the busier we are. That said, it's not an accurate indicator of
@@ -310,9 +189,162 @@ impl Spinner {
         }
     }
 
+    /// One iteration of the spinner loop: dispatches the triggers that are
+    /// due according to the clock and works out their next tick.
+    async fn tick(&self, state: &mut SpinnerState) {
+        let clock = state.clock.clone();
+        let max_triggers_per_tick = state.config.max_triggers_per_tick;
+        // Successful dispatches should update the last_ran_at in
+        // ActiveTriggerMap and compacted.
+        {
+            // Scoped to drop memory asap.
+            let mut pending_dispatches =
+                Vec::with_capacity(state.inflight_dispatches.len());
+            let mut success_dispatches =
+                Vec::with_capacity(state.inflight_dispatches.len());
+            for inflight in state.inflight_dispatches.drain(..) {
+                if inflight.handle.is_finished() {
+                    // Success? This is quick since it's already finished.
+                    if inflight.handle.await.unwrap().is_ok() {
+                        success_dispatches
+                            .push((inflight.trigger_id, inflight.ran_at));
+                    }
+                } else {
+                    // keep it around, we are still waiting for them.
+                    pending_dispatches.push(inflight);
+                }
+            }
+            // continue tracking those who didn't finish yet.
+            state.inflight_dispatches = pending_dispatches;
+            {
+                // Those who succeeded should be updated in the active map
+                let mut w = self.triggers.write().unwrap();
+                for (trigger_id, ran_at) in success_dispatches {
+                    w.update_last_ran_at(&trigger_id, ran_at);
+                }
+                // Paused triggers that are due to resume.
+                w.resume_snoozed(clock.now());
+            }
+        }
+        counter!(
+            "inflight_dispatches_total",
+            state.inflight_dispatches.len() as u64
+        );
+
+        /*
+         * 1. Go over all installed triggers that have next_tick <= now()
+         * Those are removed from the min-heap. Keep the list of removed
+         * triggers until we finish up the loop.
+         *
+         */
+        let mut dispatch_queue = vec![];
+        for _ in 0..max_triggers_per_tick {
+            let Some(temporal_state) = state.temporal_states.peek() else {
+                break;
+            };
+            if temporal_state.0.next_tick <= clock.now() {
+                let temporal_state = state.temporal_states.pop().unwrap().0;
+                trace!(
+                    "Adding trigger {} to the dispatch queue",
+                    temporal_state.trigger_id,
+                );
+                dispatch_queue.push(temporal_state);
+            } else {
+                // The rest is in the future.
+                break;
+            }
+        }
+        if dispatch_queue.len() == max_triggers_per_tick as usize {
+            warn!(
+                "Reached max dispatches per tick ({}), some triggers will be \
+                 deferred",
+                max_triggers_per_tick
+            );
+        }
+
+        /*
+         * 2. Dispatch a new event (async) for each of those triggers.
+         */
+        trace!(
+            "[TICK] temporal_state {} and dispatch_queue {}",
+            state.temporal_states.len(),
+            dispatch_queue.len(),
+        );
+        for trigger in dispatch_queue.iter() {
+            let id = trigger.trigger_id.clone();
+            let scheduled_time = trigger.next_tick;
+            let lag = clock
+                .now()
+                .signed_duration_since(scheduled_time)
+                .num_milliseconds() as f64;
+            histogram!("spinner.dispatch_lag_seconds", lag / 1000.0);
+            if lag > 10_000.0 {
+                warn!(
+                    "Spinner lag has exceeded 10s, you might need to increase \
+                     `max_triggers_per_tick` (current \
+                     '{max_triggers_per_tick}') or reduce \
+                     `spinner_yield_max_ms` (current '{}')",
+                    state.config.spinner_yield_max_ms
+                );
+            }
+
+            if let Some(handle) = self.dispatch(&id, scheduled_time) {
+                state.inflight_dispatches.push(InflightDispatch {
+                    trigger_id: id.clone(),
+                    ran_at: clock.now(),
+                    handle,
+                });
+            }
+        }
+        /*
+         * 3. Write-Lock ActiveTriggerMap
+         * 4. Calculate temporal state for the removed triggers and
+         *    re-insert.
+         */
+        if !dispatch_queue.is_empty() {
+            // Maybe individual triggers need to be advanced.
+            let mut w = self.triggers.write().unwrap();
+            for mut trigger in dispatch_queue {
+                // Those states that yield None in advance will be dropped.
+                if let Some(next_tick) = w.advance(&trigger.trigger_id) {
+                    trace!(
+                        "Trigger {} next trigger time is {}",
+                        trigger.trigger_id,
+                        next_tick
+                    );
+                    // _can_ be avoided if the trigger map is dirty, but
+                    // will keep it for simplicity.
+                    trigger.next_tick = next_tick;
+                    state.temporal_states.push(Reverse(trigger));
+                } else {
+                    // This trigger is no longer active. We should flush and
+                    // compact.
+                    w.add_to_awaiting_db_flush(trigger.trigger_id);
+                }
+            }
+        }
+        /*
+         * 5. Check if we have dirty triggers, fetch their contents.
+         * 6. Rebuild temporal state if needed.
+         */
+        // Is the state dirty?
+        if self.triggers.read().unwrap().is_dirty() {
+            trace!("Triggers updated, reloading...");
+            // TODO only reload the triggers that has been updated. Or
+            // re-construct the entire temporal state.
+            let mut w = self.triggers.write().unwrap();
+            state.temporal_states = w.build_temporal_state();
+            gauge!(
+                "spinner.active_triggers_total",
+                state.temporal_states.len() as f64
+            );
+        }
+    }
+
     fn dispatch(
         &self,
         trigger_id: &TriggerId,
+        scheduled_time: DateTime<Tz>,
     ) -> Option<tokio::task::JoinHandle<Result<(), DispatchError>>> {
         let trigger = {
             let r = self.triggers.read().unwrap();
@@ -324,6 +356,17 @@ impl Spinner {
             trigger.clone()
         };
 
+        if trigger
+            .skipped_occurrences
+            .contains(&scheduled_time.with_timezone(&Utc))
+        {
+            info!(
+                "Skipping the occurrence {} of trigger {}",
+                scheduled_time, trigger.id
+            );
+            return None;
+        }
+
         if trigger.status == Status::Paused {
             let handle = self.tokio_handle.spawn(
                 async move {
@@ -338,22 +381,14 @@ impl Spinner {
             );
             return Some(handle);
         }
-        // TODO:
-        // Can we dispatch? if we can't, we should retry a few times, then we
-        // drop into  failsafe mode.
-        // In failsafe mode:
-        //  - Stop the if spinner
-        //  - Run a health check loop to find alive dispatchers.
-        //  - Once a live dispatcher is found, re-init the spinner and continue.
+        // Dispatches that fail to reach the dispatcher are retried from the
+        // outbox by the controller.
         let provider = self.dispatcher_clients.clone();
-        // TODO: Think about when should we persist the fact that we dispatched.
+        let outbox = self.outbox.clone();
+        let clock = self.context.clock();
         let handle = self.tokio_handle.spawn(
             async move {
-                // TODO add a few retries if not logic error.
-                // We are throwing away the returned Run object to save
-                // memory we are only interested in knowing if
-                // we errored or not.
-                dispatch::dispatch(
+                dispatch::dispatch_tick(
                     RequestContext::new(
                         // We generate a new request Id as this is a
                         // system-generated request.
@@ -362,13 +397,167 @@ impl Spinner {
                     ),
                     trigger,
                     provider,
-                    DispatchMode::Async,
+                    outbox,
+                    scheduled_time.with_timezone(&Utc),
+                    clock,
                 )
                 .await
-                .map(|_| ())
             }
             .instrument(tracing::Span::current()),
         );
         Some(handle)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use lib::service::CronbackService;
+    use lib::ConfigBuilder;
+
+    use super::*;
+    use crate::scheduler::db_model::schedule::{Recurring, Schedule};
+    use crate::scheduler::db_model::Trigger;
+
+    fn build_trigger(cron: &str, created_at: DateTime<Utc>) -> Trigger {
+        let project = ProjectId::generate();
+        Trigger {
+            id: TriggerId::generate(&project).into(),
+            project_id: project,
+            name: "sample-trigger".to_owned(),
+            description: None,
+            created_at,
+            updated_at: None,
+            action: Action::Webhook(Webhook {
+                url: "http://example.com".to_owned(),
+                http_method: HttpMethod::Get,
+                timeout_s: std::time::Duration::from_secs(30),
+                retry: None,
+                success_criteria: None,
+            }),
+            payload: None,
+            status: Status::Scheduled,
+            schedule: Some(Schedule::Recurring(Recurring {
+                cron: cron.to_owned(),
+                timezone: "Europe/London".into(),
+                limit: None,
+                remaining: None,
+                misfire_policy: None,
+                max_missed_runs: None,
+                start_at: None,
+                end_at: None,
+                jitter_s: None,
+                exclude_calendars: vec![],
+                calendars: vec![],
+                dst_policy: None,
+            })),
+            last_ran_at: None,
+            concurrency_policy: Default::default(),
+            upstream_trigger_id: None,
+            paused_until: None,
+            skipped_occurrences: Default::default(),
+            labels: Default::default(),
+            retired_at: None,
+        }
+    }
+
+    /// Runs one tick of the spinner and waits for the dispatches it started.
+    async fn tick(spinner: &Spinner, state: &mut SpinnerState) {
+        spinner.tick(state).await;
+        for inflight in state.inflight_dispatches.drain(..) {
+            inflight.handle.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn manual_clock_drives_the_spinner_test() -> anyhow::Result<()> {
+        let config = ConfigBuilder::default()
+            .register_service::<SchedulerService>()
+            .build_once()?;
+        // Clocks in London go forward at 1am on the 26th of March 2023.
+        let start = Utc.with_ymd_and_hms(2023, 3, 25, 0, 0, 0).unwrap();
+        let clock = Arc::new(ManualClock::new(start));
+        let context =
+            SchedulerService::make_context(config, Default::default())
+                .with_clock(clock.clone());
+        let triggers =
+            Arc::new(RwLock::new(ActiveTriggerMap::new(context.clock())));
+        // No dispatcher is running, so every dispatch lands in the outbox
+        // with the time it was scheduled for.
+        let dispatcher_clients =
+            Arc::new(GrpcClientProvider::new(context.config().clone()));
+        let outbox =
+            OutboxStore::new(SchedulerService::in_memory_database().await?);
+        let spinner = Spinner::new(
+            context.clone(),
+            triggers.clone(),
+            dispatcher_clients,
+            outbox.clone(),
+        );
+        let mut state = SpinnerState::new(&context);
+
+        // 1:30am London time every day.
+        triggers
+            .write()
+            .unwrap()
+            .add_or_update(build_trigger("0 30 1 * * *", start), false)?;
+        tick(&spinner, &mut state).await;
+
+        let at = |d, h, m| Utc.with_ymd_and_hms(2023, 3, d, h, m, 0).unwrap();
+        let dispatched = || {
+            let outbox = outbox.clone();
+            async move {
+                outbox
+                    .get_due(at(31, 0, 0), 100)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|e| (e.scheduled_at, e.created_at))
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert!(dispatched().await.is_empty());
+
+        // Nothing is due until the clock gets there.
+        clock.set(at(25, 1, 29));
+        tick(&spinner, &mut state).await;
+        assert!(dispatched().await.is_empty());
+
+        clock.set(at(25, 1, 30));
+        tick(&spinner, &mut state).await;
+        assert_eq!(vec![(at(25, 1, 30), at(25, 1, 30))], dispatched().await);
+
+        // 1:30am doesn't exist on the 26th, the tick runs right after the
+        // gap at 2:30am BST.
+        clock.set(at(26, 1, 30));
+        tick(&spinner, &mut state).await;
+        assert_eq!(
+            vec![
+                (at(25, 1, 30), at(25, 1, 30)),
+                (at(26, 1, 30), at(26, 1, 30)),
+            ],
+            dispatched().await
+        );
+
+        // The wall clock jumps backwards a day, the ticks that already ran
+        // don't run again.
+        clock.set(at(25, 1, 30));
+        tick(&spinner, &mut state).await;
+        clock.set(at(26, 1, 30));
+        tick(&spinner, &mut state).await;
+        assert_eq!(2, dispatched().await.len());
+
+        // From then on, 1:30am is 0:30am UTC.
+        clock.set(at(27, 0, 30));
+        tick(&spinner, &mut state).await;
+        assert_eq!(
+            vec![
+                (at(25, 1, 30), at(25, 1, 30)),
+                (at(26, 1, 30), at(26, 1, 30)),
+                (at(27, 0, 30), at(27, 0, 30)),
+            ],
+            dispatched().await
+        );
+        Ok(())
+    }
+}
@@ -1,15 +1,27 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
 use lib::prelude::*;
-use proto::common::PaginationIn;
+use proto::common::{PaginationIn, PaginationOut};
+use sea_orm::sea_query::{Expr, Func, OnConflict, Query};
 use sea_orm::{
     ActiveModelTrait,
     ColumnTrait,
+    Condition,
+    ConnectionTrait,
+    DatabaseBackend,
+    DbErr,
     EntityTrait,
+    Iterable,
     QueryFilter,
+    QueryOrder,
     QuerySelect,
+    TransactionTrait,
 };
 
-use super::db_model::triggers::{self, Status};
-use super::db_model::{Trigger, Triggers};
+use super::db_model::revisions::{self, TriggerSnapshot};
+use super::db_model::triggers::{self, LabelSelector, Status};
+use super::db_model::{Trigger, TriggerRevision, TriggerRevisions, Triggers};
 
 pub type TriggerStoreError = DatabaseError;
 
@@ -32,21 +44,72 @@ impl TriggerStore {
         Ok(())
     }
 
+    /// Installs the trigger and records it as its first revision in the same
+    /// transaction.
+    pub async fn install_trigger_with_revision(
+        &self,
+        trigger: Trigger,
+        author: Option<String>,
+    ) -> Result<TriggerRevision, TriggerStoreError> {
+        let txn = self.db.orm.begin().await?;
+        let active_model: triggers::ActiveModel = trigger.clone().into();
+        active_model.insert(&txn).await?;
+        let revision = insert_revision(&txn, &trigger, author).await?;
+        txn.commit().await?;
+        Ok(revision)
+    }
+
     pub async fn update_trigger(
         &self,
         trigger: Trigger,
     ) -> Result<(), TriggerStoreError> {
-        let project = trigger.project_id.clone();
+        update_trigger(&self.db.orm, trigger).await
+    }
+
+    /// Updates the trigger and records it as its next revision in the same
+    /// transaction. Triggers installed before revisions existed have none, so
+    /// `previous` is recorded first to keep the version being replaced.
+    pub async fn update_trigger_with_revision(
+        &self,
+        trigger: Trigger,
+        previous: &Trigger,
+        author: Option<String>,
+    ) -> Result<TriggerRevision, TriggerStoreError> {
+        let txn = self.db.orm.begin().await?;
+        update_trigger(&txn, trigger.clone()).await?;
+        if latest_revision(&txn, &trigger.project_id, &trigger.id)
+            .await?
+            .is_none()
+        {
+            insert_revision(&txn, previous, None).await?;
+        }
+        let revision = insert_revision(&txn, &trigger, author).await?;
+        txn.commit().await?;
+        Ok(revision)
+    }
+
+    /// Installs a trigger that was moved from another cell, overwriting it
+    /// if a previous attempt already installed it.
+    pub async fn import_trigger(
+        &self,
+        trigger: Trigger,
+    ) -> Result<(), TriggerStoreError> {
         let active_model: triggers::ActiveModel = trigger.into();
-        // Mark all the fields as dirty
-        let active_model = active_model.reset_all();
-        Triggers::update(active_model)
-            .filter(triggers::Column::ProjectId.eq(project))
-            .exec(&self.db.orm)
+        Triggers::insert(active_model)
+            .on_conflict(
+                OnConflict::columns([
+                    triggers::Column::Id,
+                    triggers::Column::ProjectId,
+                ])
+                .update_columns(triggers::Column::iter())
+                .to_owned(),
+            )
+            .exec_without_returning(&self.db.orm)
             .await?;
         Ok(())
     }
 
+    /// Deletes the trigger. Its revisions are history and are kept.
     pub async fn delete_trigger(
         &self,
         project: &ValidShardedId<ProjectId>,
@@ -58,6 +121,9 @@ impl TriggerStore {
         Ok(())
     }
 
+    /// Deletes all the triggers of a project along with their revisions. This
+    /// is the one exception to revisions being append-only: the project is
+    /// gone, and so is its history.
     pub async fn delete_triggers_by_project(
         &self,
         project: &ProjectId,
@@ -66,9 +132,62 @@ impl TriggerStore {
             .filter(triggers::Column::ProjectId.eq(project.clone()))
             .exec(&self.db.orm)
             .await?;
+        TriggerRevisions::delete_many()
+            .filter(revisions::Column::ProjectId.eq(project.clone()))
+            .exec(&self.db.orm)
+            .await?;
         Ok(())
     }
 
+    /// Deletes the triggers and revisions of all projects that live in the
+    /// given shards. Only meant for shards that were handed over to another
+    /// cell, which now holds the revisions, so no history is lost.
+    pub async fn delete_shards(
+        &self,
+        shards: &HashSet<Shard>,
+    ) -> Result<(), TriggerStoreError> {
+        let mut projects: Vec<_> = self
+            .get_triggers_by_shards(shards)
+            .await?
+            .into_iter()
+            .map(|t| t.project_id)
+            .chain(
+                self.get_revisions_by_shards(shards)
+                    .await?
+                    .into_iter()
+                    .map(|r| r.project_id),
+            )
+            .collect();
+        projects.sort();
+        projects.dedup();
+        Triggers::delete_many()
+            .filter(triggers::Column::ProjectId.is_in(projects.clone()))
+            .exec(&self.db.orm)
+            .await?;
+        TriggerRevisions::delete_many()
+            .filter(revisions::Column::ProjectId.is_in(projects))
+            .exec(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    /// All triggers, regardless of their status, of projects that live in the
+    /// given shards.
+    pub async fn get_triggers_by_shards(
+        &self,
+        shards: &HashSet<Shard>,
+    ) -> Result<Vec<Trigger>, TriggerStoreError> {
+        // Shards are only encoded in the ids, moving shards is rare enough
+        // that we can afford scanning the table.
+        let res = Triggers::find()
+            .all(&self.db.orm)
+            .await?
+            .into_iter()
+            .filter(|t| shards.contains(&t.project_id.shard()))
+            .collect();
+        Ok(res)
+    }
+
     pub async fn get_all_active_triggers(
         &self,
     ) -> Result<Vec<Trigger>, TriggerStoreError> {
@@ -82,25 +201,74 @@ impl TriggerStore {
         Ok(res)
     }
 
+    /// Cancelled and expired triggers of all projects, `limit` at a time in
+    /// the order of their ids. The next batch starts after the last trigger
+    /// of the previous one.
+    pub async fn get_retired_triggers(
+        &self,
+        after: Option<&TriggerId>,
+        limit: u64,
+    ) -> Result<Vec<Trigger>, TriggerStoreError> {
+        let mut query = Triggers::find()
+            .filter(
+                triggers::Column::Status
+                    .is_in([Status::Cancelled, Status::Expired]),
+            )
+            .order_by_asc(triggers::Column::Id)
+            .limit(limit);
+        if let Some(after) = after {
+            query = query.filter(triggers::Column::Id.gt(after.clone()));
+        }
+        Ok(query.all(&self.db.orm).await?)
+    }
+
     pub async fn get_triggers_by_project(
         &self,
         project: &ProjectId,
         pagination: PaginationIn,
         statuses: Option<Vec<Status>>,
+        label_selectors: Vec<LabelSelector>,
     ) -> Result<PaginatedResponse<Trigger>, TriggerStoreError> {
         let mut query = Triggers::find()
-            .filter(triggers::Column::ProjectId.eq(project.value()))
-            .with_pagination(&pagination);
+            .filter(triggers::Column::ProjectId.eq(project.value()));
 
         if let Some(statuses) = statuses {
             query = query.filter(triggers::Column::Status.is_in(statuses));
         }
 
-        let res = query.all(&self.db.orm).await?;
+        let backend = self.db.orm.get_database_backend();
+        for selector in label_selectors {
+            query = query.filter(label_condition(backend, selector));
+        }
 
+        let res = query.with_pagination(&pagination).all(&self.db.orm).await?;
         Ok(PaginatedResponse::paginate(res, &pagination))
     }
 
+    /// All the triggers of the project whose name starts with `name_prefix`
+    /// if set.
+    pub async fn find_triggers(
+        &self,
+        project: &ProjectId,
+        name_prefix: Option<&str>,
+    ) -> Result<Vec<Trigger>, TriggerStoreError> {
+        let mut query = Triggers::find()
+            .filter(triggers::Column::ProjectId.eq(project.value()))
+            .order_by_desc(triggers::Column::Id);
+
+        if let Some(prefix) = name_prefix {
+            query = query.filter(triggers::Column::Name.starts_with(prefix));
+        }
+
+        let mut res = query.all(&self.db.orm).await?;
+        if let Some(prefix) = name_prefix {
+            // LIKE is case insensitive on some databases and treats `_` as a
+            // wildcard, we only want exact prefixes.
+            res.retain(|t| t.name.starts_with(prefix));
+        }
+        Ok(res)
+    }
+
     pub async fn get_trigger_by_name(
         &self,
         project_id: &ProjectId,
@@ -114,6 +282,34 @@ impl TriggerStore {
         Ok(res)
     }
 
+    pub async fn get_trigger_by_id(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+    ) -> Result<Option<Trigger>, TriggerStoreError> {
+        let res = Triggers::find_by_id((trigger_id.clone(), project.clone()))
+            .one(&self.db.orm)
+            .await?;
+        Ok(res)
+    }
+
+    /// Triggers that run on completion of the trigger `upstream_trigger_id`.
+    pub async fn get_dependent_triggers(
+        &self,
+        project: &ProjectId,
+        upstream_trigger_id: &TriggerId,
+    ) -> Result<Vec<Trigger>, TriggerStoreError> {
+        let res = Triggers::find()
+            .filter(triggers::Column::ProjectId.eq(project.clone()))
+            .filter(
+                triggers::Column::UpstreamTriggerId
+                    .eq(upstream_trigger_id.value()),
+            )
+            .all(&self.db.orm)
+            .await?;
+        Ok(res)
+    }
+
     pub async fn find_trigger_id_for_name(
         &self,
         project: &ProjectId,
@@ -146,6 +342,218 @@ impl TriggerStore {
 
         Ok(res)
     }
+
+    /// Records the trigger as its next revision.
+    pub async fn add_revision(
+        &self,
+        trigger: &Trigger,
+        author: Option<String>,
+    ) -> Result<TriggerRevision, TriggerStoreError> {
+        insert_revision(&self.db.orm, trigger, author).await
+    }
+
+    pub async fn get_latest_revision(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+    ) -> Result<Option<i64>, TriggerStoreError> {
+        latest_revision(&self.db.orm, project, trigger_id).await
+    }
+
+    /// Revisions of a trigger, newest first. The cursor of a page is the
+    /// revision number it starts at.
+    pub async fn get_revisions(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+        pagination: &PaginationIn,
+    ) -> Result<PaginatedResponse<TriggerRevision>, TriggerStoreError> {
+        let mut query = TriggerRevisions::find()
+            .filter(revisions::Column::ProjectId.eq(project.clone()))
+            .filter(revisions::Column::TriggerId.eq(trigger_id.clone()))
+            .order_by_desc(revisions::Column::Revision)
+            // One more than the limit to know if there is a next page.
+            .limit(Some(pagination.paginated_query_limit()));
+        if let Some(ref cursor) = pagination.cursor {
+            // A cursor that isn't a revision number can't start any page.
+            let cursor = cursor.parse::<i64>().unwrap_or(0);
+            query = query.filter(revisions::Column::Revision.lte(cursor));
+        }
+        let mut res = query.all(&self.db.orm).await?;
+
+        let next = res.get(pagination.limit()).map(|r| r.revision);
+        res.truncate(pagination.limit());
+        Ok(PaginatedResponse::from(
+            res,
+            PaginationOut {
+                has_more: next.is_some(),
+                next_cursor: next.map(|r| r.to_string()),
+            },
+        ))
+    }
+
+    /// All revisions of the triggers of projects that live in the given
+    /// shards.
+    pub async fn get_revisions_by_shards(
+        &self,
+        shards: &HashSet<Shard>,
+    ) -> Result<Vec<TriggerRevision>, TriggerStoreError> {
+        let res = TriggerRevisions::find()
+            .all(&self.db.orm)
+            .await?
+            .into_iter()
+            .filter(|r| shards.contains(&r.project_id.shard()))
+            .collect();
+        Ok(res)
+    }
+
+    /// Installs a revision that was moved from another cell, a revision that
+    /// already exists is left untouched.
+    pub async fn import_revision(
+        &self,
+        revision: TriggerRevision,
+    ) -> Result<(), TriggerStoreError> {
+        let active_model: revisions::ActiveModel = revision.into();
+        TriggerRevisions::insert(active_model)
+            .on_conflict(
+                OnConflict::columns([
+                    revisions::Column::TriggerId,
+                    revisions::Column::Revision,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(&self.db.orm)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_revision(
+        &self,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+        revision: i64,
+    ) -> Result<Option<TriggerRevision>, TriggerStoreError> {
+        let res = TriggerRevisions::find_by_id((trigger_id.clone(), revision))
+            .filter(revisions::Column::ProjectId.eq(project.clone()))
+            .one(&self.db.orm)
+            .await?;
+        Ok(res)
+    }
+}
+
+async fn update_trigger<C: ConnectionTrait>(
+    db: &C,
+    trigger: Trigger,
+) -> Result<(), TriggerStoreError> {
+    let project = trigger.project_id.clone();
+    let active_model: triggers::ActiveModel = trigger.into();
+    // Mark all the fields as dirty
+    let active_model = active_model.reset_all();
+    Triggers::update(active_model)
+        .filter(triggers::Column::ProjectId.eq(project))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+async fn latest_revision<C: ConnectionTrait>(
+    db: &C,
+    project: &ValidShardedId<ProjectId>,
+    trigger_id: &TriggerId,
+) -> Result<Option<i64>, TriggerStoreError> {
+    let res: Option<Option<i64>> = TriggerRevisions::find()
+        .select_only()
+        .column_as(revisions::Column::Revision.max(), "revision")
+        .filter(revisions::Column::ProjectId.eq(project.clone()))
+        .filter(revisions::Column::TriggerId.eq(trigger_id.clone()))
+        .into_tuple()
+        .one(db)
+        .await?;
+    Ok(res.flatten())
+}
+
+async fn insert_revision<C: ConnectionTrait>(
+    db: &C,
+    trigger: &Trigger,
+    author: Option<String>,
+) -> Result<TriggerRevision, TriggerStoreError> {
+    // The revision number is picked by the insert itself rather than read
+    // beforehand. Writers that still race on it are rejected by the unique
+    // index instead of overwriting each other's history.
+    let values = Query::select()
+        .expr(Expr::val(trigger.id.clone()))
+        .expr(
+            Expr::expr(Func::coalesce([
+                Expr::col(revisions::Column::Revision).max(),
+                Expr::val(0).into(),
+            ]))
+            .add(1),
+        )
+        .expr(Expr::val(trigger.project_id.clone()))
+        .expr(Expr::val(author))
+        .expr(Expr::val(TriggerSnapshot(trigger.clone())))
+        .expr(Expr::val(Utc::now()))
+        .from(TriggerRevisions)
+        .and_where(
+            Expr::col(revisions::Column::ProjectId)
+                .eq(trigger.project_id.clone()),
+        )
+        .and_where(
+            Expr::col(revisions::Column::TriggerId).eq(trigger.id.clone()),
+        )
+        .to_owned();
+    let insert = Query::insert()
+        .into_table(TriggerRevisions)
+        .columns([
+            revisions::Column::TriggerId,
+            revisions::Column::Revision,
+            revisions::Column::ProjectId,
+            revisions::Column::Author,
+            revisions::Column::Trigger,
+            revisions::Column::CreatedAt,
+        ])
+        .select_from(values)
+        .expect("selected values match the inserted columns")
+        .to_owned();
+    db.execute(db.get_database_backend().build(&insert)).await?;
+
+    let revision = TriggerRevisions::find()
+        .filter(revisions::Column::ProjectId.eq(trigger.project_id.clone()))
+        .filter(revisions::Column::TriggerId.eq(trigger.id.clone()))
+        .order_by_desc(revisions::Column::Revision)
+        .one(db)
+        .await?
+        .ok_or(DbErr::RecordNotInserted)?;
+    Ok(revision)
+}
+
+/// The condition matching `selector` against the JSON `labels` column.
+/// Label keys are validated to alphanumerics, '-', '_', '.' and '/', so
+/// quoting them in the JSON path is enough.
+fn label_condition(
+    backend: DatabaseBackend,
+    selector: LabelSelector,
+) -> Condition {
+    let label = match backend {
+        | DatabaseBackend::Postgres => {
+            Expr::cust_with_values(r#"("labels" ->> ?)"#, [selector.key])
+        }
+        | _ => {
+            Expr::cust_with_values(
+                r#"json_extract("labels", ?)"#,
+                [format!(r#"$."{}""#, selector.key)],
+            )
+        }
+    };
+    if selector.not_equal {
+        // A missing label doesn't equal any value.
+        Condition::any()
+            .add(Expr::expr(label.clone()).is_null())
+            .add(Expr::expr(label).ne(selector.value))
+    } else {
+        Condition::all().add(Expr::expr(label).eq(selector.value))
+    }
 }
 
 #[cfg(test)]
@@ -155,6 +563,7 @@ mod tests {
     use chrono::{Timelike, Utc};
 
     use super::*;
+    use crate::scheduler::db_model::triggers::Labels;
     use crate::scheduler::SchedulerService;
 
     fn build_trigger(
@@ -181,12 +590,30 @@ mod tests {
                 http_method: HttpMethod::Get,
                 timeout_s: Duration::from_secs(5),
                 retry: None,
+                success_criteria: None,
             }),
             status,
             last_ran_at: None,
+            concurrency_policy: Default::default(),
+            upstream_trigger_id: None,
+            paused_until: None,
+            skipped_occurrences: Default::default(),
+            labels: Default::default(),
+            retired_at: None,
         }
     }
 
+    async fn revisions_of(
+        store: &TriggerStore,
+        project: &ValidShardedId<ProjectId>,
+        trigger_id: &TriggerId,
+    ) -> Result<Vec<TriggerRevision>, TriggerStoreError> {
+        let res = store
+            .get_revisions(project, trigger_id, &PaginationIn::default())
+            .await?;
+        Ok(res.data)
+    }
+
     #[tokio::test]
     async fn test_trigger_store() -> anyhow::Result<()> {
         let db = SchedulerService::in_memory_database().await?;
@@ -241,7 +668,12 @@ mod tests {
 
         // Test get by owner
         let mut results = store
-            .get_triggers_by_project(&project1, PaginationIn::default(), None)
+            .get_triggers_by_project(
+                &project1,
+                PaginationIn::default(),
+                None,
+                vec![],
+            )
             .await?;
         let mut expected = vec![t1.clone(), t2.clone()];
         expected.sort_by(|a, b| a.id.cmp(&b.id));
@@ -264,10 +696,46 @@ mod tests {
                 &project1,
                 PaginationIn::default(),
                 Some(vec![Status::Paused]),
+                vec![],
             )
             .await?;
         assert_eq!(results.data, vec![t2.clone()]);
 
+        // Test find by name prefix
+        let t6 = build_trigger("t_6", project1.clone(), Status::Paused);
+        store.install_trigger(t6.clone()).await?;
+        assert_eq!(
+            store.find_triggers(&project1, Some("t_")).await?,
+            vec![t6.clone()]
+        );
+        let mut results = store.find_triggers(&project1, Some("t")).await?;
+        results.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut expected = vec![t1.clone(), t2.clone(), t6.clone()];
+        expected.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(results, expected);
+        assert_eq!(store.find_triggers(&project2, None).await?.len(), 2);
+        store.delete_trigger(&project1, &t6.id).await?;
+
+        // Test get by id
+        assert_eq!(
+            store.get_trigger_by_id(&project1, &t2.id).await?,
+            Some(t2.clone())
+        );
+        assert_eq!(store.get_trigger_by_id(&project2, &t2.id).await?, None);
+
+        // Test get dependent triggers
+        let mut t5 = build_trigger("t5", project1.clone(), Status::OnDemand);
+        t5.upstream_trigger_id = Some(t2.id.clone());
+        store.install_trigger(t5.clone()).await?;
+        assert_eq!(
+            store.get_dependent_triggers(&project1, &t2.id).await?,
+            vec![t5.clone()]
+        );
+        assert!(store
+            .get_dependent_triggers(&project1, &t1.id)
+            .await?
+            .is_empty());
+
         // Test update trigger
         let mut new_t1 = t1.clone();
         new_t1.status = Status::Expired;
@@ -304,4 +772,381 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_trigger_labels() -> anyhow::Result<()> {
+        let db = SchedulerService::in_memory_database().await?;
+        let store = TriggerStore::new(db);
+
+        let project1 = ProjectId::generate();
+        let labels = |pairs: &[(&str, &str)]| {
+            Labels(
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            )
+        };
+        let selector = |key: &str, value: &str, not_equal: bool| {
+            LabelSelector {
+                key: key.to_string(),
+                value: value.to_string(),
+                not_equal,
+            }
+        };
+        let names = |results: &PaginatedResponse<Trigger>| {
+            let mut names: Vec<_> =
+                results.data.iter().map(|t| t.name.clone()).collect();
+            names.sort();
+            names
+        };
+
+        let mut t1 = build_trigger("t1", project1.clone(), Status::Scheduled);
+        t1.labels = labels(&[("team", "billing"), ("env", "prod")]);
+        let mut t2 = build_trigger("t2", project1.clone(), Status::Scheduled);
+        t2.labels = labels(&[("team", "billing"), ("env", "staging")]);
+        let mut t3 = build_trigger("t3", project1.clone(), Status::Paused);
+        t3.labels = labels(&[("team", "search")]);
+        store.install_trigger(t1.clone()).await?;
+        store.install_trigger(t2.clone()).await?;
+        store.install_trigger(t3.clone()).await?;
+
+        // Labels are persisted.
+        assert_eq!(
+            store.get_trigger_by_name(&project1, &t1.name).await?,
+            Some(t1.clone())
+        );
+
+        let results = store
+            .get_triggers_by_project(
+                &project1,
+                PaginationIn::default(),
+                None,
+                vec![selector("team", "billing", false)],
+            )
+            .await?;
+        assert_eq!(names(&results), vec!["t1", "t2"]);
+
+        // Triggers without the label match inequality selectors.
+        let results = store
+            .get_triggers_by_project(
+                &project1,
+                PaginationIn::default(),
+                None,
+                vec![selector("env", "staging", true)],
+            )
+            .await?;
+        assert_eq!(names(&results), vec!["t1", "t3"]);
+
+        // All selectors must match.
+        let results = store
+            .get_triggers_by_project(
+                &project1,
+                PaginationIn::default(),
+                None,
+                vec![
+                    selector("team", "billing", false),
+                    selector("env", "staging", true),
+                ],
+            )
+            .await?;
+        assert_eq!(results.data, vec![t1.clone()]);
+
+        // Selectors combine with status filters.
+        let results = store
+            .get_triggers_by_project(
+                &project1,
+                PaginationIn::default(),
+                Some(vec![Status::Paused]),
+                vec![selector("env", "staging", true)],
+            )
+            .await?;
+        assert_eq!(results.data, vec![t3.clone()]);
+
+        // Pagination applies to the matching triggers.
+        let results = store
+            .get_triggers_by_project(
+                &project1,
+                PaginationIn {
+                    limit: 1,
+                    cursor: None,
+                },
+                None,
+                vec![selector("team", "billing", false)],
+            )
+            .await?;
+        assert_eq!(results.data.len(), 1);
+        assert!(results.pagination.has_more);
+        let first_page = results.data[0].name.clone();
+        let results = store
+            .get_triggers_by_project(
+                &project1,
+                PaginationIn {
+                    limit: 1,
+                    cursor: results.pagination.next_cursor,
+                },
+                None,
+                vec![selector("team", "billing", false)],
+            )
+            .await?;
+        assert_eq!(results.data.len(), 1);
+        assert!(!results.pagination.has_more);
+        let mut pages = vec![first_page, results.data[0].name.clone()];
+        pages.sort();
+        assert_eq!(pages, vec!["t1", "t2"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_trigger_revisions() -> anyhow::Result<()> {
+        let db = SchedulerService::in_memory_database().await?;
+        let store = TriggerStore::new(db);
+
+        let project1 = ProjectId::generate();
+        let project2 = ProjectId::generate();
+        let t1 = build_trigger("t1", project1.clone(), Status::Scheduled);
+        let t2 = build_trigger("t2", project1.clone(), Status::Scheduled);
+        store.install_trigger(t1.clone()).await?;
+        store.install_trigger(t2.clone()).await?;
+        assert_eq!(store.get_latest_revision(&project1, &t1.id).await?, None);
+
+        let rev1 = store.add_revision(&t1, Some("key1".to_string())).await?;
+        assert_eq!(rev1.revision, 1);
+        let mut new_t1 = t1.clone();
+        new_t1.description = Some("updated".to_string());
+        let rev2 = store.add_revision(&new_t1, None).await?;
+        assert_eq!(rev2.revision, 2);
+        // Revisions are numbered per trigger.
+        assert_eq!(store.add_revision(&t2, None).await?.revision, 1);
+
+        let revisions = revisions_of(&store, &project1, &t1.id).await?;
+        assert_eq!(
+            revisions.iter().map(|r| r.revision).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        assert_eq!(revisions[1].author.as_deref(), Some("key1"));
+        assert_eq!(revisions[1].trigger.0, t1);
+        assert_eq!(revisions[0].trigger.0, new_t1);
+
+        // One revision per page.
+        let page = PaginationIn {
+            cursor: None,
+            limit: 1,
+        };
+        let res = store.get_revisions(&project1, &t1.id, &page).await?;
+        assert_eq!(res.data.len(), 1);
+        assert_eq!(res.data[0].revision, 2);
+        assert!(res.pagination.has_more);
+        assert_eq!(res.pagination.next_cursor.as_deref(), Some("1"));
+        let page = PaginationIn {
+            cursor: res.pagination.next_cursor,
+            limit: 1,
+        };
+        let res = store.get_revisions(&project1, &t1.id, &page).await?;
+        assert_eq!(res.data.len(), 1);
+        assert_eq!(res.data[0].revision, 1);
+        assert!(!res.pagination.has_more);
+        assert_eq!(res.pagination.next_cursor, None);
+
+        assert_eq!(
+            store
+                .get_revision(&project1, &t1.id, 1)
+                .await?
+                .map(|r| r.trigger.0),
+            Some(t1.clone())
+        );
+        assert_eq!(store.get_revision(&project1, &t1.id, 3).await?, None);
+        assert_eq!(
+            store.get_latest_revision(&project1, &t1.id).await?,
+            Some(2)
+        );
+        // Wrong project.
+        assert_eq!(store.get_revision(&project2, &t1.id, 1).await?, None);
+        assert!(revisions_of(&store, &project2, &t1.id).await?.is_empty());
+
+        // Revisions outlive their trigger.
+        store.delete_trigger(&project1, &t1.id).await?;
+        assert_eq!(revisions_of(&store, &project1, &t1.id).await?.len(), 2);
+        // Unless the whole project goes away.
+        store.delete_triggers_by_project(&project1).await?;
+        assert!(revisions_of(&store, &project1, &t1.id).await?.is_empty());
+        assert!(revisions_of(&store, &project1, &t2.id).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_trigger_with_revision() -> anyhow::Result<()> {
+        let db = SchedulerService::in_memory_database().await?;
+        let store = TriggerStore::new(db);
+
+        let project = ProjectId::generate();
+        let t1 = build_trigger("t1", project.clone(), Status::Scheduled);
+        let rev1 = store
+            .install_trigger_with_revision(t1.clone(), Some("key1".to_string()))
+            .await?;
+        assert_eq!(rev1.revision, 1);
+        assert_eq!(rev1.author.as_deref(), Some("key1"));
+        assert_eq!(rev1.trigger.0, t1);
+
+        // A failed install leaves no revision behind.
+        assert!(matches!(
+            store.install_trigger_with_revision(t1.clone(), None).await,
+            Err(TriggerStoreError::DuplicateRecord)
+        ));
+        assert_eq!(store.get_latest_revision(&project, &t1.id).await?, Some(1));
+
+        let mut new_t1 = t1.clone();
+        new_t1.description = Some("updated".to_string());
+        let rev2 = store
+            .update_trigger_with_revision(new_t1.clone(), &t1, None)
+            .await?;
+        assert_eq!(rev2.revision, 2);
+        assert_eq!(
+            store
+                .get_trigger_by_id(&project, &t1.id)
+                .await?
+                .and_then(|t| t.description),
+            Some("updated".to_string())
+        );
+
+        // Triggers installed before revisions existed get the version being
+        // replaced recorded first.
+        let t2 = build_trigger("t2", project.clone(), Status::Scheduled);
+        store.install_trigger(t2.clone()).await?;
+        let mut new_t2 = t2.clone();
+        new_t2.description = Some("updated".to_string());
+        let rev = store
+            .update_trigger_with_revision(new_t2.clone(), &t2, None)
+            .await?;
+        assert_eq!(rev.revision, 2);
+        let revisions = revisions_of(&store, &project, &t2.id).await?;
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].trigger.0, new_t2);
+        assert_eq!(revisions[1].trigger.0, t2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_trigger_store_shards() -> anyhow::Result<()> {
+        let source =
+            TriggerStore::new(SchedulerService::in_memory_database().await?);
+        let target =
+            TriggerStore::new(SchedulerService::in_memory_database().await?);
+
+        let project_in_shard = |shard: u64| {
+            ValidShardedId::<ProjectId>::from_string_unsafe(format!(
+                "prj_{}{}",
+                Shard(shard).encoded(),
+                ulid::Ulid::new()
+            ))
+        };
+        let project1 = project_in_shard(7);
+        let project2 = project_in_shard(7);
+        let project3 = project_in_shard(8);
+
+        let t1 = build_trigger("t1", project1.clone(), Status::Scheduled);
+        let t2 = build_trigger("t2", project2.clone(), Status::Expired);
+        let t3 = build_trigger("t3", project3.clone(), Status::Scheduled);
+        source.install_trigger(t1.clone()).await?;
+        source.install_trigger(t2.clone()).await?;
+        source.install_trigger(t3.clone()).await?;
+        let rev1 = source.add_revision(&t1, None).await?;
+        let rev3 = source.add_revision(&t3, None).await?;
+
+        let shards = HashSet::from([Shard(7)]);
+        let mut moved = source.get_triggers_by_shards(&shards).await?;
+        moved.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut expected = vec![t1.clone(), t2.clone()];
+        expected.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(moved, expected);
+
+        // Importing is idempotent and keeps the trigger as is.
+        for trigger in &moved {
+            target.import_trigger(trigger.clone()).await?;
+            target.import_trigger(trigger.clone()).await?;
+        }
+        let mut imported = target.get_triggers_by_shards(&shards).await?;
+        imported.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(imported, expected);
+
+        // Revisions are handed over along with their triggers.
+        // Timestamps lose their nanoseconds in the database, so revisions are
+        // compared by their trigger and number.
+        let key = |r: &TriggerRevision| (r.trigger_id.clone(), r.revision);
+        let moved_revisions = source.get_revisions_by_shards(&shards).await?;
+        assert_eq!(
+            moved_revisions.iter().map(key).collect::<Vec<_>>(),
+            vec![key(&rev1)]
+        );
+        for revision in &moved_revisions {
+            target.import_revision(revision.clone()).await?;
+            target.import_revision(revision.clone()).await?;
+        }
+        assert_eq!(
+            revisions_of(&target, &project1, &t1.id).await?,
+            moved_revisions
+        );
+
+        source.delete_shards(&shards).await?;
+        assert!(source.get_triggers_by_shards(&shards).await?.is_empty());
+        assert!(source.get_revisions_by_shards(&shards).await?.is_empty());
+        assert_eq!(
+            source.get_trigger_by_name(&project3, &t3.name).await?,
+            Some(t3.clone())
+        );
+        assert_eq!(
+            revisions_of(&source, &project3, &t3.id)
+                .await?
+                .iter()
+                .map(key)
+                .collect::<Vec<_>>(),
+            vec![key(&rev3)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_trigger_store_retired() -> anyhow::Result<()> {
+        let db = SchedulerService::in_memory_database().await?;
+        let store = TriggerStore::new(db);
+
+        let project1 = ProjectId::generate();
+        let project2 = ProjectId::generate();
+
+        let t1 = build_trigger("t1", project1.clone(), Status::Scheduled);
+        let mut t2 = build_trigger("t2", project1.clone(), Status::Cancelled);
+        t2.retired_at = Some(Utc::now());
+        let t3 = build_trigger("t3", project2.clone(), Status::Expired);
+        let t4 = build_trigger("t4", project2.clone(), Status::OnDemand);
+        for t in [&t1, &t2, &t3, &t4] {
+            store.install_trigger(t.clone()).await?;
+        }
+
+        let mut retired = store.get_retired_triggers(None, 10).await?;
+        retired.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(retired, vec![t2.clone(), t3.clone()]);
+
+        // Batches pick up where the previous one stopped.
+        let first = store.get_retired_triggers(None, 1).await?;
+        assert_eq!(first.len(), 1);
+        let second = store.get_retired_triggers(Some(&first[0].id), 1).await?;
+        assert_eq!(second.len(), 1);
+        assert_ne!(first[0].id, second[0].id);
+        assert!(store
+            .get_retired_triggers(Some(&second[0].id), 1)
+            .await?
+            .is_empty());
+
+        // Triggers retired before `retired_at` existed fall back to their
+        // creation time.
+        assert_eq!(retired[0].retired_since(), t2.retired_at);
+        assert_eq!(retired[1].retired_since(), Some(t3.created_at));
+        assert_eq!(t1.retired_since(), None);
+
+        Ok(())
+    }
 }
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
 use cronback_services::scheduler::{test_helpers, SchedulerService};
 use dto::traits::ProstOptionExt;
 use lib::clients::ScopedSchedulerSvcClient;
@@ -8,20 +12,34 @@ use proto::common::{
     action,
     Action,
     HttpMethod,
+    PaginationIn,
     Payload,
     UpsertEffect,
     Webhook,
 };
 use proto::scheduler_svc::{
+    BulkAction,
+    BulkUpdateTriggersRequest,
+    BulkUpdateTriggersResponse,
     DeleteProjectTriggersRequest,
     GetTriggerRequest,
     GetTriggerResponse,
+    ListTriggerRevisionsRequest,
     ListTriggersRequest,
     ListTriggersResponse,
+    RestoreTriggerRevisionRequest,
+    UpdateTimepointsRequest,
     UpsertTriggerRequest,
     UpsertTriggerResponse,
 };
-use proto::triggers::{schedule, Recurring, Schedule, Trigger, TriggerStatus};
+use proto::triggers::{
+    schedule,
+    Recurring,
+    RunAt,
+    Schedule,
+    Trigger,
+    TriggerStatus,
+};
 use tonic::Request;
 use tracing::info;
 use tracing_test::traced_test;
@@ -57,6 +75,7 @@ fn make_trigger(
                 http_method: HttpMethod::Get.into(),
                 timeout_s: 30.0,
                 retry: None,
+                success_criteria: None,
             })),
         }),
         status: Default::default(),
@@ -73,6 +92,7 @@ async fn install_trigger(
     let upsert = UpsertTriggerRequest {
         precondition: None,
         trigger_name: None,
+        author: None,
         trigger: Some(trigger),
     };
     client_provider
@@ -100,6 +120,21 @@ async fn list_triggers(
         .into_inner()
 }
 
+async fn bulk_update_triggers(
+    client_provider: &TestGrpcClientProvider<ScopedSchedulerSvcClient>,
+    project: &ValidShardedId<ProjectId>,
+    request: BulkUpdateTriggersRequest,
+) -> BulkUpdateTriggersResponse {
+    client_provider
+        .get_client(&RequestId::new(), project)
+        .await
+        .unwrap()
+        .bulk_update_triggers(Request::new(request))
+        .await
+        .unwrap()
+        .into_inner()
+}
+
 async fn get_trigger(
     client_provider: &TestGrpcClientProvider<ScopedSchedulerSvcClient>,
     project: &ValidShardedId<ProjectId>,
@@ -135,6 +170,7 @@ async fn install_trigger_valid_test() {
     let install_trigger = UpsertTriggerRequest {
         precondition: None,
         trigger_name: None,
+        author: None,
         trigger: Some(Trigger {
             payload: Some(Payload {
                 body: "Hello World".into(),
@@ -147,6 +183,7 @@ async fn install_trigger_valid_test() {
                     http_method: HttpMethod::Get.into(),
                     timeout_s: 30.0,
                     retry: None,
+                    success_criteria: None,
                 })),
             }),
             status: Default::default(),
@@ -193,8 +230,6 @@ async fn install_trigger_valid_test() {
 
 #[traced_test]
 #[tokio::test]
-// TODO: Remove when update is fixed
-#[ignore]
 async fn install_trigger_uniqueness_test() {
     let shutdown = Shutdown::default();
     let config = ConfigBuilder::default()
@@ -209,6 +244,7 @@ async fn install_trigger_uniqueness_test() {
         // No precondition
         precondition: None,
         trigger_name: None,
+        author: None,
         trigger: Some(Trigger {
             payload: Some(Payload {
                 body: "Hello World".into(),
@@ -221,6 +257,7 @@ async fn install_trigger_uniqueness_test() {
                     http_method: HttpMethod::Get.into(),
                     timeout_s: 30.0,
                     retry: None,
+                    success_criteria: None,
                 })),
             }),
             status: Default::default(),
@@ -259,6 +296,7 @@ async fn install_trigger_uniqueness_test() {
         // We rely on the name to match the trigger.
         precondition: None,
         trigger_name: Some("sample-trigger-2".to_string()),
+        author: None,
         trigger: Some(Trigger {
             payload: Some(Payload {
                 body: "Hello World".into(),
@@ -272,6 +310,7 @@ async fn install_trigger_uniqueness_test() {
                     http_method: HttpMethod::Get.into(),
                     timeout_s: 30.0,
                     retry: None,
+                    success_criteria: None,
                 })),
             }),
             status: Default::default(),
@@ -311,6 +350,7 @@ async fn install_trigger_uniqueness_test() {
         // We rely on the name to match the trigger.
         precondition: None,
         trigger_name: Some("sample-trigger-2".to_string()),
+        author: None,
         trigger: Some(Trigger {
             payload: Some(Payload {
                 body: "Hello World".into(),
@@ -325,6 +365,7 @@ async fn install_trigger_uniqueness_test() {
                     http_method: HttpMethod::Get.into(),
                     timeout_s: 30.0,
                     retry: None,
+                    success_criteria: None,
                 })),
             }),
             status: Default::default(),
@@ -352,6 +393,132 @@ async fn install_trigger_uniqueness_test() {
     assert_eq!(TriggerStatus::OnDemand, updated_trigger.status());
 }
 
+#[traced_test]
+#[tokio::test]
+async fn trigger_revisions_test() {
+    let shutdown = Shutdown::default();
+    let config = ConfigBuilder::default()
+        .register_service::<SchedulerService>()
+        .build_once()
+        .unwrap();
+    let context = SchedulerService::make_context(config, shutdown);
+    let project = ProjectId::generate();
+    let (_serve_future, client_provider) =
+        test_helpers::test_server_and_client(context).await;
+    let mut client = client_provider
+        .get_client(&RequestId::new(), &project)
+        .await
+        .unwrap();
+
+    install_trigger(
+        &client_provider,
+        &project,
+        make_trigger("trigger-1", true, "first"),
+    )
+    .await;
+    // Update the trigger.
+    let updated = client
+        .upsert_trigger(Request::new(UpsertTriggerRequest {
+            precondition: None,
+            trigger_name: Some("trigger-1".to_string()),
+            author: Some("key1".to_string()),
+            trigger: Some(make_trigger("trigger-1", false, "second")),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(UpsertEffect::Modified, updated.effect());
+    assert_eq!(TriggerStatus::OnDemand, updated.trigger.unwrap().status());
+
+    let revisions = client
+        .list_trigger_revisions(Request::new(ListTriggerRevisionsRequest {
+            name: "trigger-1".to_string(),
+            pagination: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .revisions;
+    assert_eq!(
+        revisions.iter().map(|r| r.revision).collect::<Vec<_>>(),
+        vec![2, 1]
+    );
+    assert_eq!(revisions[0].author.as_deref(), Some("key1"));
+    assert_eq!(revisions[1].author, None);
+    assert_eq!(
+        revisions[1].trigger.unwrap_ref().description.as_deref(),
+        Some("first")
+    );
+
+    // Restoring brings back the first definition as a new revision.
+    let restored = client
+        .restore_trigger_revision(Request::new(RestoreTriggerRevisionRequest {
+            name: "trigger-1".to_string(),
+            revision: 1,
+            author: Some("key2".to_string()),
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .trigger
+        .unwrap();
+    assert_eq!("trigger-1", restored.name);
+    assert_eq!(Some("first".to_owned()), restored.description);
+    assert_eq!(TriggerStatus::Scheduled, restored.status());
+    assert!(restored.updated_at.is_some());
+
+    let page = client
+        .list_trigger_revisions(Request::new(ListTriggerRevisionsRequest {
+            name: "trigger-1".to_string(),
+            pagination: Some(PaginationIn {
+                limit: 2,
+                cursor: None,
+            }),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        page.revisions
+            .iter()
+            .map(|r| r.revision)
+            .collect::<Vec<_>>(),
+        vec![3, 2]
+    );
+    assert_eq!(page.revisions[0].author.as_deref(), Some("key2"));
+    let pagination = page.pagination.unwrap();
+    assert!(pagination.has_more);
+    let page = client
+        .list_trigger_revisions(Request::new(ListTriggerRevisionsRequest {
+            name: "trigger-1".to_string(),
+            pagination: Some(PaginationIn {
+                limit: 2,
+                cursor: pagination.next_cursor,
+            }),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        page.revisions
+            .iter()
+            .map(|r| r.revision)
+            .collect::<Vec<_>>(),
+        vec![1]
+    );
+    assert!(!page.pagination.unwrap().has_more);
+
+    let missing = client
+        .restore_trigger_revision(Request::new(RestoreTriggerRevisionRequest {
+            name: "trigger-1".to_string(),
+            revision: 10,
+            author: None,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+}
+
 #[traced_test]
 #[tokio::test]
 async fn delete_project_triggers_test() {
@@ -479,3 +646,222 @@ async fn delete_project_triggers_test() {
         assert_eq!("project2", get_resp.trigger.unwrap().description());
     }
 }
+
+#[traced_test]
+#[tokio::test]
+async fn bulk_update_triggers_test() {
+    let shutdown = Shutdown::default();
+    let config = ConfigBuilder::default()
+        .register_service::<SchedulerService>()
+        .build_once()
+        .unwrap();
+    let context = SchedulerService::make_context(config, shutdown);
+    let project = ProjectId::generate();
+
+    info!("Initialising test server...");
+    let (_serve_future, client_provider) =
+        test_helpers::test_server_and_client(context).await;
+
+    for (name, scheduled) in [
+        ("billing-daily", true),
+        ("billing-weekly", true),
+        ("billing-manual", false),
+        ("search-daily", true),
+    ] {
+        let install_resp = install_trigger(
+            &client_provider,
+            &project,
+            make_trigger(name, scheduled, "bulk"),
+        )
+        .await;
+        assert_eq!(UpsertEffect::Created, install_resp.effect());
+    }
+    let status_of = |resp: &BulkUpdateTriggersResponse, name: &str| {
+        resp.results
+            .iter()
+            .find(|r| r.name == name)
+            .map(|r| (r.status, r.error.is_some()))
+    };
+
+    // A dry run reports the outcome without pausing anything.
+    let resp = bulk_update_triggers(
+        &client_provider,
+        &project,
+        BulkUpdateTriggersRequest {
+            action: BulkAction::Pause.into(),
+            name_prefix: Some("billing-".to_owned()),
+            dry_run: true,
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(3, resp.results.len());
+    assert_eq!(
+        Some((Some(TriggerStatus::Paused.into()), false)),
+        status_of(&resp, "billing-daily")
+    );
+    // On-demand triggers can't be paused.
+    assert_eq!(Some((None, true)), status_of(&resp, "billing-manual"));
+    let trigger = get_trigger(&client_provider, &project, "billing-daily")
+        .await
+        .unwrap()
+        .trigger
+        .unwrap();
+    assert_eq!(TriggerStatus::Scheduled, trigger.status());
+
+    // Pause the scheduled billing triggers for real.
+    let resp = bulk_update_triggers(
+        &client_provider,
+        &project,
+        BulkUpdateTriggersRequest {
+            action: BulkAction::Pause.into(),
+            statuses: vec![TriggerStatus::Scheduled.into()],
+            name_prefix: Some("billing-".to_owned()),
+            dry_run: false,
+        },
+    )
+    .await;
+    assert_eq!(2, resp.results.len());
+    for name in ["billing-daily", "billing-weekly"] {
+        let trigger = get_trigger(&client_provider, &project, name)
+            .await
+            .unwrap()
+            .trigger
+            .unwrap();
+        assert_eq!(TriggerStatus::Paused, trigger.status());
+    }
+    let trigger = get_trigger(&client_provider, &project, "search-daily")
+        .await
+        .unwrap()
+        .trigger
+        .unwrap();
+    assert_eq!(TriggerStatus::Scheduled, trigger.status());
+
+    // Cancelling works on both paused and on-demand triggers.
+    let resp = bulk_update_triggers(
+        &client_provider,
+        &project,
+        BulkUpdateTriggersRequest {
+            action: BulkAction::Cancel.into(),
+            name_prefix: Some("billing-".to_owned()),
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(3, resp.results.len());
+    assert!(resp
+        .results
+        .iter()
+        .all(|r| r.status == Some(TriggerStatus::Cancelled.into())));
+
+    // Delete the cancelled ones.
+    let resp = bulk_update_triggers(
+        &client_provider,
+        &project,
+        BulkUpdateTriggersRequest {
+            action: BulkAction::Delete.into(),
+            statuses: vec![TriggerStatus::Cancelled.into()],
+            ..Default::default()
+        },
+    )
+    .await;
+    assert_eq!(3, resp.results.len());
+    let list_resp = list_triggers(&client_provider, &project).await;
+    assert_eq!(1, list_resp.triggers.len());
+    assert_eq!("search-daily", list_resp.triggers[0].name);
+}
+
+#[traced_test]
+#[tokio::test]
+async fn update_timepoints_test() {
+    let shutdown = Shutdown::default();
+    let config = ConfigBuilder::default()
+        .register_service::<SchedulerService>()
+        .build_once()
+        .unwrap();
+    let start = Utc.with_ymd_and_hms(2023, 7, 1, 9, 0, 0).unwrap();
+    let clock = Arc::new(ManualClock::new(start));
+    let context = SchedulerService::make_context(config, shutdown)
+        .with_clock(clock.clone());
+    let project = ProjectId::generate();
+
+    info!("Initialising test server...");
+    let (_serve_future, client_provider) =
+        test_helpers::test_server_and_client(context).await;
+
+    let at = |m| start + chrono::Duration::minutes(m);
+    let mut trigger = make_trigger("reminders", false, "timepoints");
+    trigger.schedule = Some(Schedule {
+        schedule: Some(schedule::Schedule::RunAt(RunAt {
+            timepoints: vec![at(1).into()],
+            ..Default::default()
+        })),
+    });
+    install_trigger(&client_provider, &project, trigger).await;
+
+    let update = |add: Vec<i64>, remove: Vec<i64>| {
+        let (client_provider, project) = (&client_provider, &project);
+        async move {
+            client_provider
+                .get_client(&RequestId::new(), project)
+                .await
+                .unwrap()
+                .update_timepoints(Request::new(UpdateTimepointsRequest {
+                    name: "reminders".to_owned(),
+                    add: add.into_iter().map(|m| at(m).into()).collect(),
+                    remove: remove.into_iter().map(|m| at(m).into()).collect(),
+                }))
+                .await
+                .map(|r| r.into_inner().trigger.unwrap())
+        }
+    };
+
+    let trigger = update(vec![3, 2], vec![]).await.unwrap();
+    assert_eq!(
+        vec![at(1).into(), at(2).into(), at(3).into()],
+        trigger.estimated_future_runs
+    );
+
+    let trigger = update(vec![], vec![1, 3]).await.unwrap();
+    assert_eq!(vec![at(2).into()], trigger.estimated_future_runs);
+
+    // Timepoints can't be added in the past.
+    let err = update(vec![-1], vec![]).await.unwrap_err();
+    assert_eq!(tonic::Code::InvalidArgument, err.code());
+
+    // The trigger expires once the added timepoint passes.
+    clock.set(at(5));
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let trigger = get_trigger(&client_provider, &project, "reminders")
+        .await
+        .unwrap()
+        .trigger
+        .unwrap();
+    assert_eq!(TriggerStatus::Expired, trigger.status());
+
+    // Removing timepoints from an expired trigger doesn't make sense.
+    let err = update(vec![], vec![2]).await.unwrap_err();
+    assert_eq!(tonic::Code::FailedPrecondition, err.code());
+
+    // A rejected update leaves it expired.
+    let err = update((10..5012).collect(), vec![]).await.unwrap_err();
+    assert_eq!(tonic::Code::InvalidArgument, err.code());
+    let trigger = get_trigger(&client_provider, &project, "reminders")
+        .await
+        .unwrap()
+        .trigger
+        .unwrap();
+    assert_eq!(TriggerStatus::Expired, trigger.status());
+
+    // Adding timepoints schedules it again.
+    let trigger = update(vec![10], vec![]).await.unwrap();
+    assert_eq!(TriggerStatus::Scheduled, trigger.status());
+    assert_eq!(vec![at(10).into()], trigger.estimated_future_runs);
+    let trigger = get_trigger(&client_provider, &project, "reminders")
+        .await
+        .unwrap()
+        .trigger
+        .unwrap();
+    assert_eq!(TriggerStatus::Scheduled, trigger.status());
+    assert_eq!(vec![at(10).into()], trigger.estimated_future_runs);
+}